@@ -1,11 +1,13 @@
 pub mod visitor;
 
 use crate::reporting::TokenSpan;
+use crate::tir::PrimitiveType;
 
 pub struct ASTRoot<'a> {
     pub span: TokenSpan,
     pub mod_decl: ASTMod<'a>,
     pub use_decls: Vec<ASTUse<'a>>,
+    pub type_aliases: Vec<ASTTypeAlias<'a>>,
     pub types: Vec<ASTType<'a>>,
 }
 
@@ -14,18 +16,35 @@ pub struct ASTMod<'a> {
     pub path: ASTPath<'a>,
 }
 
+pub enum ASTUseKind<'a> {
+    // "use a::b::C;", or "use a::b::C as D;" with the alias
+    Single(Option<&'a str>),
+    // "use a::b::*;", the path is the one of the module
+    Glob,
+}
+
 pub struct ASTUse<'a> {
     pub span: TokenSpan,
     pub path: ASTPath<'a>,
+    pub kind: ASTUseKind<'a>,
+}
+
+// "type Name<T> = Type<T>;", aliases only exist in the file declaring them
+pub struct ASTTypeAlias<'a> {
+    pub span: TokenSpan,
+    pub name: &'a str,
+    pub generics: Vec<&'a str>,
+    pub type_info: ASTTypeInfo<'a>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ASTModifier {
     Static,
     Abstract,
     Native,
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum ASTVisibility {
     Public,
     Module,
@@ -38,6 +57,25 @@ pub enum ASTTypeKind<'a> {
         super_class: Option<ASTPartialTypeInfo<'a>>,
         impls: Vec<ASTPartialTypeInfo<'a>>,
     },
+    Interface {
+        members: Vec<ASTMember<'a>>,
+        super_interfaces: Vec<ASTPartialTypeInfo<'a>>,
+    },
+    Enum {
+        variants: Vec<ASTVariant<'a>>,
+    },
+}
+
+pub enum ASTVariantKind<'a> {
+    Unit,
+    Tuple(Vec<ASTTypeInfo<'a>>),
+    Struct(Vec<ASTNameAndType<'a>>),
+}
+
+pub struct ASTVariant<'a> {
+    pub span: TokenSpan,
+    pub name: &'a str,
+    pub kind: ASTVariantKind<'a>,
 }
 
 pub struct ASTType<'a> {
@@ -65,6 +103,12 @@ pub enum ASTMemberKind<'a> {
         parameters: Vec<ASTNameAndType<'a>>,
         block: Option<ASTStatementBlock<'a>>,
     },
+    // "pub Wrapper(inside: T) { }", the name has to be the one of the class
+    Constructor {
+        name: &'a str,
+        parameters: Vec<ASTNameAndType<'a>>,
+        block: ASTStatementBlock<'a>,
+    },
 }
 
 pub struct ASTMember<'a> {
@@ -102,16 +146,28 @@ pub struct ASTPath<'a> {
     pub elements: Vec<&'a str>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum ASTOperator {
     Plus,
     Minus,
     Mul,
     Div,
+    Mod,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
     PlusAssign,
     MinusAssign,
     MulAssign,
     DivAssign,
+    ModAssign,
+    BitAndAssign,
+    BitOrAssign,
+    BitXorAssign,
+    ShlAssign,
+    ShrAssign,
     Assign,
     And,
     Or,
@@ -125,6 +181,7 @@ pub enum ASTOperator {
     Inc,
     Dec,
     Not,
+    BitNot,
 }
 
 pub enum ASTStatementKind<'a> {
@@ -146,11 +203,15 @@ pub struct ASTStatementBlock<'a> {
 pub enum ASTExprKind<'a> {
     Path(ASTPath<'a>),
     Ident(&'a str),
-    StringLiteral(&'a str),
-    Num(&'a str),
-    Float(&'a str),
+    StringLiteral(String),
+    CharLiteral(char),
+    Num(u64, Option<PrimitiveType>),
+    Float(f64, Option<PrimitiveType>),
     Boolean(bool),
     Null,
+    This,
+    // "super" on its own, to access the members of the super class
+    Super,
 
     BinOp(Box<ASTExpr<'a>>, ASTOperator, Box<ASTExpr<'a>>),
     PreOp(ASTOperator, Box<ASTExpr<'a>>),
@@ -160,6 +221,14 @@ pub enum ASTExprKind<'a> {
     StaticAccess(Box<ASTExpr<'a>>, &'a str),
     Call(Box<ASTExpr<'a>>, Vec<ASTExpr<'a>>),
     Indexing(Box<ASTExpr<'a>>, Box<ASTExpr<'a>>),
+    // "Event::Chat { from: a, text: b }"
+    StructLiteral(Box<ASTExpr<'a>>, Vec<(&'a str, ASTExpr<'a>)>),
+    // Start, end and whether the end is included ("0..=n")
+    Range(Box<ASTExpr<'a>>, Box<ASTExpr<'a>>, bool),
+    // "new Wrapper<u64>(10)"
+    New(ASTTypeInfo<'a>, Vec<ASTExpr<'a>>),
+    // "super(a, b)", only allowed as the first statement of a constructor
+    SuperCall(Vec<ASTExpr<'a>>),
 
     Block(ASTStatementBlock<'a>),
     IfElse(
@@ -168,10 +237,16 @@ pub enum ASTExprKind<'a> {
         ASTStatementBlock<'a>,
     ),
     If(Box<ASTExpr<'a>>, ASTStatementBlock<'a>),
-    Loop(ASTStatementBlock<'a>),
-    While(Box<ASTExpr<'a>>, ASTStatementBlock<'a>),
-    Match(/* TODO */),
-    For(/* TODO */),
+    // Loops start with their optional label ("'outer: loop { }")
+    Loop(Option<&'a str>, ASTStatementBlock<'a>),
+    While(Option<&'a str>, Box<ASTExpr<'a>>, ASTStatementBlock<'a>),
+    Match(Box<ASTExpr<'a>>, Vec<ASTMatchArm<'a>>),
+    // "for name in iterable { }"
+    For(Option<&'a str>, &'a str, Box<ASTExpr<'a>>, ASTStatementBlock<'a>),
+    // "break 'outer value", both are optional
+    Break(Option<&'a str>, Option<Box<ASTExpr<'a>>>),
+    Continue(Option<&'a str>),
+    Return(Option<Box<ASTExpr<'a>>>),
 }
 
 pub struct ASTExpr<'a> {
@@ -179,6 +254,38 @@ pub struct ASTExpr<'a> {
     pub span: TokenSpan,
 }
 
+pub enum ASTPatternKind<'a> {
+    // "_"
+    Wildcard,
+    Binding(&'a str),
+    // Literal expression, possibly negated
+    Literal(Box<ASTExpr<'a>>),
+    // Start, end and whether the end is included ("1..=5")
+    Range(Box<ASTExpr<'a>>, Box<ASTExpr<'a>>, bool),
+    // "E::A"
+    UnitVariant(ASTPath<'a>),
+    // "E::B(x, _)"
+    TupleVariant(ASTPath<'a>, Vec<ASTPattern<'a>>),
+    // "E::C { x, y: 1, .. }", the boolean is set when there is a ".."
+    StructVariant(ASTPath<'a>, Vec<(&'a str, ASTPattern<'a>)>, bool),
+    // "x: Type" or "_: Type"
+    TypeTest(Option<&'a str>, ASTTypeInfo<'a>),
+    // "A | B"
+    Or(Vec<ASTPattern<'a>>),
+}
+
+pub struct ASTPattern<'a> {
+    pub kind: ASTPatternKind<'a>,
+    pub span: TokenSpan,
+}
+
+pub struct ASTMatchArm<'a> {
+    pub span: TokenSpan,
+    pub pattern: ASTPattern<'a>,
+    pub guard: Option<ASTExpr<'a>>,
+    pub body: ASTExpr<'a>,
+}
+
 impl<'a> ASTPartialTypeInfo<'a> {
     pub fn into_type_info(&self) -> ASTTypeInfo<'a> {
         ASTTypeInfo {
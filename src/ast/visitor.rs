@@ -7,6 +7,10 @@ pub trait ASTVisitor {
         for use_decl in &obj.use_decls {
             self.walk_use(use_decl);
         }
+
+        for type_alias in &obj.type_aliases {
+            self.walk_type_alias(type_alias);
+        }
     }
 
     fn walk_mod(&mut self, obj: &ASTMod) {
@@ -17,6 +21,10 @@ pub trait ASTVisitor {
         self.walk_path(&obj.path);
     }
 
+    fn walk_type_alias(&mut self, obj: &ASTTypeAlias) {
+        self.walk_type_info(&obj.type_info);
+    }
+
     fn walk_modifier(&mut self, _obj: &ASTModifier) {}
 
     fn walk_visibility(&mut self, _obj: &ASTVisibility) {}
@@ -38,6 +46,22 @@ pub trait ASTVisitor {
                     self.walk_partial_type_info(super_class);
                 }
             }
+            ASTTypeKind::Interface {
+                members,
+                super_interfaces,
+            } => {
+                for member in members {
+                    self.walk_member(member);
+                }
+                for super_interface in super_interfaces {
+                    self.walk_partial_type_info(super_interface);
+                }
+            }
+            ASTTypeKind::Enum { variants } => {
+                for variant in variants {
+                    self.walk_variant(variant);
+                }
+            }
         }
 
         for modifier in &obj.modifiers {
@@ -55,6 +79,22 @@ pub trait ASTVisitor {
         }
     }
 
+    fn walk_variant(&mut self, obj: &ASTVariant) {
+        match &obj.kind {
+            ASTVariantKind::Unit => {}
+            ASTVariantKind::Tuple(fields) => {
+                for field in fields {
+                    self.walk_type_info(field);
+                }
+            }
+            ASTVariantKind::Struct(fields) => {
+                for field in fields {
+                    self.walk_name_and_type(field);
+                }
+            }
+        }
+    }
+
     fn walk_member(&mut self, obj: &ASTMember) {
         match &obj.kind {
             ASTMemberKind::Field {
@@ -79,6 +119,14 @@ pub trait ASTVisitor {
                     self.walk_name_and_type(parameter);
                 }
             }
+            ASTMemberKind::Constructor {
+                parameters, block, ..
+            } => {
+                for parameter in parameters {
+                    self.walk_name_and_type(parameter);
+                }
+                self.walk_statement_block(block);
+            }
         }
 
         for modifier in &obj.modifiers {
@@ -129,15 +177,63 @@ pub trait ASTVisitor {
         }
     }
 
+    fn walk_match_arm(&mut self, obj: &ASTMatchArm) {
+        self.walk_pattern(&obj.pattern);
+        if let Some(guard) = &obj.guard {
+            self.walk_expr(guard);
+        }
+        self.walk_expr(&obj.body);
+    }
+
+    fn walk_pattern(&mut self, obj: &ASTPattern) {
+        match &obj.kind {
+            ASTPatternKind::Wildcard => {}
+            ASTPatternKind::Binding(_) => {}
+            ASTPatternKind::Literal(literal) => {
+                self.walk_expr(literal);
+            }
+            ASTPatternKind::Range(start, end, _) => {
+                self.walk_expr(start);
+                self.walk_expr(end);
+            }
+            ASTPatternKind::UnitVariant(path) => {
+                self.walk_path(path);
+            }
+            ASTPatternKind::TupleVariant(path, fields) => {
+                self.walk_path(path);
+                for field in fields {
+                    self.walk_pattern(field);
+                }
+            }
+            ASTPatternKind::StructVariant(path, fields, _) => {
+                self.walk_path(path);
+                for (_, field) in fields {
+                    self.walk_pattern(field);
+                }
+            }
+            ASTPatternKind::TypeTest(_, type_info) => {
+                self.walk_type_info(type_info);
+            }
+            ASTPatternKind::Or(alternatives) => {
+                for alternative in alternatives {
+                    self.walk_pattern(alternative);
+                }
+            }
+        }
+    }
+
     fn walk_expr(&mut self, obj: &ASTExpr) {
         match &obj.kind {
             ASTExprKind::Path(_) => {}
             ASTExprKind::Ident(_) => {}
             ASTExprKind::StringLiteral(_) => {}
-            ASTExprKind::Num(_) => {}
-            ASTExprKind::Float(_) => {}
+            ASTExprKind::CharLiteral(_) => {}
+            ASTExprKind::Num(_, _) => {}
+            ASTExprKind::Float(_, _) => {}
             ASTExprKind::Boolean(_) => {}
             ASTExprKind::Null => {}
+            ASTExprKind::This => {}
+            ASTExprKind::Super => {}
             ASTExprKind::BinOp(left, _, right) => {
                 self.walk_expr(left);
                 self.walk_expr(right);
@@ -164,6 +260,27 @@ pub trait ASTVisitor {
                 self.walk_expr(expr);
                 self.walk_expr(index);
             }
+            ASTExprKind::StructLiteral(expr, fields) => {
+                self.walk_expr(expr);
+                for (_, field) in fields {
+                    self.walk_expr(field);
+                }
+            }
+            ASTExprKind::Range(start, end, _) => {
+                self.walk_expr(start);
+                self.walk_expr(end);
+            }
+            ASTExprKind::New(type_info, args) => {
+                self.walk_type_info(type_info);
+                for arg in args {
+                    self.walk_expr(arg);
+                }
+            }
+            ASTExprKind::SuperCall(args) => {
+                for arg in args {
+                    self.walk_expr(arg);
+                }
+            }
             ASTExprKind::Block(block) => {
                 self.walk_statement_block(block);
             }
@@ -172,17 +289,36 @@ pub trait ASTVisitor {
                 self.walk_statement_block(block_if);
                 self.walk_statement_block(block_else);
             }
-            ASTExprKind::Loop(block) => {
+            ASTExprKind::Loop(_, block) => {
                 self.walk_statement_block(block);
             }
-            ASTExprKind::Match() => { /* TODO*/ }
+            ASTExprKind::Match(scrutinee, arms) => {
+                self.walk_expr(scrutinee);
+                for arm in arms {
+                    self.walk_match_arm(arm);
+                }
+            }
             ASTExprKind::If(_, block) => {
                 self.walk_statement_block(block);
             }
-            ASTExprKind::While(_, block) => {
+            ASTExprKind::While(_, _, block) => {
+                self.walk_statement_block(block);
+            }
+            ASTExprKind::For(_, _, iterable, block) => {
+                self.walk_expr(iterable);
                 self.walk_statement_block(block);
             }
-            ASTExprKind::For() => {}
+            ASTExprKind::Break(_, value) => {
+                if let Some(value) = value {
+                    self.walk_expr(value);
+                }
+            }
+            ASTExprKind::Continue(_) => {}
+            ASTExprKind::Return(value) => {
+                if let Some(value) = value {
+                    self.walk_expr(value);
+                }
+            }
         }
     }
 }
@@ -0,0 +1,128 @@
+use crate::codegen::{Codegen, CodegenError};
+use crate::codegen::context::CodegenContext;
+use crate::reporting::TokenSpan;
+use crate::tir::{TIRExpr, TIRExprKind, TIRTypeInfo, TIROperator, PrimitiveType};
+use oolang_bytecode::BytecodeFormat;
+use oolang_bytecode::instruction_structure::{InstructionFormat, ConstantFormat};
+
+// Something that can be assigned to. What the field or element belongs to is evaluated once and kept in temporary slots,
+// compound assignments and "++" / "--" read the place before writing to it.
+enum Place {
+    Local(u64),
+    // Slot of the object and field ref index
+    Field(u64, usize),
+    StaticField(usize),
+    // Slots of the array and of the index
+    Element(u64, u64)
+}
+
+impl<'a> Codegen<'a> {
+    fn codegen_place(&self, target: &TIRExpr<'a>, bytecode: &mut Vec<u8>, codegen_context: &mut CodegenContext<'a>) -> Result<Place, CodegenError> {
+        let mut codegen_temporary = |expr: &TIRExpr<'a>, bytecode: &mut Vec<u8>| -> Result<u64, CodegenError> {
+            self.codegen_tir_expr(expr, bytecode, codegen_context)?;
+            let slot = codegen_context.allocate_local();
+            InstructionFormat::StoreLocal { slot }.write(bytecode);
+            Ok(slot)
+        };
+        // The type checker only accepts these as assignment targets
+        Ok(match &target.kind {
+            TIRExprKind::LocalAccess(slot) => Place::Local(*slot as u64),
+            TIRExprKind::FieldAccess(on, field_ref_index) => Place::Field(codegen_temporary(on, bytecode)?, *field_ref_index),
+            TIRExprKind::StaticFieldAccess(field_ref_index) => Place::StaticField(*field_ref_index),
+            TIRExprKind::Indexing(array, index) => {
+                let array_slot = codegen_temporary(array, bytecode)?;
+                Place::Element(array_slot, codegen_temporary(index, bytecode)?)
+            }
+            _ => unreachable!()
+        })
+    }
+
+    // Pushes what the store instruction of the place takes before the value
+    fn codegen_place_prefix(&self, place: &Place, bytecode: &mut Vec<u8>) {
+        match place {
+            Place::Local(_) | Place::StaticField(_) => {}
+            Place::Field(object_slot, _) => InstructionFormat::LoadLocal { slot: *object_slot }.write(bytecode),
+            Place::Element(array_slot, index_slot) => {
+                InstructionFormat::LoadLocal { slot: *array_slot }.write(bytecode);
+                InstructionFormat::LoadLocal { slot: *index_slot }.write(bytecode);
+            }
+        }
+    }
+
+    // Type the value of the place is stored as, which is not the one it is used as for fields declared as generics
+    fn place_declared_type(&self, place: &Place, target_type: &TIRTypeInfo) -> TIRTypeInfo {
+        match place {
+            Place::Field(_, field_ref_index) | Place::StaticField(field_ref_index) => self.field_ref_pool.field_refs[*field_ref_index].type_info.clone(),
+            Place::Local(_) | Place::Element(_, _) => target_type.clone()
+        }
+    }
+
+    fn codegen_place_load(&self, place: &Place, target_type: &TIRTypeInfo, bytecode: &mut Vec<u8>) {
+        self.codegen_place_prefix(place, bytecode);
+        match place {
+            Place::Local(slot) => InstructionFormat::LoadLocal { slot: *slot },
+            Place::Field(_, field_ref_index) => InstructionFormat::GetField { field_ref_index: *field_ref_index as u64 },
+            Place::StaticField(field_ref_index) => InstructionFormat::GetStaticField { field_ref_index: *field_ref_index as u64 },
+            Place::Element(_, _) => InstructionFormat::ArrayLoad
+        }.write(bytecode);
+        self.codegen_unbox(&self.place_declared_type(place, target_type), target_type.clone(), bytecode);
+    }
+
+    // Stores the value of the given type that was pushed after the prefix of the place
+    fn codegen_place_store(&self, place: &Place, target_type: &TIRTypeInfo, value_type: &TIRTypeInfo, bytecode: &mut Vec<u8>) {
+        self.codegen_conversion(&self.place_declared_type(place, target_type), target_type, value_type, bytecode);
+        match place {
+            Place::Local(slot) => InstructionFormat::StoreLocal { slot: *slot },
+            Place::Field(_, field_ref_index) => InstructionFormat::SetField { field_ref_index: *field_ref_index as u64 },
+            Place::StaticField(field_ref_index) => InstructionFormat::SetStaticField { field_ref_index: *field_ref_index as u64 },
+            Place::Element(_, _) => InstructionFormat::ArrayStore
+        }.write(bytecode);
+    }
+
+    // "=" and the compound assignments, which evaluate to nothing
+    pub fn codegen_assignment(&self, target: &TIRExpr<'a>, operator: &TIROperator, value: &TIRExpr<'a>, span: TokenSpan, bytecode: &mut Vec<u8>, codegen_context: &mut CodegenContext<'a>) -> Result<TIRTypeInfo, CodegenError> {
+        let target_type = self.checked_type(target);
+        let place = self.codegen_place(target, bytecode, codegen_context)?;
+        self.codegen_place_prefix(&place, bytecode);
+        let value_type = match operator.compound_operator() {
+            Some(compound_operator) => {
+                self.codegen_place_load(&place, &target_type, bytecode);
+                let right_type = self.codegen_tir_expr(value, bytecode, codegen_context)?;
+                self.codegen_binary_operator(&compound_operator, &target_type, &right_type, span, bytecode)?
+            }
+            None => self.codegen_tir_expr(value, bytecode, codegen_context)?
+        };
+        self.codegen_place_store(&place, &target_type, &value_type, bytecode);
+        Ok(self.primitive_type_info(PrimitiveType::Void, span))
+    }
+
+    // "++" and "--", evaluating to the value after the step when prefixed and the one before when postfixed
+    pub fn codegen_step(&self, target: &TIRExpr<'a>, operator: &TIROperator, prefixed: bool, span: TokenSpan, bytecode: &mut Vec<u8>, codegen_context: &mut CodegenContext<'a>) -> Result<TIRTypeInfo, CodegenError> {
+        let target_type = self.checked_type(target);
+        let primitive = self.operand_primitive(&target_type).ok_or(CodegenError::InvalidOperands(span, operator.clone()))?;
+        let step_operator = match operator {
+            TIROperator::Inc => TIROperator::Plus,
+            _ => TIROperator::Minus
+        };
+        let place = self.codegen_place(target, bytecode, codegen_context)?;
+
+        self.codegen_place_load(&place, &target_type, bytecode);
+        let old_value_slot = codegen_context.allocate_local();
+        if !prefixed {
+            InstructionFormat::StoreLocal { slot: old_value_slot }.write(bytecode);
+            InstructionFormat::LoadLocal { slot: old_value_slot }.write(bytecode);
+        }
+        InstructionFormat::Constant(ConstantFormat::Integer(1, self.primitive_to_type_info_kind_format(primitive))).write(bytecode);
+        self.codegen_binary_operator(&step_operator, &target_type, &target_type, span, bytecode)?;
+
+        // The prefix of the place goes below the new value
+        let new_value_slot = codegen_context.allocate_local();
+        InstructionFormat::StoreLocal { slot: new_value_slot }.write(bytecode);
+        self.codegen_place_prefix(&place, bytecode);
+        InstructionFormat::LoadLocal { slot: new_value_slot }.write(bytecode);
+        self.codegen_place_store(&place, &target_type, &target_type, bytecode);
+
+        InstructionFormat::LoadLocal { slot: if prefixed { new_value_slot } else { old_value_slot } }.write(bytecode);
+        Ok(target_type)
+    }
+}
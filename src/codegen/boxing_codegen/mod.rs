@@ -0,0 +1,117 @@
+use crate::codegen::{Codegen, CodegenError};
+use crate::codegen::context::CodegenContext;
+use crate::tir::{TIRExpr, TIRTypeInfo, TIRTypeInfoKind, PrimitiveType};
+use oolang_bytecode::BytecodeFormat;
+use oolang_bytecode::instruction_structure::InstructionFormat;
+
+// Generics only hold references at runtime. Primitives are boxed into their standard library class (see PrimitiveType::boxed_name)
+// when they are given where a generic is declared, and unboxed when they come out of one.
+// Arguments of a smaller numeric type than their parameter are converted to it (see PrimitiveType::widens_to).
+
+impl<'a> Codegen<'a> {
+    // Primitive to box or unbox when a value of the type it is used as goes through the declared type
+    fn boxed_primitive(&self, declared: &TIRTypeInfo, used_as: &TIRTypeInfo) -> Option<PrimitiveType> {
+        match (&declared.kind, &used_as.kind) {
+            (TIRTypeInfoKind::Generic { array_dim: 0, .. }, TIRTypeInfoKind::Primitive { primitive, array_dim: 0 }) =>
+                Some(*primitive).filter(|primitive| primitive.boxed_name().is_some()),
+            _ => None
+        }
+    }
+
+    // Type a member declared in a generic type is used as on the given type, the generics are the ones of the owner
+    // Values of members whose generics aren't known are used as their own type
+    pub fn member_used_as(&self, declared: &TIRTypeInfo, owner: Option<&TIRTypeInfo>, actual: &TIRTypeInfo) -> TIRTypeInfo {
+        match (&declared.kind, owner.map(|owner| &owner.kind)) {
+            (TIRTypeInfoKind::Generic { .. }, Some(TIRTypeInfoKind::TypeRef { type_ref_index, generics, .. })) if !generics.is_empty() =>
+                self.type_ref_pool.substitute_generics(declared, *type_ref_index, generics),
+            (TIRTypeInfoKind::Generic { .. }, _) => actual.clone(),
+            _ => declared.clone()
+        }
+    }
+
+    // Widens a value that was just pushed to the type it is used as, then boxes it if it is stored as a generic
+    pub fn codegen_conversion(&self, declared: &TIRTypeInfo, used_as: &TIRTypeInfo, actual: &TIRTypeInfo, bytecode: &mut Vec<u8>) {
+        match (self.operand_primitive(actual), self.operand_primitive(used_as)) {
+            (Some(from), Some(to)) if from.widens_to(to) => InstructionFormat::Convert {
+                from: self.primitive_to_type_info_kind_format(from),
+                to: self.primitive_to_type_info_kind_format(to)
+            }.write(bytecode),
+            _ => {}
+        }
+        if let Some(primitive) = self.boxed_primitive(declared, used_as) {
+            InstructionFormat::Box { operand: self.primitive_to_type_info_kind_format(primitive) }.write(bytecode);
+        }
+    }
+
+    // Pushes the arguments of parameters declared in the owner type, boxing the ones given to generic parameters and widening the others
+    pub fn codegen_args(&self, args: &[TIRExpr<'a>], parameters: &[TIRTypeInfo], owner: Option<&TIRTypeInfo>, bytecode: &mut Vec<u8>, codegen_context: &mut CodegenContext<'a>) -> Result<(), CodegenError> {
+        for (arg, parameter) in args.iter().zip(parameters) {
+            let arg_type = self.codegen_tir_expr(arg, bytecode, codegen_context)?;
+            let used_as = self.member_used_as(parameter, owner, &arg_type);
+            self.codegen_conversion(parameter, &used_as, &arg_type, bytecode);
+        }
+        Ok(())
+    }
+
+    // Unboxes the value of a member declared as a generic if it is a primitive where it is used, evaluates to the type it is used as
+    pub fn codegen_unbox(&self, declared: &TIRTypeInfo, used_as: TIRTypeInfo, bytecode: &mut Vec<u8>) -> TIRTypeInfo {
+        if let Some(primitive) = self.boxed_primitive(declared, &used_as) {
+            InstructionFormat::Unbox { operand: self.primitive_to_type_info_kind_format(primitive) }.write(bytecode);
+        }
+        used_as
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{compile, generates};
+    use oolang_bytecode::instruction_structure::{InstructionFormat, ConstantFormat};
+    use oolang_bytecode::typefile_structure::TypeInfoKindFormat;
+
+    const WRAPPER: &str = "
+        mod a;
+        class Wrapper<T> {
+            inside: T;
+            Wrapper(inside: T) { this.inside = inside; }
+            fn unwrap() -> T { inside }
+            fn set(value: T) { inside = value; }
+        }
+        class A {
+            fn run() -> u64 {
+                let wrapper: Wrapper<u64> = new Wrapper(10u32);
+                wrapper.set(5u8);
+                wrapper.unwrap()
+            }
+        }
+    ";
+
+    #[test]
+    fn primitives_are_widened_then_boxed_into_generics() {
+        let bytecode_files = compile(WRAPPER).unwrap();
+        assert!(generates(&bytecode_files, "A", &[
+            InstructionFormat::Constant(ConstantFormat::Integer(10, TypeInfoKindFormat::U32)),
+            InstructionFormat::Convert { from: TypeInfoKindFormat::U32, to: TypeInfoKindFormat::U64 },
+            InstructionFormat::Box { operand: TypeInfoKindFormat::U64 },
+            InstructionFormat::New { type_ref_index: 0, constructor_index: 0 }
+        ]));
+        assert!(generates(&bytecode_files, "A", &[
+            InstructionFormat::Constant(ConstantFormat::Integer(5, TypeInfoKindFormat::U8)),
+            InstructionFormat::Convert { from: TypeInfoKindFormat::U8, to: TypeInfoKindFormat::U64 },
+            InstructionFormat::Box { operand: TypeInfoKindFormat::U64 },
+            InstructionFormat::InvokeVirtual { method_ref_index: 1 }
+        ]));
+    }
+
+    #[test]
+    fn primitives_are_unboxed_out_of_generics() {
+        let bytecode_files = compile(WRAPPER).unwrap();
+        assert!(generates(&bytecode_files, "A", &[
+            InstructionFormat::LoadLocal { slot: 1 },
+            InstructionFormat::InvokeVirtual { method_ref_index: 0 },
+            InstructionFormat::Unbox { operand: TypeInfoKindFormat::U64 },
+            InstructionFormat::Return
+        ]));
+        // Inside the generic class the values are objects, nothing is boxed
+        assert!(!generates(&bytecode_files, "Wrapper", &[InstructionFormat::Box { operand: TypeInfoKindFormat::U64 }]));
+    }
+}
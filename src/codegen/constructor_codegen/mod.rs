@@ -0,0 +1,117 @@
+use crate::codegen::{Codegen, CodegenError};
+use crate::codegen::context::CodegenContext;
+use crate::tir::{TIRExpr, TIRStatementBlock, TIRTypeInfo};
+use crate::type_ref_pool::TypeRefKind;
+use oolang_bytecode::BytecodeFormat;
+use oolang_bytecode::instruction_structure::InstructionFormat;
+
+impl<'a> Codegen<'a> {
+    // "this" is in the first slot, followed by the parameters
+    pub fn codegen_constructor(
+        &self,
+        super_call: &Option<(Vec<usize>, Vec<TIRExpr<'a>>)>,
+        super_class: Option<&TIRTypeInfo>,
+        constructor_prefix: &[(u64, &TIRExpr<'a>)],
+        block: &TIRStatementBlock<'a>,
+        codegen_context: &mut CodegenContext<'a>
+    ) -> Result<Vec<u8>, CodegenError> {
+        let mut bytecode = vec![];
+        let this = 0;
+
+        // The super class is fully constructed before the fields of this class are initialized
+        // The type checker left only the constructor that is called
+        if let Some((constructor_indexes, args)) = super_call {
+            let constructor_index = constructor_indexes[0];
+            InstructionFormat::LoadLocal { slot: this }.write(&mut bytecode);
+            let super_class_type_ref_index = self.type_info_to_type_ref_index(super_class.unwrap());
+            let parameters = match &self.type_ref_pool.type_refs[super_class_type_ref_index as usize].kind {
+                TypeRefKind::Class(class_type_ref) => &class_type_ref.constructors[constructor_index],
+                _ => unreachable!()
+            };
+            self.codegen_args(args, parameters, super_class, &mut bytecode, codegen_context)?;
+            InstructionFormat::InvokeConstructor {
+                type_ref_index: super_class_type_ref_index,
+                constructor_index: constructor_index as u64
+            }.write(&mut bytecode);
+        }
+
+        for (field_ref_index, expression) in constructor_prefix {
+            InstructionFormat::LoadLocal { slot: this }.write(&mut bytecode);
+            self.codegen_tir_expr(expression, &mut bytecode, codegen_context)?;
+            InstructionFormat::SetField { field_ref_index: *field_ref_index }.write(&mut bytecode);
+        }
+
+        let block_type = self.codegen_tir_statement_block(block, &mut bytecode, codegen_context)?;
+        self.codegen_discard(&block_type, &mut bytecode);
+        InstructionFormat::Return.write(&mut bytecode);
+
+        Ok(bytecode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{compile, generates};
+    use oolang_bytecode::instruction_structure::{InstructionFormat, ConstantFormat};
+    use oolang_bytecode::typefile_structure::TypeInfoKindFormat;
+
+    #[test]
+    fn generic_constructors_and_super_calls_are_generated() {
+        let bytecode_files = compile("
+            mod author::project_name;
+            pub inter Wrapper<T> {
+                pub fn unwrap() -> T;
+            }
+            pub class SimpleWrapper<T> impl Wrapper<T> {
+                inside: T;
+                pub SimpleWrapper(inside: T) {
+                    this.inside = inside;
+                }
+                pub fn unwrap() -> T {
+                    inside
+                }
+            }
+            pub class WrapperWrapper<T, U impl Wrapper<T>>: SimpleWrapper<U> {
+                pub WrapperWrapper(inside: U) {
+                    super(inside);
+                }
+            }
+            pub class SimpleWrapperWrapper<T, U: SimpleWrapper<T>>: SimpleWrapper<U> {
+                pub SimpleWrapperWrapper(inside: U) {
+                    super(inside);
+                }
+            }
+            type DoubleWrapper<T> = SimpleWrapper<SimpleWrapper<T>>;
+            pub class Main {
+                pub static fn main() {
+                    let wrapped_integer: SimpleWrapper<u64> = new SimpleWrapper(10);
+                    let wrapped_wrapped_integer: SimpleWrapperWrapper<u64, SimpleWrapper<u64>> = new SimpleWrapperWrapper(wrapped_integer);
+                    let doubly_wrapped: DoubleWrapper<f64> = new SimpleWrapper(new SimpleWrapper(1.5));
+                }
+            }
+        ").unwrap();
+        assert!(generates(&bytecode_files, "Main", &[
+            InstructionFormat::Constant(ConstantFormat::Integer(10, TypeInfoKindFormat::U64)),
+            InstructionFormat::Box { operand: TypeInfoKindFormat::U64 },
+            InstructionFormat::New { type_ref_index: 1, constructor_index: 0 },
+            InstructionFormat::StoreLocal { slot: 0 },
+            InstructionFormat::LoadLocal { slot: 0 },
+            InstructionFormat::New { type_ref_index: 3, constructor_index: 0 }
+        ]));
+        assert!(generates(&bytecode_files, "Main", &[
+            InstructionFormat::Constant(ConstantFormat::Float(1.5, TypeInfoKindFormat::F64)),
+            InstructionFormat::Box { operand: TypeInfoKindFormat::F64 },
+            InstructionFormat::New { type_ref_index: 1, constructor_index: 0 },
+            InstructionFormat::New { type_ref_index: 1, constructor_index: 0 }
+        ]));
+        // The generic parameter is an object in the sub class as well, it is given as is
+        for sub_class in ["WrapperWrapper", "SimpleWrapperWrapper"] {
+            assert!(generates(&bytecode_files, sub_class, &[
+                InstructionFormat::LoadLocal { slot: 0 },
+                InstructionFormat::LoadLocal { slot: 1 },
+                InstructionFormat::InvokeConstructor { type_ref_index: 1, constructor_index: 0 },
+                InstructionFormat::Return
+            ]));
+        }
+    }
+}
@@ -1,3 +1,53 @@
+pub struct LoopLabels<'a> {
+    pub label: Option<&'a str>,
+    // "continue" jumps to the start, "break" to the end
+    pub start_label: u64,
+    pub end_label: u64,
+    // Slot "break" stores its value in, for loops that evaluate to one
+    pub value_slot: Option<u64>
+}
+
 pub struct CodegenContext<'a> {
+    local_count: u64,
+    label_count: u64,
+    // Innermost loop last
+    loops: Vec<LoopLabels<'a>>
+}
+
+impl<'a> CodegenContext<'a> {
+    // The lowerer gave a slot to every local of the body, temporaries go after them
+    pub fn new(local_count: usize) -> Self {
+        Self {
+            local_count: local_count as u64,
+            label_count: 0,
+            loops: vec![]
+        }
+    }
+
+    // Unnamed slot for temporaries
+    pub fn allocate_local(&mut self) -> u64 {
+        self.local_count += 1;
+        self.local_count - 1
+    }
+
+    pub fn new_label(&mut self) -> u64 {
+        self.label_count += 1;
+        self.label_count - 1
+    }
+
+    pub fn push_loop(&mut self, loop_labels: LoopLabels<'a>) {
+        self.loops.push(loop_labels);
+    }
+
+    pub fn pop_loop(&mut self) {
+        self.loops.pop();
+    }
 
-}
\ No newline at end of file
+    // Same as in the lowerer, which made sure that the loop exists
+    pub fn find_loop(&self, label: Option<&str>) -> &LoopLabels<'a> {
+        match label {
+            Some(label) => self.loops.iter().rev().find(|l| l.label == Some(label)),
+            None => self.loops.last()
+        }.unwrap()
+    }
+}
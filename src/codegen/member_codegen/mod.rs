@@ -1,8 +1,646 @@
 use crate::codegen::{Codegen, CodegenError};
-use crate::tir::{TIRExpr, TIRTypeInfo};
+use crate::codegen::context::{CodegenContext, LoopLabels};
+use crate::reporting::TokenSpan;
+use crate::type_ref_pool::TypeRefKind;
+use crate::tir::{TIRExpr, TIRExprKind, TIRTypeInfo, TIRTypeInfoKind, TIROperator, PrimitiveType, TIRStatementBlock, TIRStatementKind};
+use oolang_bytecode::BytecodeFormat;
+use oolang_bytecode::instruction_structure::{InstructionFormat, ConstantFormat, BinaryOpFormat, UnaryOpFormat};
 
 impl<'a> Codegen<'a> {
-    pub fn codegen_tir_expr(&self, tir_expr: &TIRExpr<'a>, bytecode: &mut Vec<u8>, codegen_context: &mut CodegenContext<'a>) -> Result<TIRTypeInfo, CodegenError<'a>> {
-        todo!()
+    pub fn primitive_type_info(&self, primitive: PrimitiveType, span: TokenSpan) -> TIRTypeInfo {
+        TIRTypeInfo {
+            kind: TIRTypeInfoKind::Primitive {
+                primitive,
+                array_dim: 0
+            },
+            span
+        }
     }
-}
\ No newline at end of file
+
+    pub fn operand_primitive(&self, type_info: &TIRTypeInfo) -> Option<PrimitiveType> {
+        match &type_info.kind {
+            TIRTypeInfoKind::Primitive { primitive, array_dim: 0 } => Some(*primitive),
+            _ => None
+        }
+    }
+
+    // Type of an expression that its instructions don't give, the type checker typed every expression that gets generated
+    pub fn checked_type(&self, tir_expr: &TIRExpr<'a>) -> TIRTypeInfo {
+        tir_expr.type_info.clone().unwrap()
+    }
+
+    // Returns the instruction and the primitive type of the result
+    fn binary_operator_instruction(&self, operator: &TIROperator, left: PrimitiveType, right: PrimitiveType) -> Option<(BinaryOpFormat, PrimitiveType)> {
+        let result = operator.binary_result(left, right)?;
+        let op = match operator {
+            TIROperator::Plus => BinaryOpFormat::Add,
+            TIROperator::Minus => BinaryOpFormat::Sub,
+            TIROperator::Mul => BinaryOpFormat::Mul,
+            TIROperator::Div => BinaryOpFormat::Div,
+            TIROperator::Mod => BinaryOpFormat::Rem,
+            TIROperator::BitAnd => BinaryOpFormat::BitAnd,
+            TIROperator::BitOr => BinaryOpFormat::BitOr,
+            TIROperator::BitXor => BinaryOpFormat::BitXor,
+            TIROperator::Shl => BinaryOpFormat::Shl,
+            TIROperator::Shr => BinaryOpFormat::Shr,
+            TIROperator::Eq => BinaryOpFormat::Eq,
+            TIROperator::NotEq => BinaryOpFormat::NotEq,
+            TIROperator::Gt => BinaryOpFormat::Gt,
+            TIROperator::GtEq => BinaryOpFormat::GtEq,
+            TIROperator::Ls => BinaryOpFormat::Ls,
+            TIROperator::LsEq => BinaryOpFormat::LsEq,
+            _ => return None
+        };
+
+        Some((op, result))
+    }
+
+    // Operands are already pushed, evaluates to the type of the result
+    pub fn codegen_binary_operator(&self, operator: &TIROperator, left_type: &TIRTypeInfo, right_type: &TIRTypeInfo, span: TokenSpan, bytecode: &mut Vec<u8>) -> Result<TIRTypeInfo, CodegenError> {
+        let instruction = match (self.operand_primitive(left_type), self.operand_primitive(right_type)) {
+            (Some(left), Some(right)) => self.binary_operator_instruction(operator, left, right)
+                .map(|(op, result)| (op, left, result)),
+            _ => None
+        };
+        let (op, operand, result) = instruction.ok_or(CodegenError::InvalidOperands(span, operator.clone()))?;
+        InstructionFormat::BinaryOp {
+            op,
+            operand: self.primitive_to_type_info_kind_format(operand)
+        }.write(bytecode);
+        Ok(self.primitive_type_info(result, span))
+    }
+
+    fn unary_operator_instruction(&self, operator: &TIROperator, operand: PrimitiveType) -> Option<UnaryOpFormat> {
+        operator.unary_result(operand)?;
+        match operator {
+            TIROperator::Minus => Some(UnaryOpFormat::Neg),
+            TIROperator::Not => Some(UnaryOpFormat::Not),
+            TIROperator::BitNot => Some(UnaryOpFormat::BitNot),
+            _ => None
+        }
+    }
+
+    // Pops the value of an expression whose value isn't used, if it has one
+    pub fn codegen_discard(&self, value_type: &TIRTypeInfo, bytecode: &mut Vec<u8>) {
+        match self.operand_primitive(value_type) {
+            Some(PrimitiveType::Void) | Some(PrimitiveType::Never) => {}
+            _ => InstructionFormat::Pop.write(bytecode)
+        }
+    }
+
+    // Evaluates to the ending expression of the block, if there is one
+    pub fn codegen_tir_statement_block(&self, block: &TIRStatementBlock<'a>, bytecode: &mut Vec<u8>, codegen_context: &mut CodegenContext<'a>) -> Result<TIRTypeInfo, CodegenError> {
+        let mut block_type = self.primitive_type_info(PrimitiveType::Void, block.span);
+        for statement in &block.statements {
+            match &statement.kind {
+                TIRStatementKind::Local(slot, _, expr) => if let Some(expr) = expr {
+                    self.codegen_tir_expr(expr, bytecode, codegen_context)?;
+                    InstructionFormat::StoreLocal { slot: *slot as u64 }.write(bytecode);
+                }
+                TIRStatementKind::Expression(expr) => {
+                    let expr_type = self.codegen_tir_expr(expr, bytecode, codegen_context)?;
+                    if statement.ending {
+                        block_type = expr_type;
+                    } else {
+                        self.codegen_discard(&expr_type, bytecode);
+                    }
+                }
+            }
+        }
+
+        Ok(block_type)
+    }
+
+    pub fn codegen_tir_expr(&self, tir_expr: &TIRExpr<'a>, bytecode: &mut Vec<u8>, codegen_context: &mut CodegenContext<'a>) -> Result<TIRTypeInfo, CodegenError> {
+        match &tir_expr.kind {
+            TIRExprKind::Num(value, suffix) => {
+                // Unsuffixed literals get their type from the type checker
+                let primitive = suffix.or_else(|| tir_expr.type_info.as_ref().and_then(|t| self.operand_primitive(t))).unwrap_or(PrimitiveType::I64);
+                InstructionFormat::Constant(ConstantFormat::Integer(*value, self.primitive_to_type_info_kind_format(primitive))).write(bytecode);
+                Ok(self.primitive_type_info(primitive, tir_expr.span))
+            }
+            TIRExprKind::Float(value, suffix) => {
+                let primitive = suffix.or_else(|| tir_expr.type_info.as_ref().and_then(|t| self.operand_primitive(t))).unwrap_or(PrimitiveType::F64);
+                InstructionFormat::Constant(ConstantFormat::Float(*value, self.primitive_to_type_info_kind_format(primitive))).write(bytecode);
+                Ok(self.primitive_type_info(primitive, tir_expr.span))
+            }
+            TIRExprKind::Null => {
+                InstructionFormat::Constant(ConstantFormat::Null).write(bytecode);
+                Ok(self.checked_type(tir_expr))
+            }
+            TIRExprKind::Boolean(boolean) => {
+                InstructionFormat::Constant(ConstantFormat::Boolean(*boolean)).write(bytecode);
+                Ok(self.primitive_type_info(PrimitiveType::Boolean, tir_expr.span))
+            }
+            TIRExprKind::StringLiteral(string) => {
+                InstructionFormat::Constant(ConstantFormat::String(string)).write(bytecode);
+                Ok(self.checked_type(tir_expr))
+            }
+            TIRExprKind::CharLiteral(character) => {
+                InstructionFormat::Constant(ConstantFormat::Character(*character)).write(bytecode);
+                Ok(self.primitive_type_info(PrimitiveType::Character, tir_expr.span))
+            }
+            // "super" is the same object as "this", seen as its super class
+            TIRExprKind::This | TIRExprKind::Super => {
+                InstructionFormat::LoadLocal { slot: 0 }.write(bytecode);
+                Ok(self.checked_type(tir_expr))
+            }
+            TIRExprKind::LocalAccess(slot) => {
+                InstructionFormat::LoadLocal { slot: *slot as u64 }.write(bytecode);
+                Ok(self.checked_type(tir_expr))
+            }
+            TIRExprKind::BinOp(left, operator, right) => match operator {
+                TIROperator::Plus | TIROperator::Minus | TIROperator::Mul | TIROperator::Div | TIROperator::Mod |
+                TIROperator::BitAnd | TIROperator::BitOr | TIROperator::BitXor | TIROperator::Shl | TIROperator::Shr |
+                TIROperator::Eq | TIROperator::NotEq | TIROperator::Gt | TIROperator::GtEq | TIROperator::Ls | TIROperator::LsEq => {
+                    let left_type = self.codegen_tir_expr(left, bytecode, codegen_context)?;
+                    let right_type = self.codegen_tir_expr(right, bytecode, codegen_context)?;
+                    self.codegen_binary_operator(operator, &left_type, &right_type, tir_expr.span, bytecode)
+                }
+                // The right operand is only evaluated when the left one doesn't decide the result
+                TIROperator::And | TIROperator::Or => {
+                    let short_circuit_label = codegen_context.new_label();
+                    let end_label = codegen_context.new_label();
+                    self.codegen_tir_expr(left, bytecode, codegen_context)?;
+                    InstructionFormat::JumpIfFalse { label: short_circuit_label }.write(bytecode);
+                    if let TIROperator::And = operator {
+                        self.codegen_tir_expr(right, bytecode, codegen_context)?;
+                        InstructionFormat::Jump { label: end_label }.write(bytecode);
+                        InstructionFormat::Label { label: short_circuit_label }.write(bytecode);
+                        InstructionFormat::Constant(ConstantFormat::Boolean(false)).write(bytecode);
+                    } else {
+                        InstructionFormat::Constant(ConstantFormat::Boolean(true)).write(bytecode);
+                        InstructionFormat::Jump { label: end_label }.write(bytecode);
+                        InstructionFormat::Label { label: short_circuit_label }.write(bytecode);
+                        self.codegen_tir_expr(right, bytecode, codegen_context)?;
+                    }
+                    InstructionFormat::Label { label: end_label }.write(bytecode);
+                    Ok(self.primitive_type_info(PrimitiveType::Boolean, tir_expr.span))
+                }
+                _ => self.codegen_assignment(left, operator, right, tir_expr.span, bytecode, codegen_context)
+            }
+            TIRExprKind::PreOp(operator, expr) => match operator {
+                TIROperator::Minus | TIROperator::Not | TIROperator::BitNot => {
+                    let expr_type = self.codegen_tir_expr(expr, bytecode, codegen_context)?;
+                    let operand = self.operand_primitive(&expr_type)
+                        .filter(|operand| self.unary_operator_instruction(operator, *operand).is_some())
+                        .ok_or(CodegenError::InvalidOperands(tir_expr.span, operator.clone()))?;
+                    InstructionFormat::UnaryOp {
+                        op: self.unary_operator_instruction(operator, operand).unwrap(),
+                        operand: self.primitive_to_type_info_kind_format(operand)
+                    }.write(bytecode);
+                    Ok(self.primitive_type_info(operand, tir_expr.span))
+                }
+                _ => self.codegen_step(expr, operator, true, tir_expr.span, bytecode, codegen_context)
+            }
+            TIRExprKind::PostOp(expr, operator) => self.codegen_step(expr, operator, false, tir_expr.span, bytecode, codegen_context),
+            TIRExprKind::EnumConstruct(type_ref_index, variant_index, args) => {
+                // Fields are pushed in declaration order, the instruction pops them
+                let fields = match &self.type_ref_pool.type_refs[*type_ref_index].kind {
+                    TypeRefKind::Enum(enum_type_ref) => &enum_type_ref.variants[*variant_index].fields,
+                    _ => unreachable!()
+                };
+                self.codegen_args(args, fields, tir_expr.type_info.as_ref(), bytecode, codegen_context)?;
+                InstructionFormat::EnumConstruct {
+                    type_ref_index: *type_ref_index as u64,
+                    discriminant: *variant_index as u64
+                }.write(bytecode);
+                // Generics of generic enums come from the type checker
+                Ok(tir_expr.type_info.clone().unwrap_or_else(|| TIRTypeInfo {
+                    kind: TIRTypeInfoKind::TypeRef {
+                        type_ref_index: *type_ref_index,
+                        generics: vec![],
+                        array_dim: 0
+                    },
+                    span: tir_expr.span
+                }))
+            }
+            TIRExprKind::New(type_info, constructor_index, args) => {
+                let type_ref_index = self.type_info_to_type_ref_index(type_info);
+                let parameters = match &self.type_ref_pool.type_refs[type_ref_index as usize].kind {
+                    TypeRefKind::Class(class_type_ref) => &class_type_ref.constructors[*constructor_index],
+                    _ => unreachable!()
+                };
+                self.codegen_args(args, parameters, Some(type_info), bytecode, codegen_context)?;
+                InstructionFormat::New {
+                    type_ref_index,
+                    constructor_index: *constructor_index as u64
+                }.write(bytecode);
+                Ok(type_info.clone())
+            }
+            // Member types come from the type checker, which substitutes the generics of the type they're accessed on
+            TIRExprKind::FieldAccess(on, field_ref_index) => {
+                self.codegen_tir_expr(on, bytecode, codegen_context)?;
+                InstructionFormat::GetField { field_ref_index: *field_ref_index as u64 }.write(bytecode);
+                let declared = &self.field_ref_pool.field_refs[*field_ref_index].type_info;
+                Ok(self.codegen_unbox(declared, tir_expr.type_info.clone().unwrap_or_else(|| declared.clone()), bytecode))
+            }
+            TIRExprKind::StaticFieldAccess(field_ref_index) => {
+                InstructionFormat::GetStaticField { field_ref_index: *field_ref_index as u64 }.write(bytecode);
+                Ok(self.field_ref_pool.field_refs[*field_ref_index].type_info.clone())
+            }
+            TIRExprKind::MethodCall(on, method_ref_index, args) => {
+                let on_type = self.codegen_tir_expr(on, bytecode, codegen_context)?;
+                let method_ref = &self.method_ref_pool.method_refs[*method_ref_index];
+                let owner = self.type_ref_pool.as_super(&on_type, method_ref.associated_type_ref_index);
+                self.codegen_args(args, &method_ref.parameters, owner.as_ref(), bytecode, codegen_context)?;
+                let method_ref_index = *method_ref_index as u64;
+                // Methods called on "super" are not dispatched on the runtime class
+                match on.kind {
+                    TIRExprKind::Super => InstructionFormat::InvokeSpecial { method_ref_index }.write(bytecode),
+                    _ => InstructionFormat::InvokeVirtual { method_ref_index }.write(bytecode)
+                }
+                Ok(self.codegen_unbox(&method_ref.return_type, tir_expr.type_info.clone().unwrap_or_else(|| method_ref.return_type.clone()), bytecode))
+            }
+            TIRExprKind::StaticMethodCall(method_ref_index, args) => {
+                let method_ref = &self.method_ref_pool.method_refs[*method_ref_index];
+                self.codegen_args(args, &method_ref.parameters, None, bytecode, codegen_context)?;
+                InstructionFormat::InvokeStatic { method_ref_index: *method_ref_index as u64 }.write(bytecode);
+                // Generics of static methods of generic types are inferred by the type checker
+                Ok(self.codegen_unbox(&method_ref.return_type, tir_expr.type_info.clone().unwrap_or_else(|| method_ref.return_type.clone()), bytecode))
+            }
+            TIRExprKind::Indexing(array, index) => {
+                self.codegen_tir_expr(array, bytecode, codegen_context)?;
+                self.codegen_tir_expr(index, bytecode, codegen_context)?;
+                InstructionFormat::ArrayLoad.write(bytecode);
+                Ok(self.checked_type(tir_expr))
+            }
+            TIRExprKind::ArrayLength(array) => {
+                self.codegen_tir_expr(array, bytecode, codegen_context)?;
+                InstructionFormat::ArrayLength.write(bytecode);
+                Ok(self.primitive_type_info(PrimitiveType::U64, tir_expr.span))
+            }
+            TIRExprKind::Block(block) => self.codegen_tir_statement_block(block, bytecode, codegen_context),
+            TIRExprKind::Match(scrutinee, arms) => self.codegen_match(scrutinee, arms, tir_expr.span, bytecode, codegen_context),
+            TIRExprKind::If(condition, block) => {
+                let end_label = codegen_context.new_label();
+                self.codegen_tir_expr(condition, bytecode, codegen_context)?;
+                InstructionFormat::JumpIfFalse { label: end_label }.write(bytecode);
+                let block_type = self.codegen_tir_statement_block(block, bytecode, codegen_context)?;
+                self.codegen_discard(&block_type, bytecode);
+                InstructionFormat::Label { label: end_label }.write(bytecode);
+                Ok(self.primitive_type_info(PrimitiveType::Void, tir_expr.span))
+            }
+            TIRExprKind::IfElse(condition, then_block, else_block) => {
+                let (else_label, end_label) = (codegen_context.new_label(), codegen_context.new_label());
+                self.codegen_tir_expr(condition, bytecode, codegen_context)?;
+                InstructionFormat::JumpIfFalse { label: else_label }.write(bytecode);
+                self.codegen_tir_statement_block(then_block, bytecode, codegen_context)?;
+                InstructionFormat::Jump { label: end_label }.write(bytecode);
+                InstructionFormat::Label { label: else_label }.write(bytecode);
+                self.codegen_tir_statement_block(else_block, bytecode, codegen_context)?;
+                InstructionFormat::Label { label: end_label }.write(bytecode);
+                Ok(self.checked_type(tir_expr))
+            }
+            TIRExprKind::Loop(label, block) => {
+                let loop_type = self.checked_type(tir_expr);
+                // "break" leaves the value of the loop in a slot, the stack can hold anything when it is reached
+                let value_slot = match self.operand_primitive(&loop_type) {
+                    Some(PrimitiveType::Void) | Some(PrimitiveType::Never) => None,
+                    _ => Some(codegen_context.allocate_local())
+                };
+                let (start_label, end_label) = (codegen_context.new_label(), codegen_context.new_label());
+                InstructionFormat::Label { label: start_label }.write(bytecode);
+                codegen_context.push_loop(LoopLabels {
+                    label: *label,
+                    start_label,
+                    end_label,
+                    value_slot
+                });
+                let block_type = self.codegen_tir_statement_block(block, bytecode, codegen_context)?;
+                codegen_context.pop_loop();
+                self.codegen_discard(&block_type, bytecode);
+                InstructionFormat::Jump { label: start_label }.write(bytecode);
+                InstructionFormat::Label { label: end_label }.write(bytecode);
+                if let Some(value_slot) = value_slot {
+                    InstructionFormat::LoadLocal { slot: value_slot }.write(bytecode);
+                }
+                Ok(loop_type)
+            }
+            TIRExprKind::While(label, condition, block) => {
+                let (start_label, end_label) = (codegen_context.new_label(), codegen_context.new_label());
+                InstructionFormat::Label { label: start_label }.write(bytecode);
+                // "break" in the condition exits this loop too
+                codegen_context.push_loop(LoopLabels {
+                    label: *label,
+                    start_label,
+                    end_label,
+                    value_slot: None
+                });
+                self.codegen_tir_expr(condition, bytecode, codegen_context)?;
+                InstructionFormat::JumpIfFalse { label: end_label }.write(bytecode);
+                let block_type = self.codegen_tir_statement_block(block, bytecode, codegen_context)?;
+                codegen_context.pop_loop();
+                self.codegen_discard(&block_type, bytecode);
+                InstructionFormat::Jump { label: start_label }.write(bytecode);
+                InstructionFormat::Label { label: end_label }.write(bytecode);
+                Ok(self.primitive_type_info(PrimitiveType::Void, tir_expr.span))
+            }
+            TIRExprKind::Break(label, value) => {
+                let (end_label, value_slot) = {
+                    let loop_labels = codegen_context.find_loop(*label);
+                    (loop_labels.end_label, loop_labels.value_slot)
+                };
+                if let Some(value) = value {
+                    let value_type = self.codegen_tir_expr(value, bytecode, codegen_context)?;
+                    match value_slot {
+                        Some(value_slot) => InstructionFormat::StoreLocal { slot: value_slot }.write(bytecode),
+                        None => self.codegen_discard(&value_type, bytecode)
+                    }
+                }
+                InstructionFormat::Jump { label: end_label }.write(bytecode);
+                Ok(self.primitive_type_info(PrimitiveType::Never, tir_expr.span))
+            }
+            TIRExprKind::Continue(label) => {
+                InstructionFormat::Jump { label: codegen_context.find_loop(*label).start_label }.write(bytecode);
+                Ok(self.primitive_type_info(PrimitiveType::Never, tir_expr.span))
+            }
+            // The value is left on the stack for the method to return it
+            TIRExprKind::Return(value) => {
+                if let Some(value) = value {
+                    self.codegen_tir_expr(value, bytecode, codegen_context)?;
+                }
+                InstructionFormat::Return.write(bytecode);
+                Ok(self.primitive_type_info(PrimitiveType::Never, tir_expr.span))
+            }
+            // The type checker resolved or desugared these, or reported them as errors
+            TIRExprKind::MemberAccess(..) | TIRExprKind::Call(..) | TIRExprKind::OverloadedCall(..) | TIRExprKind::OverloadedNew(..) |
+            TIRExprKind::For(..) | TIRExprKind::TypeAccess(_) | TIRExprKind::Range(..) => unreachable!()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{compile, generates};
+    use oolang_bytecode::instruction_structure::{InstructionFormat, ConstantFormat, BinaryOpFormat, UnaryOpFormat};
+    use oolang_bytecode::typefile_structure::TypeInfoKindFormat;
+
+    #[test]
+    fn this_super_and_locals_are_loaded() {
+        compile("
+            mod a;
+            class Base {
+                fn size() -> u64 { 1 }
+            }
+            class Counter: Base {
+                count: u64;
+                Counter() { this.size(); }
+                fn size() -> u64 { let base = super.size(); base }
+                fn current() -> u64 { count }
+                fn me() -> Counter { this }
+            }
+        ").unwrap();
+    }
+
+    #[test]
+    fn loops_jumps_and_returns_are_generated() {
+        let bytecode_files = compile("
+            mod a;
+            class A {
+                fn eleven() -> u64 { loop { break 11; } }
+                fn nested(flag: bool) -> u64 {
+                    'outer: loop {
+                        while flag {
+                            if flag { continue 'outer; }
+                            break 'outer 1;
+                        }
+                        return 2;
+                    }
+                }
+                fn pick(flag: bool) -> u64 { if flag { return 1; } else { 2 } }
+                fn forever() { loop { } }
+            }
+        ").unwrap();
+        // The value of "break" goes in the slot after the locals, which is loaded at the end of the loop
+        assert!(generates(&bytecode_files, "A", &[
+            InstructionFormat::Label { label: 0 },
+            InstructionFormat::Constant(ConstantFormat::Integer(11, TypeInfoKindFormat::U64)),
+            InstructionFormat::StoreLocal { slot: 1 },
+            InstructionFormat::Jump { label: 1 },
+            InstructionFormat::Jump { label: 0 },
+            InstructionFormat::Label { label: 1 },
+            InstructionFormat::LoadLocal { slot: 1 },
+            InstructionFormat::Return
+        ]));
+        // "continue 'outer" and "break 'outer" jump past the "while" loop, to the labels of the outer one (0 and 1)
+        assert!(generates(&bytecode_files, "A", &[
+            InstructionFormat::Label { label: 0 },
+            InstructionFormat::Label { label: 2 },
+            InstructionFormat::LoadLocal { slot: 1 },
+            InstructionFormat::JumpIfFalse { label: 3 },
+            InstructionFormat::LoadLocal { slot: 1 },
+            InstructionFormat::JumpIfFalse { label: 4 },
+            InstructionFormat::Jump { label: 0 },
+            InstructionFormat::Label { label: 4 },
+            InstructionFormat::Constant(ConstantFormat::Integer(1, TypeInfoKindFormat::U64)),
+            InstructionFormat::StoreLocal { slot: 2 },
+            InstructionFormat::Jump { label: 1 },
+            InstructionFormat::Jump { label: 2 },
+            InstructionFormat::Label { label: 3 },
+            InstructionFormat::Constant(ConstantFormat::Integer(2, TypeInfoKindFormat::U64)),
+            InstructionFormat::Return,
+            InstructionFormat::Jump { label: 0 },
+            InstructionFormat::Label { label: 1 },
+            InstructionFormat::LoadLocal { slot: 2 },
+            InstructionFormat::Return
+        ]));
+        // The branch that returns isn't followed by another "Return"
+        assert!(generates(&bytecode_files, "A", &[
+            InstructionFormat::LoadLocal { slot: 1 },
+            InstructionFormat::JumpIfFalse { label: 0 },
+            InstructionFormat::Constant(ConstantFormat::Integer(1, TypeInfoKindFormat::U64)),
+            InstructionFormat::Return,
+            InstructionFormat::Jump { label: 1 },
+            InstructionFormat::Label { label: 0 },
+            InstructionFormat::Constant(ConstantFormat::Integer(2, TypeInfoKindFormat::U64)),
+            InstructionFormat::Label { label: 1 },
+            InstructionFormat::Return
+        ]));
+        // Loops without "break" never complete, they have no value to load and the method doesn't return after them
+        let forever = [InstructionFormat::Label { label: 0 }, InstructionFormat::Jump { label: 0 }, InstructionFormat::Label { label: 1 }];
+        assert!(generates(&bytecode_files, "A", &forever));
+        let [start, jump, end] = forever;
+        assert!(!generates(&bytecode_files, "A", &[start, jump, end, InstructionFormat::Return]));
+    }
+
+    #[test]
+    fn assignments_and_short_circuits_are_generated() {
+        compile("
+            mod a;
+            class Cell {
+                value: u64;
+                Cell(value: u64) { }
+            }
+            class A {
+                static total: u64;
+                count: u64;
+                fn run(flag: bool, cell: Cell, values: u64[]) -> bool {
+                    let local: u64 = 1;
+                    local = 2;
+                    local += 3;
+                    count *= 2;
+                    total -= 1;
+                    cell.value = local;
+                    cell.value += 1;
+                    values[0] = ++local;
+                    values[local--] <<= 1;
+                    count++;
+                    flag && local > 2 || !flag
+                }
+            }
+        ").unwrap();
+    }
+
+    #[test]
+    fn compound_assignments_and_bitwise_operators_are_generated() {
+        let bytecode_files = compile("
+            mod a;
+            class Cell { value: u64; Cell(value: u64) { } }
+            class A {
+                fn shift(a: u64) { a <<= 2; }
+                fn rem(x: u64, y: u64) { x %= y; }
+                fn add(cell: Cell) { cell.value += 1; }
+                fn xor(values: u64[], i: u64) { values[i] ^= 3; }
+                fn bits(a: u64, b: u64) -> u64 { (a & b) | (a >> 1) }
+                fn post(values: u64[], i: u64) -> u64 { values[i]++ }
+            }
+        ").unwrap();
+        let u64 = || TypeInfoKindFormat::U64;
+        assert!(generates(&bytecode_files, "A", &[
+            InstructionFormat::LoadLocal { slot: 1 },
+            InstructionFormat::Constant(ConstantFormat::Integer(2, u64())),
+            InstructionFormat::BinaryOp { op: BinaryOpFormat::Shl, operand: u64() },
+            InstructionFormat::StoreLocal { slot: 1 }
+        ]));
+        assert!(generates(&bytecode_files, "A", &[
+            InstructionFormat::LoadLocal { slot: 1 },
+            InstructionFormat::LoadLocal { slot: 2 },
+            InstructionFormat::BinaryOp { op: BinaryOpFormat::Rem, operand: u64() },
+            InstructionFormat::StoreLocal { slot: 1 }
+        ]));
+        // The object is evaluated once into a temporary, loaded for the read and for the write
+        assert!(generates(&bytecode_files, "A", &[
+            InstructionFormat::LoadLocal { slot: 1 },
+            InstructionFormat::StoreLocal { slot: 2 },
+            InstructionFormat::LoadLocal { slot: 2 },
+            InstructionFormat::LoadLocal { slot: 2 },
+            InstructionFormat::GetField { field_ref_index: 0 },
+            InstructionFormat::Constant(ConstantFormat::Integer(1, u64())),
+            InstructionFormat::BinaryOp { op: BinaryOpFormat::Add, operand: u64() },
+            InstructionFormat::SetField { field_ref_index: 0 }
+        ]));
+        // Same for the array and the index
+        assert!(generates(&bytecode_files, "A", &[
+            InstructionFormat::LoadLocal { slot: 1 },
+            InstructionFormat::StoreLocal { slot: 3 },
+            InstructionFormat::LoadLocal { slot: 2 },
+            InstructionFormat::StoreLocal { slot: 4 },
+            InstructionFormat::LoadLocal { slot: 3 },
+            InstructionFormat::LoadLocal { slot: 4 },
+            InstructionFormat::LoadLocal { slot: 3 },
+            InstructionFormat::LoadLocal { slot: 4 },
+            InstructionFormat::ArrayLoad,
+            InstructionFormat::Constant(ConstantFormat::Integer(3, u64())),
+            InstructionFormat::BinaryOp { op: BinaryOpFormat::BitXor, operand: u64() },
+            InstructionFormat::ArrayStore
+        ]));
+        assert!(generates(&bytecode_files, "A", &[
+            InstructionFormat::LoadLocal { slot: 1 },
+            InstructionFormat::LoadLocal { slot: 2 },
+            InstructionFormat::BinaryOp { op: BinaryOpFormat::BitAnd, operand: u64() },
+            InstructionFormat::LoadLocal { slot: 1 },
+            InstructionFormat::Constant(ConstantFormat::Integer(1, u64())),
+            InstructionFormat::BinaryOp { op: BinaryOpFormat::Shr, operand: u64() },
+            InstructionFormat::BinaryOp { op: BinaryOpFormat::BitOr, operand: u64() }
+        ]));
+        // The element before the step is kept in a slot to be the value of the expression
+        assert!(generates(&bytecode_files, "A", &[
+            InstructionFormat::ArrayLoad,
+            InstructionFormat::StoreLocal { slot: 5 },
+            InstructionFormat::LoadLocal { slot: 5 },
+            InstructionFormat::Constant(ConstantFormat::Integer(1, u64())),
+            InstructionFormat::BinaryOp { op: BinaryOpFormat::Add, operand: u64() },
+            InstructionFormat::StoreLocal { slot: 6 },
+            InstructionFormat::LoadLocal { slot: 3 },
+            InstructionFormat::LoadLocal { slot: 4 },
+            InstructionFormat::LoadLocal { slot: 6 },
+            InstructionFormat::ArrayStore,
+            InstructionFormat::LoadLocal { slot: 5 },
+            InstructionFormat::Return
+        ]));
+    }
+
+    #[test]
+    fn string_literals_are_standard_strings() {
+        compile("mod oolang; class String { } class A { fn greeting() -> String { \"hello\" } }").unwrap();
+    }
+
+    #[test]
+    fn for_loops_are_generated() {
+        let bytecode_files = compile("
+            mod oolang;
+            inter Iterator<T> { pub fn has_next() -> bool; pub fn next() -> T; }
+            inter Iterable<T> { pub fn iter() -> Iterator<T>; }
+            class A {
+                fn sum(values: u64[]) -> u64 { let total: u64 = 0; for value in values { total += value; } total }
+                fn count(items: Iterable<u64>) -> u64 { let total: u64 = 0; for item in items { total += item; } total }
+                fn nested(values: u64[]) {
+                    'outer: for i in 0u64..10 {
+                        for j in 0..=i { if j == 5 { break 'outer; } values[j]; }
+                    }
+                }
+            }
+        ").unwrap();
+        let u64 = || TypeInfoKindFormat::U64;
+        // Arrays: the index starts at 0 and is checked against the length before each element is loaded, then incremented
+        assert!(generates(&bytecode_files, "A", &[
+            InstructionFormat::LoadLocal { slot: 1 },
+            InstructionFormat::StoreLocal { slot: 4 },
+            InstructionFormat::Constant(ConstantFormat::Integer(0, u64())),
+            InstructionFormat::StoreLocal { slot: 5 },
+            InstructionFormat::Label { label: 0 },
+            InstructionFormat::LoadLocal { slot: 5 },
+            InstructionFormat::LoadLocal { slot: 4 },
+            InstructionFormat::ArrayLength,
+            InstructionFormat::BinaryOp { op: BinaryOpFormat::GtEq, operand: u64() },
+            InstructionFormat::JumpIfFalse { label: 2 },
+            InstructionFormat::Jump { label: 1 },
+            InstructionFormat::Label { label: 2 },
+            InstructionFormat::LoadLocal { slot: 4 },
+            InstructionFormat::LoadLocal { slot: 5 },
+            InstructionFormat::ArrayLoad,
+            InstructionFormat::StoreLocal { slot: 3 },
+            InstructionFormat::LoadLocal { slot: 5 },
+            InstructionFormat::Constant(ConstantFormat::Integer(1, u64())),
+            InstructionFormat::BinaryOp { op: BinaryOpFormat::Add, operand: u64() },
+            InstructionFormat::StoreLocal { slot: 5 }
+        ]));
+        assert!(generates(&bytecode_files, "A", &[
+            InstructionFormat::StoreLocal { slot: 2 },
+            InstructionFormat::Jump { label: 0 },
+            InstructionFormat::Label { label: 1 },
+            InstructionFormat::LoadLocal { slot: 2 },
+            InstructionFormat::Return
+        ]));
+        // Iterables: has_next (method ref 0) is called before each next (method ref 1) on the iterator returned by iter (method ref 2)
+        assert!(generates(&bytecode_files, "A", &[
+            InstructionFormat::LoadLocal { slot: 1 },
+            InstructionFormat::StoreLocal { slot: 4 },
+            InstructionFormat::LoadLocal { slot: 4 },
+            InstructionFormat::InvokeVirtual { method_ref_index: 2 },
+            InstructionFormat::StoreLocal { slot: 5 },
+            InstructionFormat::Label { label: 0 },
+            InstructionFormat::LoadLocal { slot: 5 },
+            InstructionFormat::InvokeVirtual { method_ref_index: 0 },
+            InstructionFormat::UnaryOp { op: UnaryOpFormat::Not, operand: TypeInfoKindFormat::Boolean },
+            InstructionFormat::JumpIfFalse { label: 2 },
+            InstructionFormat::Jump { label: 1 },
+            InstructionFormat::Label { label: 2 },
+            InstructionFormat::LoadLocal { slot: 5 },
+            InstructionFormat::InvokeVirtual { method_ref_index: 1 },
+            InstructionFormat::Unbox { operand: u64() },
+            InstructionFormat::StoreLocal { slot: 3 }
+        ]));
+    }
+}
@@ -0,0 +1,85 @@
+use crate::codegen::{Codegen, CodegenError};
+use crate::codegen::context::CodegenContext;
+use crate::tir::{TIRMember, TIRMemberKind, TIRStatementBlock, PrimitiveType};
+use oolang_bytecode::BytecodeFormat;
+use oolang_bytecode::instruction_structure::InstructionFormat;
+use oolang_bytecode::typefile_structure::MethodFormat;
+
+impl<'a> Codegen<'a> {
+    // The body evaluates to the return value, which is left on the stack for "Return"
+    fn codegen_method_body(&self, block: &TIRStatementBlock<'a>, local_count: usize) -> Result<Vec<u8>, CodegenError> {
+        let mut bytecode = vec![];
+        let block_type = self.codegen_tir_statement_block(block, &mut bytecode, &mut CodegenContext::new(local_count))?;
+        // Bodies that never complete already returned
+        if self.operand_primitive(&block_type) != Some(PrimitiveType::Never) {
+            InstructionFormat::Return.write(&mut bytecode);
+        }
+        Ok(bytecode)
+    }
+
+    // Methods of the type in the order of the method ref pool, abstract and native ones (like every interface method) only have their signature
+    pub fn codegen_methods(&self, type_ref_index: usize, members: &[TIRMember<'a>]) -> Result<Vec<MethodFormat<'a>>, CodegenError> {
+        let mut methods = vec![];
+        // TODO : same as fields, this iterates through every method in the pool
+        for method_ref in &self.method_ref_pool.method_refs {
+            if method_ref.associated_type_ref_index != type_ref_index {
+                continue;
+            }
+            let bytecode = match &members[method_ref.index_in_all_members].kind {
+                TIRMemberKind::Method { block: Some(block), local_count, .. } => self.codegen_method_body(block, *local_count)?,
+                TIRMemberKind::Method { block: None, .. } => vec![],
+                _ => unreachable!()
+            };
+            methods.push(MethodFormat {
+                name: method_ref.name,
+                visibility: self.visibility_to_visibility_format(method_ref.visibility),
+                parameters: method_ref.parameters.iter().map(|p| self.tir_type_info_to_type_info_format(p)).collect(),
+                return_type: self.tir_type_info_to_type_info_format(&method_ref.return_type),
+                is_static: method_ref.is_static,
+                is_abstract: method_ref.is_abstract,
+                is_native: method_ref.is_native,
+                bytecode
+            });
+        }
+        Ok(methods)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{compile, generates};
+    use oolang_bytecode::instruction_structure::InstructionFormat;
+    use oolang_bytecode::typefile_structure::TypeInfoKindFormat;
+
+    #[test]
+    fn interfaces_are_written_with_their_method_signatures() {
+        let bytecode_files = compile("mod a; pub inter Shape { pub fn area() -> u64; } pub inter Square: Shape { pub fn side() -> u64; }").unwrap();
+        let paths: Vec<&Vec<&str>> = bytecode_files.iter().map(|f| &f.file_path).collect();
+        assert_eq!(paths, [&vec!["a", "Shape"], &vec!["a", "Square"]]);
+    }
+
+    #[test]
+    fn classes_are_written_with_their_method_bodies() {
+        compile("mod a; inter Shape { pub fn area() -> u64; } class Square impl Shape { pub fn area() -> u64 { 4 } static fn unit() { } }").unwrap();
+    }
+
+    #[test]
+    fn generic_interface_methods_are_called_on_objects() {
+        let bytecode_files = compile("
+            mod a;
+            pub inter Wrapper<T> { pub fn unwrap() -> T; }
+            pub class SimpleWrapper<T> impl Wrapper<T> {
+                inside: T;
+                pub SimpleWrapper(inside: T) { this.inside = inside; }
+                pub fn unwrap() -> T { inside }
+            }
+            class A { fn read(wrapper: Wrapper<u64>) -> u64 { wrapper.unwrap() } }
+        ").unwrap();
+        assert!(generates(&bytecode_files, "A", &[
+            InstructionFormat::LoadLocal { slot: 1 },
+            InstructionFormat::InvokeVirtual { method_ref_index: 0 },
+            InstructionFormat::Unbox { operand: TypeInfoKindFormat::U64 },
+            InstructionFormat::Return
+        ]));
+    }
+}
@@ -1,14 +1,22 @@
-use crate::tir::{TIRRoot, TIRTypeKind, TIRMemberKind, TIRModifier, TIRTypeInfo, TIRType, TIRTypeInfoKind, PrimitiveType, TIRExpr, TIRExprKind, TIROperator};
-use crate::type_ref_pool::{TypeRefPool, TypeRefKind};
-use crate::field_ref_pool::{FieldRefPool, FieldRef};
-use crate::method_ref_pool::{MethodRefPool, MethodRef};
-use std::collections::HashMap;
-use oolang_bytecode::typefile_structure::{TypeFileFormat, TypeKindFormat, FieldFormat, TypeInfoFormat, TypeInfoKindFormat};
+use crate::tir::{TIRRoot, TIRTypeKind, TIRMemberKind, TIRTypeInfo, TIRType, TIRTypeInfoKind, PrimitiveType, TIRExpr, TIROperator};
+use crate::codegen::context::CodegenContext;
+use crate::type_ref_pool::{TypeRefPool, TypeRefKind, EnumVariantRefKind};
+use crate::field_ref_pool::FieldRefPool;
+use crate::method_ref_pool::MethodRefPool;
+use crate::reporting::TokenSpan;
+use crate::ast::ASTVisibility;
+use oolang_bytecode::typefile_structure::{TypeFileFormat, TypeKindFormat, FieldFormat, ConstructorFormat, TypeInfoFormat, TypeInfoKindFormat, VariantFormat, VisibilityFormat};
+use oolang_bytecode::instruction_structure::InstructionFormat;
 use oolang_bytecode::BytecodeFormat;
 use oolang_bytecode::poolfile_structure::FieldRefFormat;
 
 pub mod context;
 pub mod member_codegen;
+pub mod pattern_codegen;
+pub mod constructor_codegen;
+pub mod method_codegen;
+pub mod boxing_codegen;
+pub mod assignment_codegen;
 
 #[derive(Debug)]
 pub struct BytecodeFile<'a> {
@@ -25,136 +33,15 @@ pub struct Codegen<'a> {
 }
 
 impl<'a> Codegen<'a> {
-    pub fn new(tir_root: TIRRoot<'a>, type_ref_pool: TypeRefPool<'a>) -> Self {
+    pub fn new(tir_root: TIRRoot<'a>, type_ref_pool: TypeRefPool<'a>, field_ref_pool: FieldRefPool<'a>, method_ref_pool: MethodRefPool<'a>) -> Self {
         Self {
             tir_root,
             type_ref_pool,
-            field_ref_pool: FieldRefPool::new(),
-            method_ref_pool: MethodRefPool::new()
+            field_ref_pool,
+            method_ref_pool
         }
     }
 
-    fn create_field_and_method_refs(&mut self) -> Result<(), CodegenError<'a>> {
-        for type_decl in &self.tir_root.types {
-            match &type_decl.kind {
-                TIRTypeKind::Class { members, .. } => {
-                    let mut field_index = 0;
-                    let mut method_index = 0;
-                    let mut index_in_all_members = 0;
-                    for member in members {
-                        match &member.kind {
-                            TIRMemberKind::Field { name_and_type, .. } => {
-                                let field_ref_index = self.field_ref_pool.field_refs.len();
-
-                                if let Some(_) = self.field_ref_pool.type_ref_index_and_name_to_field_ref_index.insert(
-                                    (type_decl.type_ref_index, name_and_type.name),
-                                    field_ref_index
-                                ) {
-                                    return Err(CodegenError::FieldNameTaken(name_and_type.name));
-                                }
-                                if self.method_ref_pool.name_and_type_ref_index_to_method_ref_indexes.contains_key(
-                                    &(type_decl.type_ref_index, name_and_type.name)
-                                ) {
-                                    return Err(CodegenError::FieldNameTaken(name_and_type.name));
-                                }
-
-                                let mut is_static = false;
-                                for modifier in &member.modifiers {
-                                    match modifier {
-                                        TIRModifier::Static => if is_static {
-                                            return Err(CodegenError::DuplicateModifierOnField(TIRModifier::Static));
-                                        } else {
-                                            is_static = true
-                                        },
-                                        TIRModifier::Abstract => return Err(CodegenError::ModifierNotCompatibleForField(TIRModifier::Abstract)),
-                                        TIRModifier::Native => return Err(CodegenError::ModifierNotCompatibleForField(TIRModifier::Native)),
-                                    }
-                                }
-
-                                self.field_ref_pool.field_refs.push(FieldRef {
-                                    associated_type_ref_index: type_decl.type_ref_index,
-                                    name: name_and_type.name,
-                                    index: field_index,
-                                    index_in_all_members,
-                                    type_info: name_and_type.type_info.clone(),
-                                    is_static
-                                });
-
-                                field_index += 1;
-                            }
-                            TIRMemberKind::Method { name_and_type, parameters, .. } => {
-                                let method_ref_index = self.method_ref_pool.method_refs.len();
-                                let parameters: Vec<TIRTypeInfo> = parameters.iter().map(|p| p.type_info.clone()).collect();
-
-                                if let Some(refs_hashmap) = self.method_ref_pool.name_and_type_ref_index_to_method_ref_indexes.get_mut(
-                                    &(type_decl.type_ref_index, name_and_type.name)
-                                ) {
-                                    if let Some(_) = refs_hashmap.insert(parameters.clone(), method_ref_index) {
-                                        return Err(CodegenError::MethodNameTaken(name_and_type.name));
-                                    }
-                                }
-                                else {
-                                    let mut refs_hashmap = HashMap::new();
-                                    refs_hashmap.insert(parameters.clone(), method_ref_index);
-                                    self.method_ref_pool.name_and_type_ref_index_to_method_ref_indexes.insert(
-                                        (type_decl.type_ref_index, name_and_type.name),
-                                        refs_hashmap
-                                    );
-                                }
-                                if self.field_ref_pool.type_ref_index_and_name_to_field_ref_index.contains_key(
-                                    &(type_decl.type_ref_index, name_and_type.name)
-                                ) {
-                                    return Err(CodegenError::MethodNameTaken(name_and_type.name));
-                                }
-
-                                let mut is_static = false;
-                                let mut is_abstract = false;
-                                let mut is_native = false;
-                                for modifier in &member.modifiers {
-                                    match modifier {
-                                        TIRModifier::Static => if is_static {
-                                            return Err(CodegenError::DuplicateModifierOnField(TIRModifier::Static));
-                                        } else {
-                                            is_static = true
-                                        },
-                                        TIRModifier::Abstract => if is_abstract {
-                                            return Err(CodegenError::DuplicateModifierOnField(TIRModifier::Abstract));
-                                        } else {
-                                            is_abstract = true
-                                        },
-                                        TIRModifier::Native => if is_native {
-                                            return Err(CodegenError::DuplicateModifierOnField(TIRModifier::Native));
-                                        } else {
-                                            is_native = true
-                                        }
-                                    }
-                                }
-
-                                self.method_ref_pool.method_refs.push(MethodRef {
-                                    associated_type_ref_index: type_decl.type_ref_index,
-                                    return_type: name_and_type.type_info.clone(),
-                                    name: name_and_type.name,
-                                    parameters,
-                                    index: method_index,
-                                    index_in_all_members,
-                                    is_static,
-                                    is_abstract,
-                                    is_native
-                                });
-
-                                method_index += 1;
-                            }
-                        }
-
-                        index_in_all_members += 1;
-                    }
-                }
-            }
-         }
-
-        Ok(())
-    }
-
     fn tir_type_info_to_type_info_format(&self, tir_type: &TIRTypeInfo) -> TypeInfoFormat {
         match &tir_type.kind {
             TIRTypeInfoKind::TypeRef { type_ref_index, array_dim, .. } => {
@@ -165,35 +52,64 @@ impl<'a> Codegen<'a> {
                     array_dim: *array_dim as u64
                 }
             }
-            TIRTypeInfoKind::Generic { .. } => todo!("Convert to oolang::Object"),
+            // Generics are erased, their values are references to objects at runtime
+            TIRTypeInfoKind::Generic { array_dim, .. } => {
+                TypeInfoFormat {
+                    kind: TypeInfoKindFormat::Object,
+                    array_dim: *array_dim as u64
+                }
+            }
             TIRTypeInfoKind::Primitive { primitive, array_dim } => {
                 TypeInfoFormat {
-                    kind: match primitive {
-                        PrimitiveType::Void => TypeInfoKindFormat::Void,
-                        PrimitiveType::I64 => TypeInfoKindFormat::I64,
-                        PrimitiveType::I32 => TypeInfoKindFormat::I32,
-                        PrimitiveType::I16 => TypeInfoKindFormat::I16,
-                        PrimitiveType::I8 => TypeInfoKindFormat::I8,
-                        PrimitiveType::U64 => TypeInfoKindFormat::U64,
-                        PrimitiveType::U32 => TypeInfoKindFormat::U32,
-                        PrimitiveType::U16 => TypeInfoKindFormat::U16,
-                        PrimitiveType::U8 => TypeInfoKindFormat::U8,
-                        PrimitiveType::F64 => TypeInfoKindFormat::F64,
-                        PrimitiveType::F32 => TypeInfoKindFormat::F32,
-                        PrimitiveType::Boolean => TypeInfoKindFormat::Boolean,
-                        PrimitiveType::Character => TypeInfoKindFormat::Character
-                    },
+                    kind: self.primitive_to_type_info_kind_format(*primitive),
                     array_dim: *array_dim as u64
                 }
             }
         }
     }
 
-    fn codegen_tir_type(&self, tir_type: &TIRType<'a>, bytecode: &mut Vec<u8>, poolfile_id: u64) -> Result<(), CodegenError<'a>> {
+    fn primitive_to_type_info_kind_format(&self, primitive: PrimitiveType) -> TypeInfoKindFormat {
+        match primitive {
+            PrimitiveType::Void => TypeInfoKindFormat::Void,
+            PrimitiveType::I64 => TypeInfoKindFormat::I64,
+            PrimitiveType::I32 => TypeInfoKindFormat::I32,
+            PrimitiveType::I16 => TypeInfoKindFormat::I16,
+            PrimitiveType::I8 => TypeInfoKindFormat::I8,
+            PrimitiveType::U64 => TypeInfoKindFormat::U64,
+            PrimitiveType::U32 => TypeInfoKindFormat::U32,
+            PrimitiveType::U16 => TypeInfoKindFormat::U16,
+            PrimitiveType::U8 => TypeInfoKindFormat::U8,
+            PrimitiveType::F64 => TypeInfoKindFormat::F64,
+            PrimitiveType::F32 => TypeInfoKindFormat::F32,
+            PrimitiveType::Boolean => TypeInfoKindFormat::Boolean,
+            PrimitiveType::Character => TypeInfoKindFormat::Character,
+            // Never values don't exist at runtime
+            PrimitiveType::Never => unreachable!()
+        }
+    }
+
+    // Visibility is written for the VM to enforce it when other compilation units use the type
+    fn visibility_to_visibility_format(&self, visibility: ASTVisibility) -> VisibilityFormat {
+        match visibility {
+            ASTVisibility::Public => VisibilityFormat::Public,
+            ASTVisibility::Module => VisibilityFormat::Module,
+            ASTVisibility::Private => VisibilityFormat::Private
+        }
+    }
+
+    fn type_info_to_type_ref_index(&self, type_info: &TIRTypeInfo) -> u64 {
+        match &type_info.kind {
+            TIRTypeInfoKind::TypeRef { type_ref_index, .. } => *type_ref_index as u64,
+            _ => unreachable!()
+        }
+    }
+
+    fn codegen_tir_type(&self, tir_type: &TIRType<'a>, bytecode: &mut Vec<u8>, poolfile_id: u64) -> Result<(), CodegenError> {
         match &tir_type.kind {
-            TIRTypeKind::Class { members, super_class } => {
-                // Contains field expression assignments
-                let mut constructor_prefix: Vec<u8> = vec![];
+            TIRTypeKind::Class { members, super_class, impls } => {
+                // Contains field expression assignments, generated at the start of every constructor so that they share its locals
+                let mut constructor_prefix: Vec<(u64, &TIRExpr<'a>)> = vec![];
+                let mut constructor_prefix_local_count = 0;
                 // Contains static field expression assignments
                 let mut static_prefix: Vec<u8> = vec![];
 
@@ -204,44 +120,35 @@ impl<'a> Codegen<'a> {
                 };
 
                 let mut fields = vec![];
-                let mut methods = vec![];
+                let mut constructors = vec![];
 
                 let super_class_type_ref_index = if let Some(sctri) = &class_type_ref.super_class {
-                    match &sctri.kind {
-                        TIRTypeInfoKind::TypeRef { type_ref_index, .. } => Some(*type_ref_index as u64),
-                        _ => unreachable!()
-                    }
+                    Some(self.type_info_to_type_ref_index(sctri))
                 } else {
                     None
                 };
+                let impl_type_ref_indexes = impls.iter().map(|i| self.type_info_to_type_ref_index(i)).collect();
 
                 // TODO : optimize this so that we don't have to iterate through every field in the pool to get the fields of the current type
-                for field_ref in &self.field_ref_pool.field_refs {
+                for (field_ref_index, field_ref) in self.field_ref_pool.field_refs.iter().enumerate() {
                     if field_ref.associated_type_ref_index == tir_type.type_ref_index {
                         fields.push(FieldFormat {
                             name: field_ref.name,
+                            visibility: self.visibility_to_visibility_format(field_ref.visibility),
                             type_info: self.tir_type_info_to_type_info_format(&field_ref.type_info),
                             is_static: field_ref.is_static
                         });
 
                         match &members[field_ref.index_in_all_members].kind {
-                            TIRMemberKind::Field { expression, .. } => {
+                            TIRMemberKind::Field { expression, local_count, .. } => {
                                 if let Some(expression) = expression {
-                                    self.codegen_tir_expr(
-                                        &TIRExpr {
-                                            kind: TIRExprKind::BinOp(
-                                                Box::new(TIRExpr {
-                                                    kind: TIRExprKind::VariableAccess(field_ref.name),
-                                                    span: expression.span
-                                                }),
-                                                TIROperator::Assign,
-                                                Box::new(expression.clone()
-                                                )),
-                                            span: expression.span
-                                        },
-                                        &mut constructor_prefix,
-                                        todo!()
-                                    )?;
+                                    if field_ref.is_static {
+                                        self.codegen_tir_expr(expression, &mut static_prefix, &mut CodegenContext::new(*local_count))?;
+                                        InstructionFormat::SetStaticField { field_ref_index: field_ref_index as u64 }.write(&mut static_prefix);
+                                    } else {
+                                        constructor_prefix.push((field_ref_index as u64, expression));
+                                        constructor_prefix_local_count = constructor_prefix_local_count.max(*local_count);
+                                    }
                                 }
                             }
                             _ => unreachable!()
@@ -249,14 +156,66 @@ impl<'a> Codegen<'a> {
                     }
                 }
 
-                todo!("methods");
+                for member in members {
+                    if let TIRMemberKind::Constructor { parameters, super_call, block, local_count } = &member.kind {
+                        let mut codegen_context = CodegenContext::new(constructor_prefix_local_count.max(*local_count));
+                        constructors.push(ConstructorFormat {
+                            visibility: self.visibility_to_visibility_format(class_type_ref.constructor_visibilities[constructors.len()]),
+                            parameters: parameters.iter().map(|p| self.tir_type_info_to_type_info_format(&p.type_info)).collect(),
+                            bytecode: self.codegen_constructor(super_call, class_type_ref.super_class.as_ref(), &constructor_prefix, block, &mut codegen_context)?
+                        });
+                    }
+                }
+
+                let methods = self.codegen_methods(tir_type.type_ref_index, members)?;
 
                 TypeFileFormat {
                     poolfile_id,
+                    visibility: self.visibility_to_visibility_format(self.type_ref_pool.type_refs[tir_type.type_ref_index].visibility),
                     type_kind: TypeKindFormat::Class {
                         fields,
+                        constructors,
                         methods,
-                        super_class_type_ref_index
+                        super_class_type_ref_index,
+                        impl_type_ref_indexes
+                    }
+                }.write(bytecode);
+            }
+            TIRTypeKind::Interface { members, super_interfaces } => {
+                let methods = self.codegen_methods(tir_type.type_ref_index, members)?;
+                let super_interface_type_ref_indexes = super_interfaces.iter().map(|i| self.type_info_to_type_ref_index(i)).collect();
+
+                TypeFileFormat {
+                    poolfile_id,
+                    visibility: self.visibility_to_visibility_format(self.type_ref_pool.type_refs[tir_type.type_ref_index].visibility),
+                    type_kind: TypeKindFormat::Interface {
+                        methods,
+                        super_interface_type_ref_indexes
+                    }
+                }.write(bytecode);
+            }
+            TIRTypeKind::Enum { .. } => {
+                let enum_type_ref = match &self.type_ref_pool.type_refs[tir_type.type_ref_index].kind {
+                    TypeRefKind::Enum(enum_type_ref) => enum_type_ref,
+                    _ => unreachable!()
+                };
+
+                // The VM tells variants apart by their discriminant, field names are only kept for struct-like variants
+                let variants = enum_type_ref.variants.iter().enumerate().map(|(discriminant, variant)| VariantFormat {
+                    name: variant.name,
+                    discriminant: discriminant as u64,
+                    field_names: match &variant.kind {
+                        EnumVariantRefKind::Struct(names) => names.clone(),
+                        _ => vec![]
+                    },
+                    fields: variant.fields.iter().map(|f| self.tir_type_info_to_type_info_format(f)).collect()
+                }).collect();
+
+                TypeFileFormat {
+                    poolfile_id,
+                    visibility: self.visibility_to_visibility_format(self.type_ref_pool.type_refs[tir_type.type_ref_index].visibility),
+                    type_kind: TypeKindFormat::Enum {
+                        variants
                     }
                 }.write(bytecode);
             }
@@ -265,8 +224,7 @@ impl<'a> Codegen<'a> {
         Ok(())
     }
 
-    pub fn get_bytecode(mut self) -> Result<Vec<BytecodeFile<'a>>, CodegenError<'a>> {
-        self.create_field_and_method_refs()?;
+    pub fn get_bytecode(self) -> Result<Vec<BytecodeFile<'a>>, CodegenError> {
         let mut bytecode_files = vec![];
 
         // TODO : replace by UUID
@@ -288,10 +246,17 @@ impl<'a> Codegen<'a> {
 }
 
 #[derive(Debug)]
-pub enum CodegenError<'a> {
-    MethodNameTaken(&'a str),
-    FieldNameTaken(&'a str),
-    DuplicateModifierOnField(TIRModifier),
-    ModifierNotCompatibleForField(TIRModifier),
-    DuplicateModifierOnMethod(TIRModifier)
+pub enum CodegenError {
+    InvalidOperands(TokenSpan, TIROperator),
+    // Only classes, interfaces and enums can be tested for
+    InvalidTypeTest(TokenSpan)
+}
+
+impl CodegenError {
+    pub fn span(&self) -> TokenSpan {
+        match self {
+            CodegenError::InvalidOperands(span, _) |
+            CodegenError::InvalidTypeTest(span) => *span
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,199 @@
+use crate::codegen::{Codegen, CodegenError};
+use crate::codegen::context::CodegenContext;
+use crate::reporting::TokenSpan;
+use crate::tir::{TIRExpr, TIRPattern, TIRPatternKind, TIRPatternLiteral, TIRMatchArm, TIRTypeInfo, TIRTypeInfoKind, PrimitiveType};
+use crate::type_ref_pool::TypeRefKind;
+use oolang_bytecode::BytecodeFormat;
+use oolang_bytecode::instruction_structure::{InstructionFormat, ConstantFormat, BinaryOpFormat};
+
+// Matches are generated as a chain of tests, each failing test jumps to the next arm
+
+impl<'a> Codegen<'a> {
+    // Pushes the literal, unsuffixed numbers take the type of the value they are compared to
+    fn codegen_pattern_literal(&self, literal: &TIRPatternLiteral, hint: Option<PrimitiveType>, bytecode: &mut Vec<u8>) -> PrimitiveType {
+        let (constant, primitive) = match literal {
+            TIRPatternLiteral::Integer(value, suffix) => {
+                let primitive = suffix.or(hint.filter(|h| h.is_integer())).unwrap_or(PrimitiveType::I64);
+                // Negative values are written in two's complement
+                (ConstantFormat::Integer(*value as u64, self.primitive_to_type_info_kind_format(primitive)), primitive)
+            }
+            TIRPatternLiteral::Float(value, suffix) => {
+                let primitive = suffix.or(hint.filter(|h| h.is_float())).unwrap_or(PrimitiveType::F64);
+                (ConstantFormat::Float(*value, self.primitive_to_type_info_kind_format(primitive)), primitive)
+            }
+            TIRPatternLiteral::Character(character) => (ConstantFormat::Character(*character), PrimitiveType::Character),
+            TIRPatternLiteral::Boolean(boolean) => (ConstantFormat::Boolean(*boolean), PrimitiveType::Boolean),
+            // Tested with IsNull instead
+            TIRPatternLiteral::Null => unreachable!()
+        };
+        InstructionFormat::Constant(constant).write(bytecode);
+        primitive
+    }
+
+    // Jumps to the fail label if "value" doesn't compare to the literal with the operator
+    fn codegen_literal_test(&self, literal: &TIRPatternLiteral, op: BinaryOpFormat, value: u64, hint: Option<PrimitiveType>, fail_label: u64, bytecode: &mut Vec<u8>) {
+        InstructionFormat::LoadLocal { slot: value }.write(bytecode);
+        let operand = self.codegen_pattern_literal(literal, hint, bytecode);
+        InstructionFormat::BinaryOp {
+            op,
+            operand: self.primitive_to_type_info_kind_format(operand)
+        }.write(bytecode);
+        InstructionFormat::JumpIfFalse { label: fail_label }.write(bytecode);
+    }
+
+    // Tests the value stored in the "value" slot, storing the bindings on success and jumping to the fail label otherwise
+    fn codegen_pattern_test(&self, pattern: &TIRPattern, value: u64, hint: Option<&TIRTypeInfo>, fail_label: u64, bytecode: &mut Vec<u8>, codegen_context: &mut CodegenContext<'a>) -> Result<(), CodegenError> {
+        let primitive_hint = hint.and_then(|h| self.operand_primitive(h));
+        match &pattern.kind {
+            TIRPatternKind::Wildcard => {}
+            TIRPatternKind::Binding(slot) => {
+                InstructionFormat::LoadLocal { slot: value }.write(bytecode);
+                InstructionFormat::StoreLocal { slot: *slot as u64 }.write(bytecode);
+            }
+            TIRPatternKind::Literal(TIRPatternLiteral::Null) => {
+                InstructionFormat::LoadLocal { slot: value }.write(bytecode);
+                InstructionFormat::IsNull.write(bytecode);
+                InstructionFormat::JumpIfFalse { label: fail_label }.write(bytecode);
+            }
+            TIRPatternKind::Literal(literal) =>
+                self.codegen_literal_test(literal, BinaryOpFormat::Eq, value, primitive_hint, fail_label, bytecode),
+            TIRPatternKind::Range(start, end, inclusive) => {
+                self.codegen_literal_test(start, BinaryOpFormat::GtEq, value, primitive_hint, fail_label, bytecode);
+                let op = if *inclusive { BinaryOpFormat::LsEq } else { BinaryOpFormat::Ls };
+                self.codegen_literal_test(end, op, value, primitive_hint, fail_label, bytecode);
+            }
+            TIRPatternKind::EnumVariant(type_ref_index, variant_index, fields) => {
+                InstructionFormat::LoadLocal { slot: value }.write(bytecode);
+                InstructionFormat::EnumDiscriminant.write(bytecode);
+                InstructionFormat::Constant(ConstantFormat::Integer(*variant_index as u64, self.primitive_to_type_info_kind_format(PrimitiveType::U64))).write(bytecode);
+                InstructionFormat::BinaryOp {
+                    op: BinaryOpFormat::Eq,
+                    operand: self.primitive_to_type_info_kind_format(PrimitiveType::U64)
+                }.write(bytecode);
+                InstructionFormat::JumpIfFalse { label: fail_label }.write(bytecode);
+
+                let field_types = match &self.type_ref_pool.type_refs[*type_ref_index].kind {
+                    TypeRefKind::Enum(enum_type_ref) => &enum_type_ref.variants[*variant_index].fields,
+                    _ => unreachable!()
+                };
+                for (index, field) in fields.iter().enumerate() {
+                    if let TIRPatternKind::Wildcard = field.kind {
+                        continue;
+                    }
+                    let field_value = codegen_context.allocate_local();
+                    InstructionFormat::LoadLocal { slot: value }.write(bytecode);
+                    InstructionFormat::EnumField { index: index as u64 }.write(bytecode);
+                    // Fields declared as generics of the enum are unboxed to the type the matched value gives them
+                    let used_as = self.member_used_as(&field_types[index], hint, &field_types[index]);
+                    let field_type = self.codegen_unbox(&field_types[index], used_as, bytecode);
+                    InstructionFormat::StoreLocal { slot: field_value }.write(bytecode);
+                    self.codegen_pattern_test(field, field_value, Some(&field_type), fail_label, bytecode, codegen_context)?;
+                }
+            }
+            TIRPatternKind::TypeTest(slot, type_info) => {
+                match &type_info.kind {
+                    TIRTypeInfoKind::TypeRef { array_dim: 0, .. } => {}
+                    _ => return Err(CodegenError::InvalidTypeTest(pattern.span))
+                }
+                InstructionFormat::LoadLocal { slot: value }.write(bytecode);
+                InstructionFormat::InstanceOf { type_ref_index: self.type_info_to_type_ref_index(type_info) }.write(bytecode);
+                InstructionFormat::JumpIfFalse { label: fail_label }.write(bytecode);
+                if let Some(slot) = slot {
+                    InstructionFormat::LoadLocal { slot: value }.write(bytecode);
+                    InstructionFormat::StoreLocal { slot: *slot as u64 }.write(bytecode);
+                }
+            }
+            TIRPatternKind::Or(alternatives) => {
+                // Every alternative but the last one falls through to the next on failure
+                let success_label = codegen_context.new_label();
+                let (last, alternatives) = alternatives.split_last().unwrap();
+                for alternative in alternatives {
+                    let next_label = codegen_context.new_label();
+                    self.codegen_pattern_test(alternative, value, hint, next_label, bytecode, codegen_context)?;
+                    InstructionFormat::Jump { label: success_label }.write(bytecode);
+                    InstructionFormat::Label { label: next_label }.write(bytecode);
+                }
+                self.codegen_pattern_test(last, value, hint, fail_label, bytecode, codegen_context)?;
+                InstructionFormat::Label { label: success_label }.write(bytecode);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn codegen_match(&self, scrutinee: &TIRExpr<'a>, arms: &[TIRMatchArm<'a>], span: TokenSpan, bytecode: &mut Vec<u8>, codegen_context: &mut CodegenContext<'a>) -> Result<TIRTypeInfo, CodegenError> {
+        let scrutinee_type = self.codegen_tir_expr(scrutinee, bytecode, codegen_context)?;
+        let value = codegen_context.allocate_local();
+        InstructionFormat::StoreLocal { slot: value }.write(bytecode);
+
+        let end_label = codegen_context.new_label();
+        let mut result_type = None;
+        for arm in arms {
+            let next_label = codegen_context.new_label();
+
+            self.codegen_pattern_test(&arm.pattern, value, Some(&scrutinee_type), next_label, bytecode, codegen_context)?;
+            if let Some(guard) = &arm.guard {
+                self.codegen_tir_expr(guard, bytecode, codegen_context)?;
+                InstructionFormat::JumpIfFalse { label: next_label }.write(bytecode);
+            }
+            // TODO : arms have to agree on a type once expressions are type checked
+            let body_type = self.codegen_tir_expr(&arm.body, bytecode, codegen_context)?;
+            result_type.get_or_insert(body_type);
+            InstructionFormat::Jump { label: end_label }.write(bytecode);
+            InstructionFormat::Label { label: next_label }.write(bytecode);
+        }
+        // The type checker checked exhaustiveness, so this is never reached
+        InstructionFormat::Unreachable.write(bytecode);
+        InstructionFormat::Label { label: end_label }.write(bytecode);
+
+        Ok(result_type.unwrap_or(self.primitive_type_info(PrimitiveType::Void, span)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{compile, generates};
+    use oolang_bytecode::instruction_structure::{InstructionFormat, ConstantFormat, BinaryOpFormat};
+    use oolang_bytecode::typefile_structure::TypeInfoKindFormat;
+
+    #[test]
+    fn matches_are_generated() {
+        compile("
+            mod a;
+            enum Opt { Some(bool), None }
+            class A {
+                fn f(x: u8, o: Opt, c: char) -> u64 {
+                    let first: u64 = match x { 0..=127 => 1, 128..=255 => 2 };
+                    let second: u64 = match o { Opt::Some(true) | Opt::None => 3, Opt::Some(false) => 4 };
+                    match c { 'a'..='z' if first == 1 => second, _ => 5 }
+                }
+            }
+        ").unwrap();
+    }
+
+    #[test]
+    fn generic_enum_payloads_are_boxed_and_unboxed() {
+        let bytecode_files = compile("
+            mod a;
+            enum Opt<T> { Some(T), None }
+            class A {
+                fn wrap() -> Opt<u64> { Opt::Some(3u8) }
+                fn is_three(o: Opt<u8>) -> bool { match o { Opt::Some(3) => true, _ => false } }
+            }
+        ").unwrap();
+        assert!(generates(&bytecode_files, "A", &[
+            InstructionFormat::Constant(ConstantFormat::Integer(3, TypeInfoKindFormat::U8)),
+            InstructionFormat::Convert { from: TypeInfoKindFormat::U8, to: TypeInfoKindFormat::U64 },
+            InstructionFormat::Box { operand: TypeInfoKindFormat::U64 },
+            InstructionFormat::EnumConstruct { type_ref_index: 0, discriminant: 0 }
+        ]));
+        assert!(generates(&bytecode_files, "A", &[
+            InstructionFormat::EnumField { index: 0 },
+            InstructionFormat::Unbox { operand: TypeInfoKindFormat::U8 },
+            InstructionFormat::StoreLocal { slot: 3 },
+            InstructionFormat::LoadLocal { slot: 3 },
+            InstructionFormat::Constant(ConstantFormat::Integer(3, TypeInfoKindFormat::U8)),
+            InstructionFormat::BinaryOp { op: BinaryOpFormat::Eq, operand: TypeInfoKindFormat::U8 }
+        ]));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::tir::TIRTypeInfo;
+use crate::ast::ASTVisibility;
 use std::collections::HashMap;
 
 pub struct FieldRef<'a> {
@@ -9,6 +10,7 @@ pub struct FieldRef<'a> {
     pub index: usize,
     pub index_in_all_members: usize,
 
+    pub visibility: ASTVisibility,
     pub is_static: bool
 }
 
@@ -24,4 +26,9 @@ impl<'a> FieldRefPool<'a> {
             type_ref_index_and_name_to_field_ref_index: HashMap::new()
         }
     }
+
+    // First field with that name in the hierarchy (see TypeRefPool::type_hierarchy)
+    pub fn find_field(&self, hierarchy: &[usize], name: &str) -> Option<usize> {
+        hierarchy.iter().find_map(|type_ref_index| self.type_ref_index_and_name_to_field_ref_index.get(&(*type_ref_index, name)).copied())
+    }
 }
\ No newline at end of file
@@ -3,6 +3,8 @@ use crate::lexer::DelimTokenKind::*;
 use crate::lexer::KeywordTokenKind::*;
 use crate::lexer::TokenKind::*;
 use crate::reporting::CharSpan;
+use crate::tir::PrimitiveType;
+use std::fmt::{Display, Formatter};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum BinOpTokenKind {
@@ -10,6 +12,12 @@ pub enum BinOpTokenKind {
     Minus,
     Star,
     Slash,
+    Percent,
+    And,
+    Or,
+    Caret,
+    Shl,
+    Shr,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -37,6 +45,8 @@ pub enum KeywordTokenKind {
 
     Mod,
     Use,
+    As,
+    Type,
 
     If,
     Else,
@@ -44,6 +54,13 @@ pub enum KeywordTokenKind {
     Match,
     Loop,
     For,
+    In,
+    Break,
+    Continue,
+    Return,
+    New,
+    This,
+    Super,
 
     Let,
 }
@@ -53,6 +70,7 @@ pub enum TokenKind {
     Eq,
     EqEq,
     Not,
+    Tilde,
     NotEq,
     Gt,
     GtEq,
@@ -73,7 +91,10 @@ pub enum TokenKind {
     ColonColon,
     Comma,
     Dot,
+    DotDot,
+    DotDotEq,
     Arrow,
+    FatArrow,
 
     PlusPlus,
     MinusMinus,
@@ -85,16 +106,195 @@ pub enum TokenKind {
     False,
     Null,
     StringLiteral,
+    CharLiteral,
+    // "'outer", the string includes the quote
+    Label,
 
     Keyword(KeywordTokenKind),
 
+    // Span that could not be lexed, see Lexer::lex_recovering
+    Error,
+
     EndOfFile,
 }
 
+// Words that can't be used as names, they are never lexed as identifiers
+pub fn is_reserved_word(name: &str) -> bool {
+    KeywordTokenKind::from_name(name).is_some() || matches!(name, "true" | "false" | "null")
+}
+
+impl BinOpTokenKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Plus => "+",
+            Minus => "-",
+            Star => "*",
+            Slash => "/",
+            Percent => "%",
+            And => "&",
+            Or => "|",
+            Caret => "^",
+            Shl => "<<",
+            Shr => ">>",
+        }
+    }
+}
+
+impl KeywordTokenKind {
+    pub fn from_name(name: &str) -> Option<KeywordTokenKind> {
+        Some(match name {
+            "pub" => Pub,
+            "priv" => Priv,
+            "abstract" => Abstract,
+            "static" => Static,
+            "native" => Native,
+            "class" => Class,
+            "inter" => Inter,
+            "enum" => Enum,
+            "impl" => Impl,
+            "where" => Where,
+            "fn" => Fn,
+            "mod" => Mod,
+            "use" => Use,
+            "as" => As,
+            "type" => Type,
+            "if" => If,
+            "else" => Else,
+            "while" => While,
+            "match" => Match,
+            "loop" => Loop,
+            "for" => For,
+            "in" => In,
+            "break" => Break,
+            "continue" => Continue,
+            "return" => Return,
+            "new" => New,
+            "this" => This,
+            "super" => Super,
+            "let" => Let,
+            _ => return None
+        })
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Pub => "pub",
+            Priv => "priv",
+            Abstract => "abstract",
+            Static => "static",
+            Native => "native",
+            Class => "class",
+            Inter => "inter",
+            Enum => "enum",
+            Impl => "impl",
+            Where => "where",
+            Fn => "fn",
+            Mod => "mod",
+            Use => "use",
+            As => "as",
+            Type => "type",
+            If => "if",
+            Else => "else",
+            While => "while",
+            Match => "match",
+            Loop => "loop",
+            For => "for",
+            In => "in",
+            Break => "break",
+            Continue => "continue",
+            Return => "return",
+            New => "new",
+            This => "this",
+            Super => "super",
+            Let => "let",
+        }
+    }
+}
+
+// Used in diagnostics, e.g. "expected `;`, found identifier"
+impl Display for TokenKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            Eq => "=",
+            EqEq => "==",
+            Not => "!",
+            Tilde => "~",
+            NotEq => "!=",
+            Gt => ">",
+            GtEq => ">=",
+            Ls => "<",
+            LsEq => "<=",
+            OrOr => "||",
+            AndAnd => "&&",
+            BinOp(op) => op.as_str(),
+            BinOpAssign(op) => return write!(f, "`{}=`", op.as_str()),
+            OpeningDelim(Paren) => "(",
+            OpeningDelim(SBracket) => "[",
+            OpeningDelim(CBracket) => "{",
+            ClosingDelim(Paren) => ")",
+            ClosingDelim(SBracket) => "]",
+            ClosingDelim(CBracket) => "}",
+            Semicolon => ";",
+            Colon => ":",
+            Inheritance => return write!(f, "inheritance"),
+            ColonColon => "::",
+            Comma => ",",
+            Dot => ".",
+            DotDot => "..",
+            DotDotEq => "..=",
+            Arrow => "->",
+            FatArrow => "=>",
+            PlusPlus => "++",
+            MinusMinus => "--",
+            Keyword(keyword) => keyword.as_str(),
+            True => "true",
+            False => "false",
+            Null => "null",
+            Num => return write!(f, "integer literal"),
+            Float => return write!(f, "float literal"),
+            Ident => return write!(f, "identifier"),
+            StringLiteral => return write!(f, "string literal"),
+            CharLiteral => return write!(f, "character literal"),
+            Label => return write!(f, "label"),
+            Error => return write!(f, "invalid token"),
+            EndOfFile => return write!(f, "end of file"),
+        };
+        write!(f, "`{}`", symbol)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DocCommentKind {
+    // "///", documents the item that follows
+    Outer,
+    // "//!", documents the enclosing item
+    Inner,
+}
+
+pub struct DocComment<'a> {
+    pub kind: DocCommentKind,
+    pub span: CharSpan,
+    // Text after the "///" or "//!" marker
+    pub string: &'a str,
+}
+
+// Decoded value of literal tokens
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenValue {
+    None,
+    String(String),
+    Char(char),
+    Integer(u64, Option<PrimitiveType>),
+    Float(f64, Option<PrimitiveType>),
+}
+
 pub struct Token<'a> {
     pub kind: TokenKind,
     pub span: CharSpan,
     pub string: &'a str,
+    pub value: TokenValue,
+    // Doc comments found between the previous token and this one
+    pub doc_comments: Vec<DocComment<'a>>,
 }
 
 pub struct Lexer<'a> {
@@ -102,6 +302,8 @@ pub struct Lexer<'a> {
     source: &'a str,
     tokens: Vec<Token<'a>>,
     bytes: &'a [u8],
+    pending_doc_comments: Vec<DocComment<'a>>,
+    errors: Vec<LexerError>,
 }
 
 impl<'a> Lexer<'a> {
@@ -111,23 +313,24 @@ impl<'a> Lexer<'a> {
             source,
             tokens: vec![],
             bytes: source.as_bytes(),
+            pending_doc_comments: vec![],
+            errors: vec![],
         }
     }
 
-    fn scan_token(&mut self) -> Result<(), LexerError> {
-        while !self.is_at_end() && char::from(self.peek()).is_whitespace() {
-            self.advance();
+    fn skip_whitespace(&mut self) {
+        while !self.is_at_end() && self.peek_char().is_whitespace() {
+            self.advance_char();
         }
-        if self.is_at_end() {
-            return Ok(());
-        }
-
-        let base = self.current_byte;
+    }
 
+    // Errors inside of string and char literals are recorded directly so that scanning can go on
+    // until the closing quote, the whole literal then becomes an error token
+    fn scan_token(&mut self, base: usize) -> Result<(), LexerError> {
         match self.advance() {
             b'+' => Ok(self.pick_3(base, b'=', b'+', BinOpAssign(Plus), PlusPlus, BinOp(Plus))),
             b'-' => {
-                if self.peek() == b'>' {
+                if !self.is_at_end() && self.peek() == b'>' {
                     self.advance();
                     Ok(self.add(base, Arrow))
                 } else {
@@ -142,13 +345,41 @@ impl<'a> Lexer<'a> {
                 }
             }
             b'*' => Ok(self.pick_2(base, b'=', BinOpAssign(Star), BinOp(Star))),
-            b'/' => Ok(self.pick_2(base, b'=', BinOpAssign(Slash), BinOp(Slash))),
-            b'=' => Ok(self.pick_2(base, b'=', EqEq, Eq)),
+            b'/' => {
+                if !self.is_at_end() && self.peek() == b'/' {
+                    self.advance();
+                    Ok(self.scan_line_comment(base))
+                } else if !self.is_at_end() && self.peek() == b'*' {
+                    self.advance();
+                    self.scan_block_comment(base)
+                } else {
+                    Ok(self.pick_2(base, b'=', BinOpAssign(Slash), BinOp(Slash)))
+                }
+            }
+            b'=' => Ok(self.pick_3(base, b'=', b'>', EqEq, FatArrow, Eq)),
             b'!' => Ok(self.pick_2(base, b'=', NotEq, Not)),
-            b'>' => Ok(self.pick_2(base, b'=', GtEq, Gt)),
-            b'<' => Ok(self.pick_2(base, b'=', LsEq, Ls)),
-            b'|' => self.advance_if(b'|', base, OrOr),
-            b'&' => self.advance_if(b'&', base, AndAnd),
+            b'%' => Ok(self.pick_2(base, b'=', BinOpAssign(Percent), BinOp(Percent))),
+            b'^' => Ok(self.pick_2(base, b'=', BinOpAssign(Caret), BinOp(Caret))),
+            b'~' => Ok(self.add(base, Tilde)),
+            b'>' => {
+                // The parser splits ">>" back up when closing nested generics
+                if !self.is_at_end() && self.peek() == b'>' {
+                    self.advance();
+                    Ok(self.pick_2(base, b'=', BinOpAssign(Shr), BinOp(Shr)))
+                } else {
+                    Ok(self.pick_2(base, b'=', GtEq, Gt))
+                }
+            }
+            b'<' => {
+                if !self.is_at_end() && self.peek() == b'<' {
+                    self.advance();
+                    Ok(self.pick_2(base, b'=', BinOpAssign(Shl), BinOp(Shl)))
+                } else {
+                    Ok(self.pick_2(base, b'=', LsEq, Ls))
+                }
+            }
+            b'|' => Ok(self.pick_3(base, b'|', b'=', OrOr, BinOpAssign(Or), BinOp(Or))),
+            b'&' => Ok(self.pick_3(base, b'&', b'=', AndAnd, BinOpAssign(And), BinOp(And))),
             b'(' => Ok(self.add(base, OpeningDelim(Paren))),
             b')' => Ok(self.add(base, ClosingDelim(Paren))),
             b'[' => Ok(self.add(base, OpeningDelim(SBracket))),
@@ -158,94 +389,382 @@ impl<'a> Lexer<'a> {
             b';' => Ok(self.add(base, Semicolon)),
             b':' => Ok(self.pick_2(base, b':', ColonColon, Colon)),
             b',' => Ok(self.add(base, Comma)),
-            b'.' => Ok(self.add(base, Dot)),
+            b'.' => {
+                if !self.is_at_end() && self.peek() == b'.' {
+                    self.advance();
+                    Ok(self.pick_2(base, b'=', DotDotEq, DotDot))
+                } else {
+                    Ok(self.add(base, Dot))
+                }
+            }
             b'"' => {
-                macro_rules! err_if_at_end {
-                    () => {
-                        if self.is_at_end() {
-                            return Err(LexerError {
-                                kind: LexerErrorKind::UnterminatedString,
-                                pos: base,
-                            });
+                let mut value = String::new();
+                let mut run_base = self.current_byte;
+                let mut valid = true;
+                loop {
+                    if self.is_at_end() {
+                        return Err(LexerError {
+                            kind: LexerErrorKind::UnterminatedString,
+                            pos: base,
+                        });
+                    }
+                    match self.peek() {
+                        b'"' => break,
+                        b'\\' => {
+                            value.push_str(&self.source[run_base..self.current_byte]);
+                            match self.scan_escape() {
+                                Ok(c) => value.push(c),
+                                Err(error) => {
+                                    self.errors.push(error);
+                                    valid = false;
+                                }
+                            }
+                            run_base = self.current_byte;
                         }
-                    };
-                }
-                err_if_at_end!();
-                while self.peek() != b'"' {
-                    self.advance();
-                    err_if_at_end!();
+                        _ => {
+                            self.advance();
+                        }
+                    }
                 }
+                value.push_str(&self.source[run_base..self.current_byte]);
                 self.advance();
-                Ok(self.add(base, StringLiteral))
+                if valid {
+                    self.add_with_value(base, StringLiteral, TokenValue::String(value));
+                } else {
+                    self.add(base, Error);
+                }
+                Ok(())
             }
-            c if char::from(c).is_ascii_digit() => {
-                while !self.is_at_end() && char::from(self.peek()).is_ascii_digit() {
-                    self.advance();
+            b'\'' => {
+                // "'outer" is a label, unless the quote is closed right after one character like in 'a'
+                if !self.is_at_end() && Lexer::is_valid_ident(self.peek_char(), true) {
+                    let after = self.current_byte + self.peek_char().len_utf8();
+                    if self.bytes.get(after) != Some(&b'\'') {
+                        self.advance_char();
+                        while !self.is_at_end() && Lexer::is_valid_ident(self.peek_char(), false) {
+                            self.advance_char();
+                        }
+                        if !self.is_at_end() && self.peek() == b'\'' {
+                            self.advance();
+                            return Err(LexerError {
+                                kind: LexerErrorKind::CharTooLong,
+                                pos: base,
+                            });
+                        }
+                        self.add(base, Label);
+                        return Ok(());
+                    }
                 }
-
-                if self.is_at_end() {
-                    self.add(base, Num);
-                    return Ok(());
+                if self.is_at_end() || self.peek() == b'\n' {
+                    return Err(LexerError {
+                        kind: LexerErrorKind::UnterminatedChar,
+                        pos: base,
+                    });
                 }
-                if self.peek() != b'.' {
-                    self.add(base, Num);
-                    return Ok(());
+                let value = match self.peek() {
+                    b'\'' => {
+                        self.advance();
+                        return Err(LexerError {
+                            kind: LexerErrorKind::EmptyChar,
+                            pos: base,
+                        });
+                    }
+                    b'\\' => self.scan_escape(),
+                    _ => Ok(self.advance_char()),
+                };
+                if self.is_at_end() || self.peek() != b'\'' {
+                    // Tell apart a literal that is too long from a missing closing quote
+                    while !self.is_at_end() && self.peek() != b'\'' && self.peek() != b'\n' {
+                        self.advance();
+                    }
+                    let too_long = !self.is_at_end() && self.peek() == b'\'';
+                    if too_long {
+                        self.advance();
+                    }
+                    return Err(LexerError {
+                        kind: if too_long {
+                            LexerErrorKind::CharTooLong
+                        } else {
+                            LexerErrorKind::UnterminatedChar
+                        },
+                        pos: base,
+                    });
                 }
                 self.advance();
-                while !self.is_at_end() && char::from(self.peek()).is_ascii_digit() {
-                    self.advance();
+                match value {
+                    Ok(value) => self.add_with_value(base, CharLiteral, TokenValue::Char(value)),
+                    Err(error) => {
+                        self.errors.push(error);
+                        self.add(base, Error);
+                    }
                 }
-                self.add(base, Float);
                 Ok(())
             }
-            c if Lexer::is_valid_ident(c, true) => {
-                while !self.is_at_end() && Lexer::is_valid_ident(self.peek(), false) {
-                    self.advance();
+            c if char::from(c).is_ascii_digit() => self.scan_number(base, c),
+            _ => {
+                // The first byte may only be the start of a multi-byte character
+                self.current_byte = base;
+                if !Lexer::is_valid_ident(self.advance_char(), true) {
+                    return Err(LexerError {
+                        kind: LexerErrorKind::UnrecognizedCharacter,
+                        pos: base,
+                    });
+                }
+                while !self.is_at_end() && Lexer::is_valid_ident(self.peek_char(), false) {
+                    self.advance_char();
                 }
 
                 let result = &self.source[base..self.current_byte];
                 match result {
-                    "pub" => self.add(base, Keyword(Pub)),
-                    "priv" => self.add(base, Keyword(Priv)),
-                    "abstract" => self.add(base, Keyword(Abstract)),
-                    "static" => self.add(base, Keyword(Static)),
-                    "native" => self.add(base, Keyword(Native)),
-                    "class" => self.add(base, Keyword(Class)),
-                    "inter" => self.add(base, Keyword(Inter)),
-                    "enum" => self.add(base, Keyword(Enum)),
-                    "impl" => self.add(base, Keyword(Impl)),
-                    "fn" => self.add(base, Keyword(Fn)),
-                    "mod" => self.add(base, Keyword(Mod)),
-                    "use" => self.add(base, Keyword(Use)),
-                    "if" => self.add(base, Keyword(If)),
-                    "else" => self.add(base, Keyword(Else)),
-                    "while" => self.add(base, Keyword(While)),
-                    "match" => self.add(base, Keyword(Match)),
-                    "loop" => self.add(base, Keyword(Loop)),
-                    "for" => self.add(base, Keyword(For)),
-                    "let" => self.add(base, Keyword(Let)),
-
                     "true" => self.add(base, True),
                     "false" => self.add(base, False),
                     "null" => self.add(base, Null),
-                    _ => self.add(base, Ident),
+                    _ => match KeywordTokenKind::from_name(result) {
+                        Some(keyword) => self.add(base, Keyword(keyword)),
+                        None => self.add(base, Ident)
+                    },
                 }
 
                 Ok(())
             }
+        }
+    }
+
+    // Called after the leading "//" has been consumed
+    fn scan_line_comment(&mut self, base: usize) {
+        let kind = if !self.is_at_end() && self.peek() == b'!' {
+            Some(DocCommentKind::Inner)
+        } else if !self.is_at_end()
+            && self.peek() == b'/'
+            && (self.current_byte + 1 >= self.bytes.len() || self.bytes[self.current_byte + 1] != b'/')
+        {
+            // "////" and more is a regular comment
+            Some(DocCommentKind::Outer)
+        } else {
+            None
+        };
+        if kind.is_some() {
+            self.advance();
+        }
+
+        let text_base = self.current_byte;
+        while !self.is_at_end() && self.peek() != b'\n' {
+            self.advance();
+        }
+
+        if let Some(kind) = kind {
+            self.pending_doc_comments.push(DocComment {
+                kind,
+                span: CharSpan {
+                    base,
+                    len: self.current_byte - base,
+                },
+                string: &self.source[text_base..self.current_byte],
+            });
+        }
+    }
+
+    // Called after the leading "/*" has been consumed, block comments can be nested
+    fn scan_block_comment(&mut self, base: usize) -> Result<(), LexerError> {
+        let mut depth = 1;
+        while depth > 0 {
+            if self.is_at_end() {
+                return Err(LexerError {
+                    kind: LexerErrorKind::UnterminatedBlockComment,
+                    pos: base,
+                });
+            }
+            match self.advance() {
+                b'/' if !self.is_at_end() && self.peek() == b'*' => {
+                    self.advance();
+                    depth += 1;
+                }
+                b'*' if !self.is_at_end() && self.peek() == b'/' => {
+                    self.advance();
+                    depth -= 1;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    // Called after the first digit has been consumed
+    fn scan_number(&mut self, base: usize, first: u8) -> Result<(), LexerError> {
+        let radix = if first == b'0' && !self.is_at_end() {
+            match self.peek() {
+                b'x' => 16,
+                b'o' => 8,
+                b'b' => 2,
+                _ => 10,
+            }
+        } else {
+            10
+        };
+
+        let digits_base = if radix == 10 {
+            base
+        } else {
+            self.advance();
+            self.current_byte
+        };
+        self.scan_digits(radix)?;
+        let mut is_float = false;
+        if radix == 10 {
+            // "1..2" is a range and "1.foo()" a call, so the dot has to be followed by a digit
+            if self.peek_at(0) == Some(b'.') && self.peek_at(1).map_or(false, |c| c.is_ascii_digit()) {
+                self.advance();
+                self.scan_digits(10)?;
+                is_float = true;
+            }
+            if let Some(b'e') | Some(b'E') = self.peek_at(0) {
+                let sign_len = match self.peek_at(1) {
+                    Some(b'+') | Some(b'-') => 1,
+                    _ => 0,
+                };
+                if self.peek_at(1 + sign_len).map_or(false, |c| c.is_ascii_digit()) {
+                    for _ in 0..1 + sign_len {
+                        self.advance();
+                    }
+                    self.scan_digits(10)?;
+                    is_float = true;
+                }
+            }
+        }
+        let digits: String = self.source[digits_base..self.current_byte]
+            .chars()
+            .filter(|c| *c != '_')
+            .collect();
+        if digits.is_empty() {
+            return Err(LexerError {
+                kind: LexerErrorKind::MissingDigits,
+                pos: base,
+            });
+        }
+
+        let suffix_base = self.current_byte;
+        while !self.is_at_end() && Lexer::is_valid_ident(self.peek_char(), false) {
+            self.advance_char();
+        }
+        let suffix = match &self.source[suffix_base..self.current_byte] {
+            "" => None,
+            "i64" => Some(PrimitiveType::I64),
+            "i32" => Some(PrimitiveType::I32),
+            "i16" => Some(PrimitiveType::I16),
+            "i8" => Some(PrimitiveType::I8),
+            "u64" => Some(PrimitiveType::U64),
+            "u32" => Some(PrimitiveType::U32),
+            "u16" => Some(PrimitiveType::U16),
+            "u8" => Some(PrimitiveType::U8),
+            "f64" if radix == 10 => Some(PrimitiveType::F64),
+            "f32" if radix == 10 => Some(PrimitiveType::F32),
+            _ => {
+                return Err(LexerError {
+                    kind: LexerErrorKind::InvalidSuffix,
+                    pos: suffix_base,
+                })
+            }
+        };
+        let float_suffix = match suffix {
+            Some(PrimitiveType::F64) | Some(PrimitiveType::F32) => true,
+            _ => false,
+        };
+        if is_float && suffix.is_some() && !float_suffix {
+            return Err(LexerError {
+                kind: LexerErrorKind::InvalidSuffix,
+                pos: suffix_base,
+            });
+        }
+
+        if is_float || float_suffix {
+            // Only fails on malformed input, which the scanning above already rules out
+            let value = digits.parse::<f64>().unwrap();
+            self.add_with_value(base, Float, TokenValue::Float(value, suffix));
+        } else {
+            let value = u64::from_str_radix(&digits, radix).map_err(|_| LexerError {
+                kind: LexerErrorKind::IntegerTooLarge,
+                pos: base,
+            })?;
+            self.add_with_value(base, Num, TokenValue::Integer(value, suffix));
+        }
+        Ok(())
+    }
+
+    // Digits can be separated by underscores
+    fn scan_digits(&mut self, radix: u32) -> Result<(), LexerError> {
+        while !self.is_at_end() {
+            let c = char::from(self.peek());
+            if c == '_' || c.is_digit(radix) {
+                self.advance();
+            } else if c.is_ascii_digit() {
+                return Err(LexerError {
+                    kind: LexerErrorKind::InvalidDigit,
+                    pos: self.current_byte,
+                });
+            } else {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Called with the backslash as the current byte, returns the decoded character
+    fn scan_escape(&mut self) -> Result<char, LexerError> {
+        let base = self.current_byte;
+        self.advance();
+        if self.is_at_end() {
+            return Err(LexerError {
+                kind: LexerErrorKind::InvalidEscape,
+                pos: base,
+            });
+        }
+        match self.advance() {
+            b'n' => Ok('\n'),
+            b't' => Ok('\t'),
+            b'r' => Ok('\r'),
+            b'0' => Ok('\0'),
+            b'\\' => Ok('\\'),
+            b'"' => Ok('"'),
+            b'\'' => Ok('\''),
+            b'u' => {
+                let err = LexerError {
+                    kind: LexerErrorKind::InvalidUnicodeEscape,
+                    pos: base,
+                };
+                if self.is_at_end() || self.peek() != b'{' {
+                    return Err(err);
+                }
+                self.advance();
+                let digits_base = self.current_byte;
+                while !self.is_at_end() && self.peek().is_ascii_hexdigit() {
+                    self.advance();
+                }
+                let digits = &self.source[digits_base..self.current_byte];
+                if digits.is_empty() || digits.len() > 6 || self.is_at_end() || self.peek() != b'}' {
+                    return Err(err);
+                }
+                self.advance();
+                u32::from_str_radix(digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or(err)
+            }
             _ => Err(LexerError {
-                kind: LexerErrorKind::UnrecognizedCharacter,
+                kind: LexerErrorKind::InvalidEscape,
                 pos: base,
             }),
         }
     }
 
-    fn is_valid_ident(c: u8, first: bool) -> bool {
-        c == b'_'
+    // Approximates XID_Start and XID_Continue with the alphabetic and alphanumeric properties
+    fn is_valid_ident(c: char, first: bool) -> bool {
+        c == '_'
             || if first {
-                char::from(c).is_ascii_alphabetic()
+                c.is_alphabetic()
             } else {
-                char::from(c).is_ascii_alphanumeric()
+                c.is_alphanumeric()
             }
     }
 
@@ -259,25 +778,25 @@ impl<'a> Lexer<'a> {
         r
     }
 
-    fn advance_if(
-        &mut self,
-        condition: u8,
-        base: usize,
-        kind: TokenKind,
-    ) -> Result<(), LexerError> {
-        if self.peek() == condition {
-            self.advance();
-            self.add(base, kind);
-            Ok(())
-        } else {
-            Err(LexerError {
-                kind: LexerErrorKind::UnrecognizedCharacter,
-                pos: self.current_byte,
-            })
-        }
+    fn peek_at(&self, offset: usize) -> Option<u8> {
+        self.bytes.get(self.current_byte + offset).copied()
+    }
+
+    fn peek_char(&self) -> char {
+        self.source[self.current_byte..].chars().next().unwrap()
+    }
+
+    fn advance_char(&mut self) -> char {
+        let c = self.peek_char();
+        self.current_byte += c.len_utf8();
+        c
     }
 
     fn add(&mut self, base: usize, kind: TokenKind) {
+        self.add_with_value(base, kind, TokenValue::None)
+    }
+
+    fn add_with_value(&mut self, base: usize, kind: TokenKind, value: TokenValue) {
         self.tokens.push(Token {
             kind,
             span: CharSpan {
@@ -285,24 +804,19 @@ impl<'a> Lexer<'a> {
                 len: self.current_byte - base,
             },
             string: &self.source[base..self.current_byte],
+            value,
+            doc_comments: std::mem::take(&mut self.pending_doc_comments),
         })
     }
 
     fn pick_2(&mut self, base: usize, condition: u8, ok: TokenKind, not_ok: TokenKind) {
-        let kind = if self.peek() == condition {
+        let kind = if !self.is_at_end() && self.peek() == condition {
             self.advance();
             ok
         } else {
             not_ok
         };
-        self.tokens.push(Token {
-            kind,
-            span: CharSpan {
-                base,
-                len: self.current_byte - base,
-            },
-            string: &self.source[base..self.current_byte],
-        })
+        self.add(base, kind)
     }
 
     fn pick_3(
@@ -314,34 +828,53 @@ impl<'a> Lexer<'a> {
         op2: TokenKind,
         op3: TokenKind,
     ) {
-        let kind = if self.peek() == condition1 {
+        let kind = if !self.is_at_end() && self.peek() == condition1 {
             self.advance();
             op1
         } else {
-            if self.peek() == condition2 {
+            if !self.is_at_end() && self.peek() == condition2 {
                 self.advance();
                 op2
             } else {
                 op3
             }
         };
-        self.tokens.push(Token {
-            kind,
-            span: CharSpan {
-                base,
-                len: self.current_byte - base,
-            },
-            string: &self.source[base..self.current_byte],
-        })
+        self.add(base, kind)
     }
 
     fn is_at_end(&self) -> bool {
         self.current_byte >= self.bytes.len()
     }
 
-    pub fn lex(mut self) -> Result<Vec<Token<'a>>, LexerError> {
-        while !self.is_at_end() {
-            self.scan_token()?;
+    pub fn lex(self) -> Result<Vec<Token<'a>>, LexerError> {
+        let (tokens, mut errors) = self.lex_recovering();
+        if errors.is_empty() {
+            Ok(tokens)
+        } else {
+            Err(errors.remove(0))
+        }
+    }
+
+    // Reports every lexical error, the erroneous spans are emitted as error tokens so that the parser can still run
+    pub fn lex_recovering(mut self) -> (Vec<Token<'a>>, Vec<LexerError>) {
+        loop {
+            self.skip_whitespace();
+            if self.is_at_end() {
+                break;
+            }
+
+            let base = self.current_byte;
+            if let Err(error) = self.scan_token(base) {
+                self.errors.push(error);
+                if self.current_byte == base {
+                    self.advance_char();
+                }
+                // Skipping the rest of the word avoids cascading errors (e.g. "0b102" or "1.5u8")
+                while !self.is_at_end() && Lexer::is_valid_ident(self.peek_char(), false) {
+                    self.advance_char();
+                }
+                self.add(base, Error);
+            }
         }
         self.tokens.push(Token {
             kind: EndOfFile,
@@ -350,15 +883,27 @@ impl<'a> Lexer<'a> {
                 len: 0,
             },
             string: "",
+            value: TokenValue::None,
+            doc_comments: self.pending_doc_comments,
         });
-        Ok(self.tokens)
+        (self.tokens, self.errors)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum LexerErrorKind {
     UnrecognizedCharacter,
     UnterminatedString,
+    UnterminatedBlockComment,
+    UnterminatedChar,
+    EmptyChar,
+    CharTooLong,
+    InvalidEscape,
+    InvalidUnicodeEscape,
+    MissingDigits,
+    InvalidDigit,
+    InvalidSuffix,
+    IntegerTooLarge,
 }
 
 #[derive(Debug)]
@@ -366,3 +911,111 @@ pub struct LexerError {
     pub kind: LexerErrorKind,
     pub pos: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::*;
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        Lexer::new(source).lex().unwrap().iter().map(|token| token.kind).collect()
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        assert_eq!(kinds("a // b\n/* c /* nested */ d */ e //// f\n"), [Ident, Ident, EndOfFile]);
+        let error = Lexer::new("a /* /* */").lex().err().unwrap();
+        assert_eq!((error.kind, error.pos), (LexerErrorKind::UnterminatedBlockComment, 2));
+    }
+
+    #[test]
+    fn doc_comments_are_attached_to_the_next_token() {
+        let tokens = Lexer::new("/// outer\n//! inner\na").lex().unwrap();
+        let doc_comments: Vec<(DocCommentKind, &str)> = tokens[0].doc_comments.iter().map(|d| (d.kind, d.string)).collect();
+        assert_eq!(doc_comments, [(DocCommentKind::Outer, " outer"), (DocCommentKind::Inner, " inner")]);
+        assert!(tokens[1].doc_comments.is_empty());
+    }
+
+    #[test]
+    fn string_and_character_literals_are_unescaped() {
+        let tokens = Lexer::new(r#""a\"b\n\u{1F600}é" 'x' '\n' 'é' '\u{41}'"#).lex().unwrap();
+        let values: Vec<&TokenValue> = tokens.iter().map(|token| &token.value).collect();
+        assert_eq!(values, [
+            &TokenValue::String("a\"b\n😀é".to_string()),
+            &TokenValue::Char('x'),
+            &TokenValue::Char('\n'),
+            &TokenValue::Char('é'),
+            &TokenValue::Char('A'),
+            &TokenValue::None
+        ]);
+        assert_eq!(tokens[1].kind, CharLiteral);
+    }
+
+    #[test]
+    fn invalid_literals_are_reported_where_they_go_wrong() {
+        for (source, expected_pos) in [(r#""ab\q""#, 3), (r#""\u{110000}""#, 1), ("'ab'", 0), ("''", 0), ("\"abc", 0)] {
+            assert_eq!(Lexer::new(source).lex().err().unwrap().pos, expected_pos, "{}", source);
+        }
+    }
+
+    #[test]
+    fn numbers_take_radix_prefixes_separators_and_suffixes() {
+        let tokens = Lexer::new("0xff_u8 0o17 0b1010 1_000 1.5e-3 2.0f32 3i64 1..2 1e3").lex().unwrap();
+        let values: Vec<&TokenValue> = tokens.iter().map(|token| &token.value).collect();
+        assert_eq!(values, [
+            &TokenValue::Integer(255, Some(PrimitiveType::U8)),
+            &TokenValue::Integer(15, None),
+            &TokenValue::Integer(10, None),
+            &TokenValue::Integer(1000, None),
+            &TokenValue::Float(1.5e-3, None),
+            &TokenValue::Float(2.0, Some(PrimitiveType::F32)),
+            &TokenValue::Integer(3, Some(PrimitiveType::I64)),
+            // "1..2" is a range, not a float
+            &TokenValue::Integer(1, None),
+            &TokenValue::None,
+            &TokenValue::Integer(2, None),
+            &TokenValue::Float(1e3, None),
+            &TokenValue::None
+        ]);
+    }
+
+    #[test]
+    fn malformed_numbers_are_errors() {
+        for (source, expected) in [
+            ("0x", LexerErrorKind::MissingDigits),
+            ("0b12", LexerErrorKind::InvalidDigit),
+            ("1.5u8", LexerErrorKind::InvalidSuffix),
+            ("1xyz", LexerErrorKind::InvalidSuffix),
+            ("99999999999999999999", LexerErrorKind::IntegerTooLarge)
+        ] {
+            assert_eq!(Lexer::new(source).lex().err().unwrap().kind, expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn non_ascii_identifiers_strings_and_comments_are_lexed() {
+        let tokens = Lexer::new("héllo /* ü */ \"ß\" wörld").lex().unwrap();
+        let strings: Vec<&str> = tokens.iter().map(|token| token.string).collect();
+        assert_eq!(strings, ["héllo", "\"ß\"", "wörld", ""]);
+        // Spans are byte offsets into the source
+        assert_eq!((tokens[2].span.base, tokens[2].span.len), (21, 6));
+        assert_eq!(Lexer::new("a × b").lex().err().unwrap().kind, LexerErrorKind::UnrecognizedCharacter);
+    }
+
+    #[test]
+    fn lexing_recovers_after_errors() {
+        let (tokens, errors) = Lexer::new(r#"a $ b "x\qy\z" '\q' 0b102 c € d 'ab' +"#).lex_recovering();
+        let errors: Vec<(LexerErrorKind, usize)> = errors.into_iter().map(|error| (error.kind, error.pos)).collect();
+        assert_eq!(errors, [
+            (LexerErrorKind::UnrecognizedCharacter, 2),
+            (LexerErrorKind::InvalidEscape, 8),
+            (LexerErrorKind::InvalidEscape, 11),
+            (LexerErrorKind::InvalidEscape, 16),
+            (LexerErrorKind::InvalidDigit, 24),
+            (LexerErrorKind::UnrecognizedCharacter, 28),
+            (LexerErrorKind::CharTooLong, 34)
+        ]);
+        let kinds: Vec<TokenKind> = tokens.iter().map(|token| token.kind).collect();
+        // A string with two invalid escapes is a single error token
+        assert_eq!(kinds, [Ident, Error, Ident, Error, Error, Error, Ident, Error, Ident, Error, BinOp(Plus), EndOfFile]);
+    }
+}
@@ -9,3 +9,6 @@ pub mod reporting;
 pub mod field_ref_pool;
 pub mod method_ref_pool;
 pub mod type_ref_pool;
+
+#[cfg(test)]
+mod testing;
@@ -1,11 +1,12 @@
 use oolang::lexer::Lexer;
 use oolang::parser::Parser;
 use oolang::tir::ast_lowerer::ASTtoTIRLowerer;
+use oolang::tir::type_checker::TypeChecker;
 use oolang::codegen::Codegen;
+use oolang::reporting::source_file::SourceFile;
 
 fn main() {
-    let tokens = Lexer::new(
-        "
+    let source = "
     mod telno::testing;
 
     class Main {
@@ -15,14 +16,50 @@ fn main() {
 
         }
     }
-    "
-    )
-    .lex()
-    .unwrap();
+    ";
+    let mut source_file = SourceFile::new("main.oo", source);
 
-    let ast = Parser::new(tokens).parse().unwrap();
-    let (tir, type_ref_pool) = ASTtoTIRLowerer::new(ast).lower().unwrap();
-    let bytecode_files = Codegen::new(tir, type_ref_pool).get_bytecode().unwrap();
+    let (tokens, lexer_errors) = Lexer::new(source).lex_recovering();
+    for err in &lexer_errors {
+        println!("{}:{}: {:?}", source_file.name, source_file.pos_to_line_col(err.pos), err.kind);
+    }
+    if !lexer_errors.is_empty() {
+        return;
+    }
+    source_file.register_tokens(&tokens);
+
+    let (ast, parser_errors) = Parser::new(tokens).parse_recovering();
+    for err in &parser_errors {
+        println!("{}:{}: {}", source_file.name, source_file.token_span_to_range(err.span), err);
+    }
+    if !parser_errors.is_empty() {
+        return;
+    }
+    let (mut tir, type_ref_pool, field_ref_pool, method_ref_pool, lowerer_warnings) = match ASTtoTIRLowerer::new(ast).lower() {
+        Ok(lowered) => lowered,
+        Err(err) => {
+            println!("{}:{}: {:?}", source_file.name, source_file.token_span_to_range(err.span()), err);
+            return;
+        }
+    };
+    for warning in &lowerer_warnings {
+        println!("{}:{}: warning: {:?}", source_file.name, source_file.token_span_to_range(warning.span()), warning);
+    }
+
+    let type_errors = TypeChecker::new(&type_ref_pool, &field_ref_pool, &method_ref_pool).check(&mut tir);
+    for err in &type_errors {
+        println!("{}:{}: {:?}", source_file.name, source_file.token_span_to_range(err.span()), err);
+    }
+    if !type_errors.is_empty() {
+        return;
+    }
+    let bytecode_files = match Codegen::new(tir, type_ref_pool, field_ref_pool, method_ref_pool).get_bytecode() {
+        Ok(bytecode_files) => bytecode_files,
+        Err(err) => {
+            println!("{}:{}: {:?}", source_file.name, source_file.token_span_to_range(err.span()), err);
+            return;
+        }
+    };
 
     println!("{:?}", bytecode_files);
 }
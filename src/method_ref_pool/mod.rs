@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use crate::tir::TIRTypeInfo;
+use crate::ast::ASTVisibility;
 
 pub struct MethodRef<'a> {
     pub associated_type_ref_index: usize,
@@ -10,6 +11,7 @@ pub struct MethodRef<'a> {
     pub index: usize,
     pub index_in_all_members: usize,
 
+    pub visibility: ASTVisibility,
     pub is_abstract: bool,
     pub is_static: bool,
     pub is_native: bool
@@ -27,4 +29,20 @@ impl<'a> MethodRefPool<'a> {
             name_and_type_ref_index_to_method_ref_indexes: HashMap::new()
         }
     }
+
+    // Every method with that name in the hierarchy (see TypeRefPool::type_hierarchy), overridden ones excluded
+    pub fn find_methods(&self, hierarchy: &[usize], name: &str) -> Vec<usize> {
+        let mut methods: Vec<usize> = vec![];
+        for type_ref_index in hierarchy {
+            if let Some(overloads) = self.name_and_type_ref_index_to_method_ref_indexes.get(&(*type_ref_index, name)) {
+                for method_ref_index in overloads.values() {
+                    let parameters = &self.method_refs[*method_ref_index].parameters;
+                    if !methods.iter().any(|m| self.method_refs[*m].parameters == *parameters) {
+                        methods.push(*method_ref_index);
+                    }
+                }
+            }
+        }
+        methods
+    }
 }
\ No newline at end of file
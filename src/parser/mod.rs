@@ -1,27 +1,102 @@
 use crate::ast::{
-    ASTExpr, ASTExprKind, ASTGenericBound, ASTMember, ASTMemberKind, ASTMod, ASTModifier,
-    ASTNameAndType, ASTOperator, ASTPartialTypeInfo, ASTPath, ASTRoot, ASTStatement,
-    ASTStatementBlock, ASTStatementKind, ASTType, ASTTypeInfo, ASTTypeKind, ASTUse, ASTVisibility,
+    ASTExpr, ASTExprKind, ASTGenericBound, ASTMatchArm, ASTMember, ASTMemberKind, ASTMod,
+    ASTModifier, ASTNameAndType, ASTOperator, ASTPartialTypeInfo, ASTPath, ASTPattern,
+    ASTPatternKind, ASTRoot, ASTStatement,
+    ASTStatementBlock, ASTStatementKind, ASTType, ASTTypeAlias, ASTTypeInfo, ASTTypeKind, ASTUse, ASTUseKind, ASTVariant,
+    ASTVariantKind, ASTVisibility,
 };
 use crate::lexer::DelimTokenKind::SBracket;
-use crate::lexer::{BinOpTokenKind, DelimTokenKind, KeywordTokenKind, Token, TokenKind};
+use crate::lexer::{BinOpTokenKind, DelimTokenKind, KeywordTokenKind, Token, TokenKind, TokenValue};
 use crate::reporting::TokenSpan;
+use std::fmt::{Display, Formatter};
 
 #[repr(u8)]
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub enum OpPrecedence {
     Assignment,
+    Range,
     Or,
     And,
     Eq,
     Cmp,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Shift,
     Term,
     Factor,
+    // Binds tighter than every binary operator
+    Unary,
 }
 
+impl OpPrecedence {
+    fn next(self) -> Self {
+        match self {
+            OpPrecedence::Assignment => OpPrecedence::Range,
+            OpPrecedence::Range => OpPrecedence::Or,
+            OpPrecedence::Or => OpPrecedence::And,
+            OpPrecedence::And => OpPrecedence::Eq,
+            OpPrecedence::Eq => OpPrecedence::Cmp,
+            OpPrecedence::Cmp => OpPrecedence::BitOr,
+            OpPrecedence::BitOr => OpPrecedence::BitXor,
+            OpPrecedence::BitXor => OpPrecedence::BitAnd,
+            OpPrecedence::BitAnd => OpPrecedence::Shift,
+            OpPrecedence::Shift => OpPrecedence::Term,
+            OpPrecedence::Term => OpPrecedence::Factor,
+            OpPrecedence::Factor | OpPrecedence::Unary => OpPrecedence::Unary,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum Associativity {
+    Left,
+    Right,
+    // Chaining is an error (e.g. "a < b < c")
+    None,
+}
+
+// Every binary operator with the token it is parsed from
+const BINARY_OPERATORS: &[(TokenKind, ASTOperator, OpPrecedence, Associativity)] = &[
+    (TokenKind::Eq, ASTOperator::Assign, OpPrecedence::Assignment, Associativity::Right),
+    (TokenKind::BinOpAssign(BinOpTokenKind::Plus), ASTOperator::PlusAssign, OpPrecedence::Assignment, Associativity::Right),
+    (TokenKind::BinOpAssign(BinOpTokenKind::Minus), ASTOperator::MinusAssign, OpPrecedence::Assignment, Associativity::Right),
+    (TokenKind::BinOpAssign(BinOpTokenKind::Star), ASTOperator::MulAssign, OpPrecedence::Assignment, Associativity::Right),
+    (TokenKind::BinOpAssign(BinOpTokenKind::Slash), ASTOperator::DivAssign, OpPrecedence::Assignment, Associativity::Right),
+    (TokenKind::BinOpAssign(BinOpTokenKind::Percent), ASTOperator::ModAssign, OpPrecedence::Assignment, Associativity::Right),
+    (TokenKind::BinOpAssign(BinOpTokenKind::And), ASTOperator::BitAndAssign, OpPrecedence::Assignment, Associativity::Right),
+    (TokenKind::BinOpAssign(BinOpTokenKind::Or), ASTOperator::BitOrAssign, OpPrecedence::Assignment, Associativity::Right),
+    (TokenKind::BinOpAssign(BinOpTokenKind::Caret), ASTOperator::BitXorAssign, OpPrecedence::Assignment, Associativity::Right),
+    (TokenKind::BinOpAssign(BinOpTokenKind::Shl), ASTOperator::ShlAssign, OpPrecedence::Assignment, Associativity::Right),
+    (TokenKind::BinOpAssign(BinOpTokenKind::Shr), ASTOperator::ShrAssign, OpPrecedence::Assignment, Associativity::Right),
+    (TokenKind::OrOr, ASTOperator::Or, OpPrecedence::Or, Associativity::Left),
+    (TokenKind::AndAnd, ASTOperator::And, OpPrecedence::And, Associativity::Left),
+    (TokenKind::EqEq, ASTOperator::Eq, OpPrecedence::Eq, Associativity::None),
+    (TokenKind::NotEq, ASTOperator::NotEq, OpPrecedence::Eq, Associativity::None),
+    (TokenKind::Ls, ASTOperator::Ls, OpPrecedence::Cmp, Associativity::None),
+    (TokenKind::Gt, ASTOperator::Gt, OpPrecedence::Cmp, Associativity::None),
+    (TokenKind::LsEq, ASTOperator::LsEq, OpPrecedence::Cmp, Associativity::None),
+    (TokenKind::GtEq, ASTOperator::GtEq, OpPrecedence::Cmp, Associativity::None),
+    (TokenKind::BinOp(BinOpTokenKind::Or), ASTOperator::BitOr, OpPrecedence::BitOr, Associativity::Left),
+    (TokenKind::BinOp(BinOpTokenKind::Caret), ASTOperator::BitXor, OpPrecedence::BitXor, Associativity::Left),
+    (TokenKind::BinOp(BinOpTokenKind::And), ASTOperator::BitAnd, OpPrecedence::BitAnd, Associativity::Left),
+    (TokenKind::BinOp(BinOpTokenKind::Shl), ASTOperator::Shl, OpPrecedence::Shift, Associativity::Left),
+    (TokenKind::BinOp(BinOpTokenKind::Shr), ASTOperator::Shr, OpPrecedence::Shift, Associativity::Left),
+    (TokenKind::BinOp(BinOpTokenKind::Plus), ASTOperator::Plus, OpPrecedence::Term, Associativity::Left),
+    (TokenKind::BinOp(BinOpTokenKind::Minus), ASTOperator::Minus, OpPrecedence::Term, Associativity::Left),
+    (TokenKind::BinOp(BinOpTokenKind::Star), ASTOperator::Mul, OpPrecedence::Factor, Associativity::Left),
+    (TokenKind::BinOp(BinOpTokenKind::Slash), ASTOperator::Div, OpPrecedence::Factor, Associativity::Left),
+    (TokenKind::BinOp(BinOpTokenKind::Percent), ASTOperator::Mod, OpPrecedence::Factor, Associativity::Left),
+];
+
 pub struct Parser<'a> {
     tokens: Vec<Token<'a>>,
     current_token: usize,
+    // Tokens that would have been accepted at the current position, reset on every advance
+    expected: Vec<TokenKind>,
+    errors: Vec<ParserError>,
+    // Set while parsing conditions, where "a == E::A {" starts the block and not a struct literal
+    no_struct_literal: bool,
 }
 
 impl<'a> Parser<'a> {
@@ -29,32 +104,81 @@ impl<'a> Parser<'a> {
         Parser {
             tokens,
             current_token: 0,
+            expected: vec![],
+            errors: vec![],
+            no_struct_literal: false,
+        }
+    }
+
+    fn advance(&mut self) {
+        self.expected.clear();
+        if self.peek() != TokenKind::EndOfFile {
+            self.current_token += 1;
+        }
+    }
+
+    // Like comparing with peek, but remembers the kind for the error message if it doesn't match
+    fn check(&mut self, kind: TokenKind) -> bool {
+        if self.peek() == kind {
+            true
+        } else {
+            if !self.expected.contains(&kind) {
+                self.expected.push(kind);
+            }
+            false
         }
     }
 
-    fn advance(&mut self) -> &Token {
-        let t = &self.tokens[self.current_token];
-        self.current_token += 1;
-        t
+    fn unexpected(&self) -> ParserError {
+        ParserError::new(
+            TokenSpan::new(self.current_token, 1),
+            self.peek(),
+            self.expected.clone(),
+        )
     }
 
     fn advance_match(&mut self, expected: TokenKind) -> Result<(), ParserError> {
-        let token = self.peek();
-        self.advance();
-        if token == expected {
+        if self.check(expected) {
+            self.advance();
             Ok(())
         } else {
-            Err(ParserError::new(
-                TokenSpan::new(self.current_token, 1),
-                token,
-            ))
+            Err(self.unexpected())
         }
     }
 
+    // Closing generics have to split ">>", ">>=" and ">=" since the lexer can't tell them apart from operators
+    fn advance_match_gt(&mut self) -> Result<(), ParserError> {
+        let remainder = match self.peek() {
+            TokenKind::BinOp(BinOpTokenKind::Shr) => TokenKind::Gt,
+            TokenKind::BinOpAssign(BinOpTokenKind::Shr) => TokenKind::GtEq,
+            TokenKind::GtEq => TokenKind::Eq,
+            _ => return self.advance_match(TokenKind::Gt),
+        };
+        // The first '>' is consumed by shrinking the token instead of advancing
+        let token = &mut self.tokens[self.current_token];
+        token.kind = remainder;
+        token.span.base += 1;
+        token.span.len -= 1;
+        token.string = &token.string[1..];
+        Ok(())
+    }
+
     fn peek(&self) -> TokenKind {
         self.tokens[self.current_token].kind
     }
 
+    fn peek_at(&self, offset: usize) -> TokenKind {
+        match self.tokens.get(self.current_token + offset) {
+            Some(token) => token.kind,
+            None => TokenKind::EndOfFile,
+        }
+    }
+
+    // Moves the decoded literal value out of the current token
+    fn take_value(&mut self) -> TokenValue {
+        std::mem::replace(&mut self.tokens[self.current_token].value, TokenValue::None)
+    }
+
     fn statement_undo_ending(&self, statement: &mut ASTStatement) -> Result<(), ParserError> {
         match &statement.kind {
             ASTStatementKind::Expression(e) => match &e.kind {
@@ -70,19 +194,19 @@ impl<'a> Parser<'a> {
                     statement.ending = false;
                     return Ok(());
                 }
-                ASTExprKind::While(_, _) => {
+                ASTExprKind::While(_, _, _) => {
                     statement.ending = false;
                     return Ok(());
                 }
-                ASTExprKind::Loop(_) => {
+                ASTExprKind::Loop(_, _) => {
                     statement.ending = false;
                     return Ok(());
                 }
-                ASTExprKind::Match() => {
+                ASTExprKind::Match(_, _) => {
                     statement.ending = false;
                     return Ok(());
                 }
-                ASTExprKind::For() => {
+                ASTExprKind::For(_, _, _, _) => {
                     statement.ending = false;
                     return Ok(());
                 }
@@ -91,31 +215,167 @@ impl<'a> Parser<'a> {
             _ => {}
         }
 
-        let statement_end_loc = statement.span.base + statement.span.len;
-        Err(ParserError::new(
-            TokenSpan::new(statement_end_loc, 1),
-            self.tokens[statement_end_loc].kind,
-        ))
+        // The statement ends right before the current token
+        Err(self.unexpected())
+    }
+
+    // Parses the expression with struct literals allowed or not, restoring the previous state after
+    fn parse_expression_struct_literals(
+        &mut self,
+        allowed: bool,
+    ) -> Result<ASTExpr<'a>, ParserError> {
+        let no_struct_literal = std::mem::replace(&mut self.no_struct_literal, !allowed);
+        let expr = self.parse_expression();
+        self.no_struct_literal = no_struct_literal;
+        expr
+    }
+
+    // "loop", "while" or "for" after its optional label
+    fn parse_loop(
+        &mut self,
+        label: Option<&'a str>,
+        starting_token: usize,
+    ) -> Result<ASTExpr<'a>, ParserError> {
+        if self.check(TokenKind::Keyword(KeywordTokenKind::While)) {
+            self.advance();
+            let cond = self.parse_expression_struct_literals(false)?;
+            let block = self.parse_statement_block()?;
+            Ok(ASTExpr {
+                kind: ASTExprKind::While(label, Box::new(cond), block),
+                span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+            })
+        } else if self.check(TokenKind::Keyword(KeywordTokenKind::Loop)) {
+            self.advance();
+            let block = self.parse_statement_block()?;
+            Ok(ASTExpr {
+                kind: ASTExprKind::Loop(label, block),
+                span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+            })
+        } else if self.check(TokenKind::Keyword(KeywordTokenKind::For)) {
+            self.advance();
+            let name = self.tokens[self.current_token].string;
+            self.advance_match(TokenKind::Ident)?;
+            self.advance_match(TokenKind::Keyword(KeywordTokenKind::In))?;
+            let iterable = self.parse_expression_struct_literals(false)?;
+            let block = self.parse_statement_block()?;
+            Ok(ASTExpr {
+                kind: ASTExprKind::For(label, name, Box::new(iterable), block),
+                span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+            })
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    // Label after "break" or "continue", without the quote
+    fn parse_label_use(&mut self) -> Option<&'a str> {
+        if self.check(TokenKind::Label) {
+            let label = &self.tokens[self.current_token].string[1..];
+            self.advance();
+            Some(label)
+        } else {
+            None
+        }
+    }
+
+    // Value after "break" or "return", absent when the expression can't go on
+    fn parse_jump_value(&mut self) -> Result<Option<Box<ASTExpr<'a>>>, ParserError> {
+        match self.peek() {
+            TokenKind::Semicolon
+            | TokenKind::Comma
+            | TokenKind::ClosingDelim(_)
+            | TokenKind::EndOfFile => Ok(None),
+            _ => Ok(Some(Box::new(self.parse_expression()?))),
+        }
+    }
+
+    // "(a, b)" in calls, "new" and "super"
+    fn parse_arguments(&mut self) -> Result<Vec<ASTExpr<'a>>, ParserError> {
+        self.advance_match(TokenKind::OpeningDelim(DelimTokenKind::Paren))?;
+        let mut args = vec![];
+        if !self.check(TokenKind::ClosingDelim(DelimTokenKind::Paren)) {
+            args.push(self.parse_expression_struct_literals(true)?);
+            while self.check(TokenKind::Comma) {
+                self.advance();
+                args.push(self.parse_expression_struct_literals(true)?);
+            }
+        }
+        self.advance_match(TokenKind::ClosingDelim(DelimTokenKind::Paren))?;
+        Ok(args)
     }
 
     fn parse_expression_primary(&mut self) -> Result<ASTExpr<'a>, ParserError> {
         let starting_token = self.current_token;
         let token = self.peek();
         match token {
-            TokenKind::Keyword(KeywordTokenKind::While) => {
+            TokenKind::Keyword(KeywordTokenKind::While)
+            | TokenKind::Keyword(KeywordTokenKind::Loop)
+            | TokenKind::Keyword(KeywordTokenKind::For) => self.parse_loop(None, starting_token),
+            TokenKind::Label => {
+                let label = &self.tokens[self.current_token].string[1..];
+                self.advance();
+                self.advance_match(TokenKind::Colon)?;
+                self.parse_loop(Some(label), starting_token)
+            }
+            TokenKind::Keyword(KeywordTokenKind::Break) => {
+                self.advance();
+                let label = self.parse_label_use();
+                let value = self.parse_jump_value()?;
+                Ok(ASTExpr {
+                    kind: ASTExprKind::Break(label, value),
+                    span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+                })
+            }
+            TokenKind::Keyword(KeywordTokenKind::Continue) => {
+                self.advance();
+                let label = self.parse_label_use();
+                Ok(ASTExpr {
+                    kind: ASTExprKind::Continue(label),
+                    span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+                })
+            }
+            TokenKind::Keyword(KeywordTokenKind::Return) => {
+                self.advance();
+                let value = self.parse_jump_value()?;
+                Ok(ASTExpr {
+                    kind: ASTExprKind::Return(value),
+                    span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+                })
+            }
+            TokenKind::Keyword(KeywordTokenKind::New) => {
+                self.advance();
+                let type_info = self.parse_type_info()?;
+                let args = self.parse_arguments()?;
+                Ok(ASTExpr {
+                    kind: ASTExprKind::New(type_info, args),
+                    span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+                })
+            }
+            TokenKind::Keyword(KeywordTokenKind::Super) => {
+                self.advance();
+                // "super(...)" calls the super constructor, "super.f()" a method of the super class
+                let kind = if self.check(TokenKind::OpeningDelim(DelimTokenKind::Paren)) {
+                    ASTExprKind::SuperCall(self.parse_arguments()?)
+                } else {
+                    ASTExprKind::Super
+                };
+                Ok(ASTExpr {
+                    kind,
+                    span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+                })
+            }
+            TokenKind::Keyword(KeywordTokenKind::This) => {
                 self.advance();
-                let cond = self.parse_expression()?;
-                let block = self.parse_statement_block()?;
                 Ok(ASTExpr {
-                    kind: ASTExprKind::While(Box::new(cond), block),
+                    kind: ASTExprKind::This,
                     span: TokenSpan::new_rn_ex(starting_token, self.current_token),
                 })
             }
             TokenKind::Keyword(KeywordTokenKind::If) => {
                 self.advance();
-                let cond = self.parse_expression()?;
+                let cond = self.parse_expression_struct_literals(false)?;
                 let block_if = self.parse_statement_block()?;
-                if self.peek() == TokenKind::Keyword(KeywordTokenKind::Else) {
+                if self.check(TokenKind::Keyword(KeywordTokenKind::Else)) {
                     self.advance();
                     let block_else = self.parse_statement_block()?;
                     Ok(ASTExpr {
@@ -129,11 +389,15 @@ impl<'a> Parser<'a> {
                     })
                 }
             }
-            TokenKind::Keyword(KeywordTokenKind::Loop) => {
+            TokenKind::Keyword(KeywordTokenKind::Match) => {
                 self.advance();
-                let block = self.parse_statement_block()?;
+                let scrutinee = self.parse_expression_struct_literals(false)?;
+                self.advance_match(TokenKind::OpeningDelim(DelimTokenKind::CBracket))?;
+                let no_struct_literal = std::mem::replace(&mut self.no_struct_literal, false);
+                let arms = self.parse_match_arms();
+                self.no_struct_literal = no_struct_literal;
                 Ok(ASTExpr {
-                    kind: ASTExprKind::Loop(block),
+                    kind: ASTExprKind::Match(Box::new(scrutinee), arms?),
                     span: TokenSpan::new_rn_ex(starting_token, self.current_token),
                 })
             }
@@ -146,83 +410,297 @@ impl<'a> Parser<'a> {
             }
             TokenKind::OpeningDelim(DelimTokenKind::Paren) => {
                 self.advance();
-                let inner = self.parse_expression()?;
+                let inner = self.parse_expression_struct_literals(true)?;
                 self.advance_match(TokenKind::ClosingDelim(DelimTokenKind::Paren))?;
                 Ok(inner)
             }
             TokenKind::Null => {
-                let res = Ok(ASTExpr {
+                self.advance();
+                Ok(ASTExpr {
                     kind: ASTExprKind::Null,
                     span: TokenSpan::new_rn_ex(starting_token, self.current_token),
-                });
-                self.advance();
-                res
+                })
             }
             TokenKind::True => {
-                let res = Ok(ASTExpr {
+                self.advance();
+                Ok(ASTExpr {
                     kind: ASTExprKind::Boolean(true),
                     span: TokenSpan::new_rn_ex(starting_token, self.current_token),
-                });
-                self.advance();
-                res
+                })
             }
             TokenKind::False => {
-                let res = Ok(ASTExpr {
+                self.advance();
+                Ok(ASTExpr {
                     kind: ASTExprKind::Boolean(false),
                     span: TokenSpan::new_rn_ex(starting_token, self.current_token),
-                });
-                self.advance();
-                res
+                })
             }
             TokenKind::StringLiteral => {
-                let res = Ok(ASTExpr {
-                    kind: ASTExprKind::StringLiteral(self.tokens[self.current_token].string),
+                let kind = match self.take_value() {
+                    TokenValue::String(string) => ASTExprKind::StringLiteral(string),
+                    _ => unreachable!(),
+                };
+                self.advance();
+                Ok(ASTExpr {
+                    kind,
                     span: TokenSpan::new_rn_ex(starting_token, self.current_token),
-                });
+                })
+            }
+            TokenKind::CharLiteral => {
+                let kind = match self.take_value() {
+                    TokenValue::Char(character) => ASTExprKind::CharLiteral(character),
+                    _ => unreachable!(),
+                };
                 self.advance();
-                res
+                Ok(ASTExpr {
+                    kind,
+                    span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+                })
             }
             TokenKind::Float => {
-                let res = Ok(ASTExpr {
-                    kind: ASTExprKind::Float(self.tokens[self.current_token].string),
-                    span: TokenSpan::new_rn_ex(starting_token, self.current_token),
-                });
+                let kind = match self.take_value() {
+                    TokenValue::Float(value, suffix) => ASTExprKind::Float(value, suffix),
+                    _ => unreachable!(),
+                };
                 self.advance();
-                res
+                Ok(ASTExpr {
+                    kind,
+                    span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+                })
             }
             TokenKind::Num => {
-                let res = Ok(ASTExpr {
-                    kind: ASTExprKind::Num(self.tokens[self.current_token].string),
-                    span: TokenSpan::new_rn_ex(starting_token, self.current_token),
-                });
+                let kind = match self.take_value() {
+                    TokenValue::Integer(value, suffix) => ASTExprKind::Num(value, suffix),
+                    _ => unreachable!(),
+                };
                 self.advance();
-                res
+                Ok(ASTExpr {
+                    kind,
+                    span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+                })
             }
             TokenKind::Ls => {
                 self.advance();
                 let path = self.parse_path()?;
-                self.advance_match(TokenKind::Gt)?;
+                self.advance_match_gt()?;
                 Ok(ASTExpr {
                     kind: ASTExprKind::Path(path),
                     span: TokenSpan::new_rn_ex(starting_token, self.current_token),
                 })
             }
             TokenKind::Ident => {
-                let res = Ok(ASTExpr {
-                    kind: ASTExprKind::Ident(self.tokens[self.current_token].string),
+                let name = self.tokens[self.current_token].string;
+                self.advance();
+                Ok(ASTExpr {
+                    kind: ASTExprKind::Ident(name),
                     span: TokenSpan::new_rn_ex(starting_token, self.current_token),
-                });
+                })
+            }
+            _ => Err(ParserError::new_with_kind(
+                ParserErrorKind::ExpectedExpression,
+                TokenSpan::new(starting_token, 1),
+                token,
+                vec![],
+            )),
+        }
+    }
+
+    // Arms and the closing brace of a match expression
+    fn parse_match_arms(&mut self) -> Result<Vec<ASTMatchArm<'a>>, ParserError> {
+        let mut arms = vec![];
+        while !self.check(TokenKind::ClosingDelim(DelimTokenKind::CBracket)) {
+            let starting_token = self.current_token;
+            let pattern = self.parse_pattern()?;
+            let guard = if self.check(TokenKind::Keyword(KeywordTokenKind::If)) {
+                self.advance();
+                Some(self.parse_expression()?)
+            } else {
+                None
+            };
+            self.advance_match(TokenKind::FatArrow)?;
+            let body = self.parse_expression()?;
+            // Like statements, arms ending with a block don't need a comma
+            let block_like = match body.kind {
+                ASTExprKind::Block(_)
+                | ASTExprKind::IfElse(_, _, _)
+                | ASTExprKind::If(_, _)
+                | ASTExprKind::While(_, _, _)
+                | ASTExprKind::Loop(_, _)
+                | ASTExprKind::For(_, _, _, _)
+                | ASTExprKind::Match(_, _) => true,
+                _ => false,
+            };
+            arms.push(ASTMatchArm {
+                span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+                pattern,
+                guard,
+                body,
+            });
+
+            if self.check(TokenKind::Comma) {
                 self.advance();
-                res
+            } else if !block_like {
+                break;
+            }
+        }
+        self.advance_match(TokenKind::ClosingDelim(DelimTokenKind::CBracket))?;
+
+        Ok(arms)
+    }
+
+    pub fn parse_pattern(&mut self) -> Result<ASTPattern<'a>, ParserError> {
+        let starting_token = self.current_token;
+
+        let first = self.parse_pattern_single()?;
+        if !self.check(TokenKind::BinOp(BinOpTokenKind::Or)) {
+            return Ok(first);
+        }
+        let mut alternatives = vec![first];
+        while self.check(TokenKind::BinOp(BinOpTokenKind::Or)) {
+            self.advance();
+            alternatives.push(self.parse_pattern_single()?);
+        }
+
+        Ok(ASTPattern {
+            kind: ASTPatternKind::Or(alternatives),
+            span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+        })
+    }
+
+    // A literal, optionally negated ("-1")
+    fn parse_pattern_literal(&mut self) -> Result<ASTExpr<'a>, ParserError> {
+        let starting_token = self.current_token;
+
+        if self.check(TokenKind::BinOp(BinOpTokenKind::Minus)) {
+            self.advance();
+            let literal = match self.peek() {
+                TokenKind::Num | TokenKind::Float => self.parse_expression_primary()?,
+                _ => {
+                    self.expected.push(TokenKind::Num);
+                    self.expected.push(TokenKind::Float);
+                    return Err(self.unexpected());
+                }
+            };
+            return Ok(ASTExpr {
+                kind: ASTExprKind::PreOp(ASTOperator::Minus, Box::new(literal)),
+                span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+            });
+        }
+
+        match self.peek() {
+            TokenKind::Num
+            | TokenKind::Float
+            | TokenKind::CharLiteral
+            | TokenKind::StringLiteral
+            | TokenKind::True
+            | TokenKind::False
+            | TokenKind::Null => self.parse_expression_primary(),
+            token => Err(ParserError::new_with_kind(
+                ParserErrorKind::ExpectedPattern,
+                TokenSpan::new(starting_token, 1),
+                token,
+                vec![],
+            )),
+        }
+    }
+
+    fn parse_pattern_single(&mut self) -> Result<ASTPattern<'a>, ParserError> {
+        let starting_token = self.current_token;
+
+        let kind = match self.peek() {
+            TokenKind::Ident if self.peek_at(1) == TokenKind::ColonColon => {
+                let path = self.parse_path()?;
+                if self.check(TokenKind::OpeningDelim(DelimTokenKind::Paren)) {
+                    self.advance();
+                    let mut fields = vec![];
+                    while !self.check(TokenKind::ClosingDelim(DelimTokenKind::Paren)) {
+                        fields.push(self.parse_pattern()?);
+                        if self.check(TokenKind::Comma) {
+                            self.advance();
+                        } else {
+                            break;
+                        }
+                    }
+                    self.advance_match(TokenKind::ClosingDelim(DelimTokenKind::Paren))?;
+                    ASTPatternKind::TupleVariant(path, fields)
+                } else if self.check(TokenKind::OpeningDelim(DelimTokenKind::CBracket)) {
+                    self.advance();
+                    let mut fields = vec![];
+                    let mut has_rest = false;
+                    while !self.check(TokenKind::ClosingDelim(DelimTokenKind::CBracket)) {
+                        if self.check(TokenKind::DotDot) {
+                            self.advance();
+                            has_rest = true;
+                            break;
+                        }
+                        let name_token = self.current_token;
+                        let name = self.tokens[self.current_token].string;
+                        self.advance_match(TokenKind::Ident)?;
+                        // "E::C { x }" is short for "E::C { x: x }"
+                        let pattern = if self.check(TokenKind::Colon) {
+                            self.advance();
+                            self.parse_pattern()?
+                        } else {
+                            ASTPattern {
+                                kind: ASTPatternKind::Binding(name),
+                                span: TokenSpan::new(name_token, 1),
+                            }
+                        };
+                        fields.push((name, pattern));
+                        if self.check(TokenKind::Comma) {
+                            self.advance();
+                        } else {
+                            break;
+                        }
+                    }
+                    self.advance_match(TokenKind::ClosingDelim(DelimTokenKind::CBracket))?;
+                    ASTPatternKind::StructVariant(path, fields, has_rest)
+                } else {
+                    ASTPatternKind::UnitVariant(path)
+                }
+            }
+            TokenKind::Ident => {
+                let name = self.tokens[self.current_token].string;
+                self.advance();
+                let name = if name == "_" { None } else { Some(name) };
+                if self.check(TokenKind::Colon) {
+                    self.advance();
+                    ASTPatternKind::TypeTest(name, self.parse_type_info()?)
+                } else {
+                    match name {
+                        Some(name) => ASTPatternKind::Binding(name),
+                        None => ASTPatternKind::Wildcard,
+                    }
+                }
+            }
+            TokenKind::OpeningDelim(DelimTokenKind::Paren) => {
+                self.advance();
+                let inner = self.parse_pattern()?;
+                self.advance_match(TokenKind::ClosingDelim(DelimTokenKind::Paren))?;
+                return Ok(inner);
             }
             _ => {
+                let start = self.parse_pattern_literal()?;
+                let inclusive = if self.check(TokenKind::DotDotEq) {
+                    true
+                } else if self.check(TokenKind::DotDot) {
+                    false
+                } else {
+                    return Ok(ASTPattern {
+                        kind: ASTPatternKind::Literal(Box::new(start)),
+                        span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+                    });
+                };
                 self.advance();
-                Err(ParserError::new(
-                    TokenSpan::new_rn_ex(starting_token, self.current_token),
-                    token,
-                ))
+                let end = self.parse_pattern_literal()?;
+                ASTPatternKind::Range(Box::new(start), Box::new(end), inclusive)
             }
-        }
+        };
+
+        Ok(ASTPattern {
+            kind,
+            span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+        })
     }
 
     fn parse_expression_call(&mut self) -> Result<ASTExpr<'a>, ParserError> {
@@ -251,29 +729,48 @@ impl<'a> Parser<'a> {
                     }
                 }
                 TokenKind::OpeningDelim(DelimTokenKind::Paren) => {
+                    let args = self.parse_arguments()?;
+                    ASTExpr {
+                        kind: ASTExprKind::Call(Box::new(expr), args),
+                        span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+                    }
+                }
+                TokenKind::OpeningDelim(DelimTokenKind::CBracket)
+                    if !self.no_struct_literal
+                        && matches!(expr.kind, ASTExprKind::StaticAccess(_, _)) =>
+                {
                     self.advance();
-
-                    if self.peek() == TokenKind::ClosingDelim(DelimTokenKind::Paren) {
-                        ASTExpr {
-                            kind: ASTExprKind::Call(Box::new(expr), vec![]),
-                            span: TokenSpan::new_rn_ex(starting_token, self.current_token),
-                        }
-                    } else {
-                        let mut args = vec![self.parse_expression()?];
-                        while self.peek() == TokenKind::Comma {
+                    let mut fields = vec![];
+                    while !self.check(TokenKind::ClosingDelim(DelimTokenKind::CBracket)) {
+                        let name_token = self.current_token;
+                        let name = self.tokens[self.current_token].string;
+                        self.advance_match(TokenKind::Ident)?;
+                        // "E::A { x }" is short for "E::A { x: x }"
+                        let value = if self.check(TokenKind::Colon) {
                             self.advance();
-                            args.push(self.parse_expression()?);
-                        }
-                        self.advance_match(TokenKind::ClosingDelim(DelimTokenKind::Paren))?;
-                        ASTExpr {
-                            kind: ASTExprKind::Call(Box::new(expr), args),
-                            span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+                            self.parse_expression()?
+                        } else {
+                            ASTExpr {
+                                kind: ASTExprKind::Ident(name),
+                                span: TokenSpan::new(name_token, 1),
+                            }
+                        };
+                        fields.push((name, value));
+                        if self.check(TokenKind::Comma) {
+                            self.advance();
+                        } else {
+                            break;
                         }
                     }
+                    self.advance_match(TokenKind::ClosingDelim(DelimTokenKind::CBracket))?;
+                    ASTExpr {
+                        kind: ASTExprKind::StructLiteral(Box::new(expr), fields),
+                        span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+                    }
                 }
                 TokenKind::OpeningDelim(DelimTokenKind::SBracket) => {
                     self.advance();
-                    let index = self.parse_expression()?;
+                    let index = self.parse_expression_struct_literals(true)?;
                     self.advance_match(TokenKind::ClosingDelim(SBracket))?;
                     ASTExpr {
                         kind: ASTExprKind::Indexing(Box::new(expr), Box::new(index)),
@@ -331,6 +828,16 @@ impl<'a> Parser<'a> {
                     span: TokenSpan::new_rn_ex(starting_token, self.current_token),
                 })
             }
+            TokenKind::Tilde => {
+                self.advance();
+                Ok(ASTExpr {
+                    kind: ASTExprKind::PreOp(
+                        ASTOperator::BitNot,
+                        Box::new(self.parse_expression_prefix()?),
+                    ),
+                    span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+                })
+            }
             _ => self.parse_expression_call(),
         }
     }
@@ -367,50 +874,59 @@ impl<'a> Parser<'a> {
         &mut self,
         min_precedence: OpPrecedence,
     ) -> Result<ASTExpr<'a>, ParserError> {
+        let starting_token = self.current_token;
         let mut lhs = self.parse_expression_postfix()?;
+        // Precedence of the last operator if it was non-associative, to reject "a < b < c"
+        let mut last_non_associative = None;
 
-        'loop_break: loop {
-            let starting_token = self.current_token;
+        loop {
             let op_token = self.peek();
-            let (op, op_precedence) = match op_token {
-                TokenKind::Eq => (ASTOperator::Assign, OpPrecedence::Assignment),
-                TokenKind::BinOpAssign(BinOpTokenKind::Plus) => {
-                    (ASTOperator::PlusAssign, OpPrecedence::Assignment)
-                }
-                TokenKind::BinOpAssign(BinOpTokenKind::Minus) => {
-                    (ASTOperator::MinusAssign, OpPrecedence::Assignment)
-                }
-                TokenKind::BinOpAssign(BinOpTokenKind::Star) => {
-                    (ASTOperator::MulAssign, OpPrecedence::Assignment)
-                }
-                TokenKind::BinOpAssign(BinOpTokenKind::Slash) => {
-                    (ASTOperator::DivAssign, OpPrecedence::Assignment)
-                }
-                TokenKind::OrOr => (ASTOperator::Or, OpPrecedence::Or),
-                TokenKind::AndAnd => (ASTOperator::And, OpPrecedence::And),
-                TokenKind::EqEq => (ASTOperator::Eq, OpPrecedence::Eq),
-                TokenKind::Ls => (ASTOperator::Ls, OpPrecedence::Cmp),
-                TokenKind::Gt => (ASTOperator::Gt, OpPrecedence::Cmp),
-                TokenKind::LsEq => (ASTOperator::LsEq, OpPrecedence::Cmp),
-                TokenKind::GtEq => (ASTOperator::GtEq, OpPrecedence::Cmp),
-                TokenKind::BinOp(BinOpTokenKind::Plus) => (ASTOperator::Plus, OpPrecedence::Term),
-                TokenKind::BinOp(BinOpTokenKind::Minus) => (ASTOperator::Minus, OpPrecedence::Term),
-                TokenKind::BinOp(BinOpTokenKind::Star) => (ASTOperator::Mul, OpPrecedence::Factor),
-                TokenKind::BinOp(BinOpTokenKind::Slash) => (ASTOperator::Div, OpPrecedence::Factor),
-                _ => break 'loop_break,
+            let (op, op_precedence, associativity) = match BINARY_OPERATORS
+                .iter()
+                .find(|(token, ..)| *token == op_token)
+            {
+                Some(&(_, op, precedence, associativity)) => (Some(op), precedence, associativity),
+                // ".." and "..=" build a range instead of an operation, "a..b..c" is rejected
+                None if op_token == TokenKind::DotDot || op_token == TokenKind::DotDotEq => {
+                    (None, OpPrecedence::Range, Associativity::None)
+                }
+                None => break,
             };
 
             if op_precedence < min_precedence {
-                break 'loop_break;
+                break;
+            }
+            if last_non_associative == Some(op_precedence) {
+                return Err(ParserError::new_with_kind(
+                    if op.is_some() {
+                        ParserErrorKind::ChainedComparison
+                    } else {
+                        ParserErrorKind::ChainedRange
+                    },
+                    TokenSpan::new(self.current_token, 1),
+                    op_token,
+                    vec![],
+                ));
             }
 
             self.advance();
 
-            let rhs = Box::new(self.parse_expression_with_precedence(op_precedence)?);
+            let rhs_precedence = match associativity {
+                Associativity::Right => op_precedence,
+                Associativity::Left | Associativity::None => op_precedence.next(),
+            };
+            let rhs = Box::new(self.parse_expression_with_precedence(rhs_precedence)?);
             lhs = ASTExpr {
-                kind: ASTExprKind::BinOp(Box::new(lhs), op, rhs),
+                kind: match op {
+                    Some(op) => ASTExprKind::BinOp(Box::new(lhs), op, rhs),
+                    None => ASTExprKind::Range(Box::new(lhs), rhs, op_token == TokenKind::DotDotEq),
+                },
                 span: TokenSpan::new_rn_ex(starting_token, self.current_token),
             };
+            last_non_associative = match associativity {
+                Associativity::None => Some(op_precedence),
+                _ => None,
+            };
         }
 
         Ok(lhs)
@@ -428,13 +944,13 @@ impl<'a> Parser<'a> {
                 self.advance();
                 let name = self.tokens[self.current_token].string;
                 self.advance_match(TokenKind::Ident)?;
-                let type_info = if self.peek() == TokenKind::Colon {
+                let type_info = if self.check(TokenKind::Colon) {
                     self.advance();
                     Some(self.parse_type_info()?)
                 } else {
                     None
                 };
-                let assignment = if self.peek() == TokenKind::Eq {
+                let assignment = if self.check(TokenKind::Eq) {
                     self.advance();
                     Some(Box::new(self.parse_expression()?))
                 } else {
@@ -450,7 +966,7 @@ impl<'a> Parser<'a> {
             }
             _ => {
                 let expr = self.parse_expression()?;
-                let ending = self.peek() != TokenKind::Semicolon;
+                let ending = !self.check(TokenKind::Semicolon);
                 if !ending {
                     self.advance();
                 }
@@ -465,17 +981,37 @@ impl<'a> Parser<'a> {
     }
 
     pub fn parse_statement_block(&mut self) -> Result<ASTStatementBlock<'a>, ParserError> {
+        let no_struct_literal = std::mem::replace(&mut self.no_struct_literal, false);
+        let block = self.parse_statement_block_inner();
+        self.no_struct_literal = no_struct_literal;
+        block
+    }
+
+    fn parse_statement_block_inner(&mut self) -> Result<ASTStatementBlock<'a>, ParserError> {
         let starting_token = self.current_token;
 
         self.advance_match(TokenKind::OpeningDelim(DelimTokenKind::CBracket))?;
         let mut statements: Vec<ASTStatement> = vec![];
-        while self.peek() != TokenKind::ClosingDelim(DelimTokenKind::CBracket) {
+        while !self.check(TokenKind::ClosingDelim(DelimTokenKind::CBracket)) {
+            if self.peek() == TokenKind::EndOfFile {
+                return Err(self.unexpected());
+            }
             if let Some(statement) = statements.last_mut() {
                 if statement.ending {
-                    self.statement_undo_ending(statement)?;
+                    if let Err(err) = self.statement_undo_ending(statement) {
+                        // Carry on as if the ";" was there
+                        statement.ending = false;
+                        self.report(err);
+                    }
+                }
+            }
+            match self.parse_statement() {
+                Ok(statement) => statements.push(statement),
+                Err(err) => {
+                    self.report(err);
+                    self.synchronize_statement();
                 }
             }
-            statements.push(self.parse_statement()?);
         }
         self.advance();
 
@@ -490,7 +1026,7 @@ impl<'a> Parser<'a> {
 
         let mut elements = vec![self.tokens[self.current_token].string];
         self.advance_match(TokenKind::Ident)?;
-        while self.peek() == TokenKind::ColonColon {
+        while self.check(TokenKind::ColonColon) {
             self.advance();
             elements.push(self.tokens[self.current_token].string);
             self.advance_match(TokenKind::Ident)?;
@@ -508,16 +1044,16 @@ impl<'a> Parser<'a> {
         let path = self.parse_path()?;
         let mut generics = vec![];
         let mut array_dim = 0;
-        if self.peek() == TokenKind::Ls {
+        if self.check(TokenKind::Ls) {
             self.advance();
             generics.push(self.parse_type_info()?);
-            while self.peek() == TokenKind::Comma {
+            while self.check(TokenKind::Comma) {
                 self.advance();
                 generics.push(self.parse_type_info()?);
             }
-            self.advance_match(TokenKind::Gt)?;
+            self.advance_match_gt()?;
         }
-        while self.peek() == TokenKind::OpeningDelim(DelimTokenKind::SBracket) {
+        while self.check(TokenKind::OpeningDelim(DelimTokenKind::SBracket)) {
             self.advance();
             self.advance_match(TokenKind::ClosingDelim(DelimTokenKind::SBracket))?;
             array_dim += 1;
@@ -536,14 +1072,14 @@ impl<'a> Parser<'a> {
 
         let path = self.parse_path()?;
         let mut generics = vec![];
-        if self.peek() == TokenKind::Ls {
+        if self.check(TokenKind::Ls) {
             self.advance();
             generics.push(self.parse_partial_type_info()?);
-            while self.peek() == TokenKind::Comma {
+            while self.check(TokenKind::Comma) {
                 self.advance();
                 generics.push(self.parse_partial_type_info()?);
             }
-            self.advance_match(TokenKind::Gt)?;
+            self.advance_match_gt()?;
         }
 
         Ok(ASTPartialTypeInfo {
@@ -569,11 +1105,69 @@ impl<'a> Parser<'a> {
         let starting_token = self.current_token;
 
         self.advance_match(TokenKind::Keyword(KeywordTokenKind::Use))?;
-        let path = self.parse_path()?;
+        let path_start = self.current_token;
+        let mut elements = vec![self.tokens[self.current_token].string];
+        self.advance_match(TokenKind::Ident)?;
+        let mut glob = false;
+        while self.check(TokenKind::ColonColon) {
+            self.advance();
+            if self.check(TokenKind::BinOp(BinOpTokenKind::Star)) {
+                self.advance();
+                glob = true;
+                break;
+            }
+            elements.push(self.tokens[self.current_token].string);
+            self.advance_match(TokenKind::Ident)?;
+        }
+        let path = ASTPath {
+            span: TokenSpan::new_rn_ex(path_start, self.current_token),
+            elements,
+        };
+
+        let kind = if glob {
+            ASTUseKind::Glob
+        } else if self.check(TokenKind::Keyword(KeywordTokenKind::As)) {
+            self.advance();
+            let alias = self.tokens[self.current_token].string;
+            self.advance_match(TokenKind::Ident)?;
+            ASTUseKind::Single(Some(alias))
+        } else {
+            ASTUseKind::Single(None)
+        };
         self.advance_match(TokenKind::Semicolon)?;
         Ok(ASTUse {
             span: TokenSpan::new_rn_ex(starting_token, self.current_token),
             path,
+            kind,
+        })
+    }
+
+    pub fn parse_type_alias(&mut self) -> Result<ASTTypeAlias<'a>, ParserError> {
+        let starting_token = self.current_token;
+
+        self.advance_match(TokenKind::Keyword(KeywordTokenKind::Type))?;
+        let name = self.tokens[self.current_token].string;
+        self.advance_match(TokenKind::Ident)?;
+        let mut generics = vec![];
+        if self.check(TokenKind::Ls) {
+            self.advance();
+            generics.push(self.tokens[self.current_token].string);
+            self.advance_match(TokenKind::Ident)?;
+            while self.check(TokenKind::Comma) {
+                self.advance();
+                generics.push(self.tokens[self.current_token].string);
+                self.advance_match(TokenKind::Ident)?;
+            }
+            self.advance_match_gt()?;
+        }
+        self.advance_match(TokenKind::Eq)?;
+        let type_info = self.parse_type_info()?;
+        self.advance_match(TokenKind::Semicolon)?;
+        Ok(ASTTypeAlias {
+            span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+            name,
+            generics,
+            type_info,
         })
     }
 
@@ -612,14 +1206,19 @@ impl<'a> Parser<'a> {
         let name = self.tokens[self.current_token].string;
         self.advance_match(TokenKind::Ident)?;
         let mut super_requirements = vec![];
-        if self.peek() == TokenKind::Colon {
+        if self.check(TokenKind::Colon) {
             self.advance();
             super_requirements.push(self.parse_partial_type_info()?);
-            while self.peek() == TokenKind::Colon {
+            while self.check(TokenKind::Colon) {
                 self.advance();
                 super_requirements.push(self.parse_partial_type_info()?);
             }
         }
+        // "U impl Wrapper<T>", interfaces end up with the other requirements
+        while self.check(TokenKind::Keyword(KeywordTokenKind::Impl)) {
+            self.advance();
+            super_requirements.push(self.parse_partial_type_info()?);
+        }
 
         Ok(ASTGenericBound {
             span: TokenSpan::new_rn_ex(starting_token, self.current_token),
@@ -629,15 +1228,15 @@ impl<'a> Parser<'a> {
     }
 
     pub fn parse_generic_bounds(&mut self) -> Result<Vec<ASTGenericBound<'a>>, ParserError> {
-        if self.peek() == TokenKind::Ls {
+        if self.check(TokenKind::Ls) {
             let mut bounds = vec![];
             self.advance();
             bounds.push(self.parse_generic_bound()?);
-            while self.peek() == TokenKind::Comma {
+            while self.check(TokenKind::Comma) {
                 self.advance();
                 bounds.push(self.parse_generic_bound()?);
             }
-            self.advance_match(TokenKind::Gt)?;
+            self.advance_match_gt()?;
             Ok(bounds)
         } else {
             Ok(vec![])
@@ -660,28 +1259,47 @@ impl<'a> Parser<'a> {
         })
     }
 
+    pub fn parse_parameters(&mut self) -> Result<Vec<ASTNameAndType<'a>>, ParserError> {
+        self.advance_match(TokenKind::OpeningDelim(DelimTokenKind::Paren))?;
+        let mut parameters = vec![];
+        if !self.check(TokenKind::ClosingDelim(DelimTokenKind::Paren)) {
+            parameters.push(self.parse_name_and_type()?);
+            while !self.check(TokenKind::ClosingDelim(DelimTokenKind::Paren)) {
+                self.advance_match(TokenKind::Comma)?;
+                parameters.push(self.parse_name_and_type()?);
+            }
+        }
+        self.advance();
+        Ok(parameters)
+    }
+
     pub fn parse_member(&mut self) -> Result<ASTMember<'a>, ParserError> {
         let starting_token = self.current_token;
 
         let visibility = self.parse_visibility();
         let modifiers = self.parse_modifiers();
 
-        let kind = if self.peek() == TokenKind::Keyword(KeywordTokenKind::Fn) {
-            self.advance();
+        // Fields are followed by ":", constructors by their parameters
+        let kind = if self.peek() == TokenKind::Ident
+            && self.peek_at(1) == TokenKind::OpeningDelim(DelimTokenKind::Paren)
+        {
             let name = self.tokens[self.current_token].string;
-            self.advance_match(TokenKind::Ident)?;
-            self.advance_match(TokenKind::OpeningDelim(DelimTokenKind::Paren))?;
-            let mut parameters = vec![];
-            if self.peek() != TokenKind::ClosingDelim(DelimTokenKind::Paren) {
-                parameters.push(self.parse_name_and_type()?);
-                while self.peek() != TokenKind::ClosingDelim(DelimTokenKind::Paren) {
-                    self.advance_match(TokenKind::Comma)?;
-                    parameters.push(self.parse_name_and_type()?);
-                }
+            self.advance();
+            let parameters = self.parse_parameters()?;
+            let block = self.parse_statement_block()?;
+
+            ASTMemberKind::Constructor {
+                name,
+                parameters,
+                block,
             }
+        } else if self.check(TokenKind::Keyword(KeywordTokenKind::Fn)) {
             self.advance();
+            let name = self.tokens[self.current_token].string;
+            self.advance_match(TokenKind::Ident)?;
+            let parameters = self.parse_parameters()?;
             let name_and_type_start = self.current_token;
-            let type_info = if self.peek() == TokenKind::Arrow {
+            let type_info = if self.check(TokenKind::Arrow) {
                 self.advance();
                 self.parse_type_info()?
             } else {
@@ -696,7 +1314,7 @@ impl<'a> Parser<'a> {
                 }
             };
             let name_and_type_end = self.current_token;
-            let block = if self.peek() == TokenKind::Semicolon {
+            let block = if self.check(TokenKind::Semicolon) {
                 self.advance();
                 None
             } else {
@@ -714,7 +1332,7 @@ impl<'a> Parser<'a> {
             }
         } else {
             let name_and_type = self.parse_name_and_type()?;
-            let expression = if self.peek() == TokenKind::Eq {
+            let expression = if self.check(TokenKind::Eq) {
                 self.advance();
                 Some(self.parse_expression()?)
             } else {
@@ -736,6 +1354,69 @@ impl<'a> Parser<'a> {
         })
     }
 
+    pub fn parse_variant(&mut self) -> Result<ASTVariant<'a>, ParserError> {
+        let starting_token = self.current_token;
+
+        let name = self.tokens[self.current_token].string;
+        self.advance_match(TokenKind::Ident)?;
+        let kind = if self.check(TokenKind::OpeningDelim(DelimTokenKind::Paren)) {
+            self.advance();
+            let mut fields = vec![];
+            while !self.check(TokenKind::ClosingDelim(DelimTokenKind::Paren)) {
+                fields.push(self.parse_type_info()?);
+                if self.check(TokenKind::Comma) {
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+            self.advance_match(TokenKind::ClosingDelim(DelimTokenKind::Paren))?;
+            ASTVariantKind::Tuple(fields)
+        } else if self.check(TokenKind::OpeningDelim(DelimTokenKind::CBracket)) {
+            self.advance();
+            let mut fields = vec![];
+            while !self.check(TokenKind::ClosingDelim(DelimTokenKind::CBracket)) {
+                fields.push(self.parse_name_and_type()?);
+                if self.check(TokenKind::Comma) {
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+            self.advance_match(TokenKind::ClosingDelim(DelimTokenKind::CBracket))?;
+            ASTVariantKind::Struct(fields)
+        } else {
+            ASTVariantKind::Unit
+        };
+
+        Ok(ASTVariant {
+            span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+            name,
+            kind,
+        })
+    }
+
+    // Members between braces, recovering at member boundaries
+    pub fn parse_members(&mut self) -> Result<Vec<ASTMember<'a>>, ParserError> {
+        self.advance_match(TokenKind::OpeningDelim(DelimTokenKind::CBracket))?;
+        let mut members = vec![];
+        while !self.check(TokenKind::ClosingDelim(DelimTokenKind::CBracket)) {
+            if self.peek() == TokenKind::EndOfFile {
+                return Err(self.unexpected());
+            }
+            match self.parse_member() {
+                Ok(member) => members.push(member),
+                Err(err) => {
+                    self.report(err);
+                    self.synchronize_member();
+                }
+            }
+        }
+        self.advance();
+
+        Ok(members)
+    }
+
     pub fn parse_type(&mut self) -> Result<ASTType<'a>, ParserError> {
         let starting_token = self.current_token;
 
@@ -751,27 +1432,22 @@ impl<'a> Parser<'a> {
                 self.advance_match(TokenKind::Ident)?;
                 generics = self.parse_generic_bounds()?;
 
-                let super_class = if self.peek() == TokenKind::Colon {
+                let super_class = if self.check(TokenKind::Colon) {
                     self.advance();
                     Some(self.parse_partial_type_info()?)
                 } else {
                     None
                 };
                 let mut impls = vec![];
-                if self.peek() == TokenKind::Keyword(KeywordTokenKind::Impl) {
+                if self.check(TokenKind::Keyword(KeywordTokenKind::Impl)) {
                     self.advance();
                     impls.push(self.parse_partial_type_info()?);
-                    while self.peek() == TokenKind::Comma {
+                    while self.check(TokenKind::Comma) {
                         self.advance();
                         impls.push(self.parse_partial_type_info()?);
                     }
                 }
-                self.advance_match(TokenKind::OpeningDelim(DelimTokenKind::CBracket))?;
-                let mut members = vec![];
-                while self.peek() != TokenKind::ClosingDelim(DelimTokenKind::CBracket) {
-                    members.push(self.parse_member()?);
-                }
-                self.advance();
+                let members = self.parse_members()?;
 
                 ASTTypeKind::Class {
                     super_class,
@@ -779,12 +1455,53 @@ impl<'a> Parser<'a> {
                     impls,
                 }
             }
-            _ => {
+            TokenKind::Keyword(KeywordTokenKind::Inter) => {
                 self.advance();
-                return Err(ParserError::new(
-                    TokenSpan::new_rn_ex(starting_token, self.current_token),
-                    self.peek(),
-                ));
+                name = self.tokens[self.current_token].string;
+                self.advance_match(TokenKind::Ident)?;
+                generics = self.parse_generic_bounds()?;
+
+                let mut super_interfaces = vec![];
+                if self.check(TokenKind::Colon) {
+                    self.advance();
+                    super_interfaces.push(self.parse_partial_type_info()?);
+                    while self.check(TokenKind::Comma) {
+                        self.advance();
+                        super_interfaces.push(self.parse_partial_type_info()?);
+                    }
+                }
+                let members = self.parse_members()?;
+
+                ASTTypeKind::Interface {
+                    super_interfaces,
+                    members,
+                }
+            }
+            TokenKind::Keyword(KeywordTokenKind::Enum) => {
+                self.advance();
+                name = self.tokens[self.current_token].string;
+                self.advance_match(TokenKind::Ident)?;
+                generics = self.parse_generic_bounds()?;
+
+                self.advance_match(TokenKind::OpeningDelim(DelimTokenKind::CBracket))?;
+                let mut variants = vec![];
+                while !self.check(TokenKind::ClosingDelim(DelimTokenKind::CBracket)) {
+                    variants.push(self.parse_variant()?);
+                    if self.check(TokenKind::Comma) {
+                        self.advance();
+                    } else {
+                        break;
+                    }
+                }
+                self.advance_match(TokenKind::ClosingDelim(DelimTokenKind::CBracket))?;
+
+                ASTTypeKind::Enum { variants }
+            }
+            _ => {
+                self.expected.push(TokenKind::Keyword(KeywordTokenKind::Class));
+                self.expected.push(TokenKind::Keyword(KeywordTokenKind::Inter));
+                self.expected.push(TokenKind::Keyword(KeywordTokenKind::Enum));
+                return Err(self.unexpected());
             }
         };
 
@@ -798,37 +1515,333 @@ impl<'a> Parser<'a> {
         })
     }
 
-    pub fn parse(&mut self) -> Result<ASTRoot<'a>, ParserError> {
+    // Errors on tokens the lexer could not make sense of were already reported by the lexer
+    fn report(&mut self, err: ParserError) {
+        if err.got == TokenKind::Error {
+            return;
+        }
+        // An unexpected end of file bubbles up through every enclosing block, only report it once
+        if let Some(last) = self.errors.last() {
+            if last.span.base == err.span.base {
+                return;
+            }
+        }
+        self.errors.push(err);
+    }
+
+    // Skips to the end of the current statement, stopping after a ";" or before the "}" closing the block
+    fn synchronize_statement(&mut self) {
+        let mut depth = 0;
+        loop {
+            match self.peek() {
+                TokenKind::EndOfFile => return,
+                TokenKind::Semicolon if depth == 0 => {
+                    self.advance();
+                    return;
+                }
+                TokenKind::OpeningDelim(DelimTokenKind::CBracket) => depth += 1,
+                TokenKind::ClosingDelim(DelimTokenKind::CBracket) => {
+                    if depth == 0 {
+                        return;
+                    }
+                    depth -= 1;
+                }
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    // Skips to the start of the next member, or before the "}" closing the type
+    fn synchronize_member(&mut self) {
+        let mut depth = 0;
+        loop {
+            match self.peek() {
+                TokenKind::EndOfFile => return,
+                TokenKind::Semicolon if depth == 0 => {
+                    self.advance();
+                    return;
+                }
+                TokenKind::Keyword(
+                    KeywordTokenKind::Pub
+                    | KeywordTokenKind::Priv
+                    | KeywordTokenKind::Abstract
+                    | KeywordTokenKind::Static
+                    | KeywordTokenKind::Native
+                    | KeywordTokenKind::Fn,
+                ) if depth == 0 => return,
+                TokenKind::OpeningDelim(DelimTokenKind::CBracket) => depth += 1,
+                TokenKind::ClosingDelim(DelimTokenKind::CBracket) => {
+                    if depth == 0 {
+                        return;
+                    }
+                    depth -= 1;
+                    // End of a method body
+                    if depth == 0 {
+                        self.advance();
+                        return;
+                    }
+                }
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    // Skips to the start of the next type
+    fn synchronize_type(&mut self) {
+        let mut depth = 0;
+        loop {
+            match self.peek() {
+                TokenKind::EndOfFile => return,
+                TokenKind::Keyword(
+                    KeywordTokenKind::Pub
+                    | KeywordTokenKind::Priv
+                    | KeywordTokenKind::Abstract
+                    | KeywordTokenKind::Static
+                    | KeywordTokenKind::Native
+                    | KeywordTokenKind::Class
+                    | KeywordTokenKind::Inter
+                    | KeywordTokenKind::Enum,
+                ) if depth == 0 => return,
+                TokenKind::OpeningDelim(DelimTokenKind::CBracket) => depth += 1,
+                TokenKind::ClosingDelim(DelimTokenKind::CBracket) => {
+                    if depth > 0 {
+                        depth -= 1;
+                        if depth == 0 {
+                            self.advance();
+                            return;
+                        }
+                    }
+                }
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    // Parses the whole file, recovering from syntax errors so that all of them get reported at once
+    pub fn parse_recovering(&mut self) -> (ASTRoot<'a>, Vec<ParserError>) {
         let starting_token = self.current_token;
 
-        let mod_decl = self.parse_mod()?;
+        let mod_decl = match self.parse_mod() {
+            Ok(mod_decl) => mod_decl,
+            Err(err) => {
+                self.report(err);
+                self.synchronize_statement();
+                ASTMod {
+                    span: TokenSpan::new_rn_ex(starting_token, self.current_token),
+                    path: ASTPath {
+                        span: TokenSpan::new(starting_token, 0),
+                        elements: vec![],
+                    },
+                }
+            }
+        };
         let mut use_decls = vec![];
-        while self.peek() == TokenKind::Keyword(KeywordTokenKind::Use) {
-            use_decls.push(self.parse_use()?);
+        while self.check(TokenKind::Keyword(KeywordTokenKind::Use)) {
+            match self.parse_use() {
+                Ok(use_decl) => use_decls.push(use_decl),
+                Err(err) => {
+                    self.report(err);
+                    self.synchronize_statement();
+                }
+            }
         }
+        let mut type_aliases = vec![];
         let mut types = vec![];
 
-        while self.peek() != TokenKind::EndOfFile {
-            types.push(self.parse_type()?);
+        while !self.check(TokenKind::EndOfFile) {
+            let type_start = self.current_token;
+            let parsed = if self.check(TokenKind::Keyword(KeywordTokenKind::Type)) {
+                self.parse_type_alias().map(|type_alias| type_aliases.push(type_alias))
+            } else {
+                self.parse_type().map(|parsed_type| types.push(parsed_type))
+            };
+            match parsed {
+                Ok(()) => {}
+                Err(err) => {
+                    self.report(err);
+                    self.synchronize_type();
+                    // Always make progress, the error could be on a token synchronization stops at
+                    if self.current_token == type_start {
+                        self.advance();
+                    }
+                }
+            }
         }
 
-        Ok(ASTRoot {
+        let root = ASTRoot {
             span: TokenSpan::new_rn_ex(starting_token, self.current_token),
             types,
             mod_decl,
             use_decls,
-        })
+            type_aliases,
+        };
+        (root, std::mem::take(&mut self.errors))
     }
+
+    pub fn parse(&mut self) -> Result<ASTRoot<'a>, ParserError> {
+        let (root, mut errors) = self.parse_recovering();
+        if errors.is_empty() {
+            Ok(root)
+        } else {
+            Err(errors.remove(0))
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ParserErrorKind {
+    UnexpectedToken,
+    ExpectedExpression,
+    ExpectedPattern,
+    ChainedComparison,
+    ChainedRange,
 }
 
 #[derive(Debug)]
 pub struct ParserError {
+    pub kind: ParserErrorKind,
     pub span: TokenSpan,
     pub got: TokenKind,
+    // Tokens that would have been accepted instead of "got"
+    pub expected: Vec<TokenKind>,
 }
 
 impl ParserError {
-    pub fn new(span: TokenSpan, got: TokenKind) -> Self {
-        ParserError { span, got }
+    pub fn new(span: TokenSpan, got: TokenKind, expected: Vec<TokenKind>) -> Self {
+        ParserError::new_with_kind(ParserErrorKind::UnexpectedToken, span, got, expected)
+    }
+
+    pub fn new_with_kind(
+        kind: ParserErrorKind,
+        span: TokenSpan,
+        got: TokenKind,
+        expected: Vec<TokenKind>,
+    ) -> Self {
+        ParserError {
+            kind,
+            span,
+            got,
+            expected,
+        }
+    }
+}
+
+impl Display for ParserError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            ParserErrorKind::UnexpectedToken => {
+                if self.expected.is_empty() {
+                    return write!(f, "unexpected {}", self.got);
+                }
+                write!(f, "expected ")?;
+                for (i, kind) in self.expected.iter().enumerate() {
+                    if i > 0 {
+                        let separator = if i == self.expected.len() - 1 { " or " } else { ", " };
+                        write!(f, "{}", separator)?;
+                    }
+                    write!(f, "{}", kind)?;
+                }
+                write!(f, ", found {}", self.got)
+            }
+            ParserErrorKind::ExpectedExpression => write!(f, "expected expression, found {}", self.got),
+            ParserErrorKind::ExpectedPattern => write!(f, "expected pattern, found {}", self.got),
+            ParserErrorKind::ChainedComparison => {
+                write!(f, "comparison operators cannot be chained, found {}", self.got)
+            }
+            ParserErrorKind::ChainedRange => {
+                write!(f, "ranges cannot be chained, found {}", self.got)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{ASTExpr, ASTExprKind};
+    use crate::lexer::{Lexer, TokenKind};
+    use crate::parser::{Parser, ParserError, ParserErrorKind};
+
+    // Binary and unary operations in parentheses, to make the shape of the tree visible
+    fn render(expr: &ASTExpr) -> String {
+        match &expr.kind {
+            ASTExprKind::BinOp(left, operator, right) => {
+                format!("({} {:?} {})", render(left), operator, render(right))
+            }
+            ASTExprKind::PreOp(operator, on) => format!("({:?} {})", operator, render(on)),
+            ASTExprKind::Ident(name) => name.to_string(),
+            ASTExprKind::Num(value, _) => value.to_string(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn expression(source: &str) -> Result<String, ParserError> {
+        Parser::new(Lexer::new(source).lex().unwrap())
+            .parse_expression()
+            .map(|expr| render(&expr))
+    }
+
+    fn syntax_errors(source: &str) -> Vec<String> {
+        let (_, errors) = Parser::new(Lexer::new(source).lex().unwrap()).parse_recovering();
+        errors.iter().map(|error| error.to_string()).collect()
+    }
+
+    #[test]
+    fn operators_follow_their_precedence_and_associativity() {
+        for (source, expected) in [
+            ("a != b", "(a NotEq b)"),
+            ("a - b - c", "((a Minus b) Minus c)"),
+            ("a = b = c", "(a Assign (b Assign c))"),
+            ("a += b -= c", "(a PlusAssign (b MinusAssign c))"),
+            ("a + b * c % d", "(a Plus ((b Mul c) Mod d))"),
+            ("a || b && c == d", "(a Or (b And (c Eq d)))"),
+            ("a | b ^ c & d << 1", "(a BitOr (b BitXor (c BitAnd (d Shl 1))))"),
+            ("a < b == c > d", "((a Ls b) Eq (c Gt d))"),
+            ("!a && -b", "((Not a) And (Minus b))"),
+        ] {
+            assert_eq!(expression(source).unwrap(), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn comparisons_cannot_be_chained() {
+        for source in ["a < b < c", "a == b != c", "a >= b <= c"] {
+            let error = expression(source).err().unwrap();
+            assert_eq!(error.kind, ParserErrorKind::ChainedComparison, "{}", source);
+        }
+        assert_eq!(expression("0..1..2").err().unwrap().kind, ParserErrorKind::ChainedRange);
+    }
+
+    #[test]
+    fn errors_list_what_was_expected() {
+        assert_eq!(syntax_errors("mod a; class A { fn f() { a b } }"), ["expected `;` or `}`, found identifier"]);
+        let error = Parser::new(Lexer::new("mod a; class A { fn f() { a b } }").lex().unwrap()).parse().err().unwrap();
+        // The span is the unexpected token itself
+        assert_eq!((error.got, error.span.base, error.span.len), (TokenKind::Ident, 12, 1));
+    }
+
+    #[test]
+    fn parsing_recovers_at_statements_members_and_types() {
+        let errors = syntax_errors(
+            "mod a;
+            class A {
+                fn f() { let = 1; g(; h(); }
+                fn g( { }
+                fn h() { }
+            }
+            class B { x: u64 y: u64 }
+            class C { }",
+        );
+        assert_eq!(
+            errors,
+            [
+                "expected identifier, found `=`",
+                "expected expression, found `;`",
+                "expected `)` or identifier, found `{`",
+                "expected `::`, `<`, `[`, `=` or `;`, found identifier",
+            ]
+        );
     }
 }
@@ -1,8 +1,8 @@
 use crate::ast::visitor::ASTVisitor;
 use crate::ast::{
-    ASTExpr, ASTExprKind, ASTGenericBound, ASTMember, ASTMemberKind, ASTModifier,
-    ASTPartialTypeInfo, ASTPath, ASTRoot, ASTStatement, ASTStatementBlock, ASTStatementKind,
-    ASTType, ASTTypeInfo, ASTTypeKind, ASTVisibility,
+    ASTExpr, ASTExprKind, ASTGenericBound, ASTMatchArm, ASTMember, ASTMemberKind, ASTModifier,
+    ASTPartialTypeInfo, ASTPath, ASTPattern, ASTPatternKind, ASTRoot, ASTStatement, ASTStatementBlock, ASTStatementKind,
+    ASTType, ASTTypeAlias, ASTTypeInfo, ASTTypeKind, ASTUse, ASTUseKind, ASTVariant, ASTVariantKind, ASTVisibility,
 };
 use crate::reporting::string_tree::StringTree;
 
@@ -18,6 +18,14 @@ impl ASTDumperVisitor {
     }
 }
 
+// " 'label" when there is one
+fn label_suffix(label: &Option<&str>) -> String {
+    match label {
+        Some(label) => format!(" '{}", label),
+        None => String::new(),
+    }
+}
+
 impl ASTVisitor for ASTDumperVisitor {
     fn walk_expr(&mut self, obj: &ASTExpr) {
         match &obj.kind {
@@ -28,13 +36,22 @@ impl ASTVisitor for ASTDumperVisitor {
                 self.tree.add_branch(s);
             }
             ASTExprKind::StringLiteral(s) => {
-                self.tree.add_branch(s);
+                self.tree.add_branch(&format!("{:?}", s));
             }
-            ASTExprKind::Num(s) => {
-                self.tree.add_branch(s);
+            ASTExprKind::CharLiteral(c) => {
+                self.tree.add_branch(&format!("{:?}", c));
             }
-            ASTExprKind::Float(s) => {
-                self.tree.add_branch(s);
+            ASTExprKind::Num(value, suffix) => {
+                self.tree.add_branch(&match suffix {
+                    Some(suffix) => format!("{} {:?}", value, suffix),
+                    None => format!("{}", value),
+                });
+            }
+            ASTExprKind::Float(value, suffix) => {
+                self.tree.add_branch(&match suffix {
+                    Some(suffix) => format!("{:?} {:?}", value, suffix),
+                    None => format!("{:?}", value),
+                });
             }
             ASTExprKind::Boolean(b) => {
                 self.tree.add_branch(if *b { "true" } else { "false" });
@@ -42,6 +59,12 @@ impl ASTVisitor for ASTDumperVisitor {
             ASTExprKind::Null => {
                 self.tree.add_branch("null");
             }
+            ASTExprKind::This => {
+                self.tree.add_branch("this");
+            }
+            ASTExprKind::Super => {
+                self.tree.add_branch("super");
+            }
             ASTExprKind::BinOp(left, op, right) => {
                 let mut branch = ASTDumperVisitor::new(format!("{:?}", op));
                 branch.walk_expr(left);
@@ -97,6 +120,52 @@ impl ASTVisitor for ASTDumperVisitor {
 
                 self.tree.add_tree_branch(branch.tree);
             }
+            ASTExprKind::StructLiteral(expr, fields) => {
+                let mut branch = ASTDumperVisitor::new(format!("struct literal"));
+
+                let mut branch_inner = ASTDumperVisitor::new(format!("of"));
+                branch_inner.walk_expr(expr);
+                branch.tree.add_tree_branch(branch_inner.tree);
+
+                for (name, field) in fields {
+                    let mut branch_inner = ASTDumperVisitor::new(format!("field {}", name));
+                    branch_inner.walk_expr(field);
+                    branch.tree.add_tree_branch(branch_inner.tree);
+                }
+
+                self.tree.add_tree_branch(branch.tree);
+            }
+            ASTExprKind::Range(start, end, inclusive) => {
+                let mut branch = ASTDumperVisitor::new(format!(
+                    "range {}",
+                    if *inclusive { "..=" } else { ".." }
+                ));
+                branch.walk_expr(start);
+                branch.walk_expr(end);
+                self.tree.add_tree_branch(branch.tree);
+            }
+            ASTExprKind::New(type_info, args) => {
+                let mut branch = ASTDumperVisitor::new(format!("new"));
+
+                let mut branch_inner = ASTDumperVisitor::new(format!("type"));
+                branch_inner.walk_type_info(type_info);
+                branch.tree.add_tree_branch(branch_inner.tree);
+
+                let mut branch_inner = ASTDumperVisitor::new(format!("args"));
+                for arg in args {
+                    branch_inner.walk_expr(arg);
+                }
+                branch.tree.add_tree_branch(branch_inner.tree);
+
+                self.tree.add_tree_branch(branch.tree);
+            }
+            ASTExprKind::SuperCall(args) => {
+                let mut branch = ASTDumperVisitor::new(format!("super call"));
+                for arg in args {
+                    branch.walk_expr(arg);
+                }
+                self.tree.add_tree_branch(branch.tree);
+            }
             ASTExprKind::Block(block) => {
                 let mut branch = ASTDumperVisitor::new(format!("block"));
                 branch.walk_statement_block(block);
@@ -119,12 +188,24 @@ impl ASTVisitor for ASTDumperVisitor {
 
                 self.tree.add_tree_branch(branch.tree);
             }
-            ASTExprKind::Loop(block) => {
-                let mut branch = ASTDumperVisitor::new(format!("loop"));
+            ASTExprKind::Loop(label, block) => {
+                let mut branch = ASTDumperVisitor::new(format!("loop{}", label_suffix(label)));
                 branch.walk_statement_block(block);
                 self.tree.add_tree_branch(branch.tree);
             }
-            ASTExprKind::Match() => {}
+            ASTExprKind::Match(scrutinee, arms) => {
+                let mut branch = ASTDumperVisitor::new(format!("match"));
+
+                let mut branch_inner = ASTDumperVisitor::new(format!("on"));
+                branch_inner.walk_expr(scrutinee);
+                branch.tree.add_tree_branch(branch_inner.tree);
+
+                for arm in arms {
+                    branch.walk_match_arm(arm);
+                }
+
+                self.tree.add_tree_branch(branch.tree);
+            }
             ASTExprKind::If(cond, block) => {
                 let mut branch = ASTDumperVisitor::new(format!("if"));
 
@@ -138,8 +219,8 @@ impl ASTVisitor for ASTDumperVisitor {
 
                 self.tree.add_tree_branch(branch.tree);
             }
-            ASTExprKind::While(cond, block) => {
-                let mut branch = ASTDumperVisitor::new(format!("while"));
+            ASTExprKind::While(label, cond, block) => {
+                let mut branch = ASTDumperVisitor::new(format!("while{}", label_suffix(label)));
 
                 let mut branch_inner = ASTDumperVisitor::new(format!("condition"));
                 branch_inner.walk_expr(cond);
@@ -151,7 +232,120 @@ impl ASTVisitor for ASTDumperVisitor {
 
                 self.tree.add_tree_branch(branch.tree);
             }
-            ASTExprKind::For() => {}
+            ASTExprKind::For(label, name, iterable, block) => {
+                let mut branch =
+                    ASTDumperVisitor::new(format!("for {}{}", name, label_suffix(label)));
+
+                let mut branch_inner = ASTDumperVisitor::new(format!("in"));
+                branch_inner.walk_expr(iterable);
+                branch.tree.add_tree_branch(branch_inner.tree);
+
+                let mut branch_inner = ASTDumperVisitor::new(format!("do"));
+                branch_inner.walk_statement_block(block);
+                branch.tree.add_tree_branch(branch_inner.tree);
+
+                self.tree.add_tree_branch(branch.tree);
+            }
+            ASTExprKind::Break(label, value) => {
+                let mut branch = ASTDumperVisitor::new(format!("break{}", label_suffix(label)));
+                if let Some(value) = value {
+                    branch.walk_expr(value);
+                }
+                self.tree.add_tree_branch(branch.tree);
+            }
+            ASTExprKind::Continue(label) => {
+                self.tree
+                    .add_branch(&format!("continue{}", label_suffix(label)));
+            }
+            ASTExprKind::Return(value) => {
+                let mut branch = ASTDumperVisitor::new(format!("return"));
+                if let Some(value) = value {
+                    branch.walk_expr(value);
+                }
+                self.tree.add_tree_branch(branch.tree);
+            }
+        }
+    }
+
+    fn walk_match_arm(&mut self, obj: &ASTMatchArm) {
+        let mut branch = ASTDumperVisitor::new(format!("arm"));
+
+        let mut branch_inner = ASTDumperVisitor::new(format!("pattern"));
+        branch_inner.walk_pattern(&obj.pattern);
+        branch.tree.add_tree_branch(branch_inner.tree);
+
+        if let Some(guard) = &obj.guard {
+            let mut branch_inner = ASTDumperVisitor::new(format!("guard"));
+            branch_inner.walk_expr(guard);
+            branch.tree.add_tree_branch(branch_inner.tree);
+        }
+
+        let mut branch_inner = ASTDumperVisitor::new(format!("body"));
+        branch_inner.walk_expr(&obj.body);
+        branch.tree.add_tree_branch(branch_inner.tree);
+
+        self.tree.add_tree_branch(branch.tree);
+    }
+
+    fn walk_pattern(&mut self, obj: &ASTPattern) {
+        match &obj.kind {
+            ASTPatternKind::Wildcard => {
+                self.tree.add_branch("_");
+            }
+            ASTPatternKind::Binding(name) => {
+                self.tree.add_branch(&format!("binding {}", name));
+            }
+            ASTPatternKind::Literal(literal) => {
+                self.walk_expr(literal);
+            }
+            ASTPatternKind::Range(start, end, inclusive) => {
+                let mut branch = ASTDumperVisitor::new(format!(
+                    "{}",
+                    if *inclusive { "range ..=" } else { "range .." }
+                ));
+                branch.walk_expr(start);
+                branch.walk_expr(end);
+                self.tree.add_tree_branch(branch.tree);
+            }
+            ASTPatternKind::UnitVariant(path) => {
+                self.tree.add_branch(&format!("variant {}", path.elements.join("::")));
+            }
+            ASTPatternKind::TupleVariant(path, fields) => {
+                let mut branch =
+                    ASTDumperVisitor::new(format!("variant {}", path.elements.join("::")));
+                for field in fields {
+                    branch.walk_pattern(field);
+                }
+                self.tree.add_tree_branch(branch.tree);
+            }
+            ASTPatternKind::StructVariant(path, fields, has_rest) => {
+                let mut branch = ASTDumperVisitor::new(format!(
+                    "variant {}{}",
+                    path.elements.join("::"),
+                    if *has_rest { " (..)" } else { "" }
+                ));
+                for (name, field) in fields {
+                    let mut branch_inner = ASTDumperVisitor::new(format!("field {}", name));
+                    branch_inner.walk_pattern(field);
+                    branch.tree.add_tree_branch(branch_inner.tree);
+                }
+                self.tree.add_tree_branch(branch.tree);
+            }
+            ASTPatternKind::TypeTest(name, type_info) => {
+                let mut branch = ASTDumperVisitor::new(format!(
+                    "type test {}",
+                    name.unwrap_or("_")
+                ));
+                branch.walk_type_info(type_info);
+                self.tree.add_tree_branch(branch.tree);
+            }
+            ASTPatternKind::Or(alternatives) => {
+                let mut branch = ASTDumperVisitor::new(format!("or"));
+                for alternative in alternatives {
+                    branch.walk_pattern(alternative);
+                }
+                self.tree.add_tree_branch(branch.tree);
+            }
         }
     }
 
@@ -228,6 +422,25 @@ impl ASTVisitor for ASTDumperVisitor {
         self.tree.add_branch(&obj.elements.join("::"));
     }
 
+    fn walk_type_alias(&mut self, obj: &ASTTypeAlias) {
+        let mut branch = ASTDumperVisitor::new(if obj.generics.is_empty() {
+            format!("type alias {}", obj.name)
+        } else {
+            format!("type alias {}<{}>", obj.name, obj.generics.join(", "))
+        });
+        branch.walk_type_info(&obj.type_info);
+        self.tree.add_tree_branch(branch.tree);
+    }
+
+    fn walk_use(&mut self, obj: &ASTUse) {
+        let path = obj.path.elements.join("::");
+        match &obj.kind {
+            ASTUseKind::Single(Some(alias)) => self.tree.add_branch(&format!("{} as {}", path, alias)),
+            ASTUseKind::Single(None) => self.tree.add_branch(&path),
+            ASTUseKind::Glob => self.tree.add_branch(&format!("{}::*", path)),
+        }
+    }
+
     fn walk_root(&mut self, obj: &ASTRoot) {
         let mut branch = ASTDumperVisitor::new(format!("ast root"));
 
@@ -241,6 +454,12 @@ impl ASTVisitor for ASTDumperVisitor {
         }
         branch.tree.add_tree_branch(branch_inner.tree);
 
+        let mut branch_inner = ASTDumperVisitor::new(format!("type aliases"));
+        for type_alias in &obj.type_aliases {
+            branch_inner.walk_type_alias(type_alias);
+        }
+        branch.tree.add_tree_branch(branch_inner.tree);
+
         let mut branch_inner = ASTDumperVisitor::new(format!("types"));
         for type_decl in &obj.types {
             branch_inner.walk_type(type_decl);
@@ -278,6 +497,27 @@ impl ASTVisitor for ASTDumperVisitor {
         self.tree.add_tree_branch(branch.tree);
     }
 
+    fn walk_variant(&mut self, obj: &ASTVariant) {
+        let mut branch = ASTDumperVisitor::new(format!("variant {}", obj.name));
+        match &obj.kind {
+            ASTVariantKind::Unit => {}
+            ASTVariantKind::Tuple(fields) => {
+                for field in fields {
+                    branch.walk_type_info(field);
+                }
+            }
+            ASTVariantKind::Struct(fields) => {
+                for field in fields {
+                    let mut branch_inner = ASTDumperVisitor::new(format!("field {}", field.name));
+                    branch_inner.walk_type_info(&field.type_info);
+                    branch.tree.add_tree_branch(branch_inner.tree);
+                }
+            }
+        }
+
+        self.tree.add_tree_branch(branch.tree);
+    }
+
     fn walk_member(&mut self, obj: &ASTMember) {
         let branch = match &obj.kind {
             ASTMemberKind::Field {
@@ -324,6 +564,28 @@ impl ASTVisitor for ASTDumperVisitor {
                     branch.tree.add_tree_branch(branch_inner.tree);
                 }
 
+                branch
+            }
+            ASTMemberKind::Constructor {
+                name,
+                parameters,
+                block,
+            } => {
+                let mut branch = ASTDumperVisitor::new(format!("constructor {}", name));
+
+                let mut branch_inner = ASTDumperVisitor::new(format!("parameters"));
+                for parameter in parameters {
+                    let mut branch_inner_inner =
+                        ASTDumperVisitor::new(format!("parameter {}", parameter.name));
+                    branch_inner_inner.walk_type_info(&parameter.type_info);
+                    branch_inner.tree.add_tree_branch(branch_inner_inner.tree);
+                }
+                branch.tree.add_tree_branch(branch_inner.tree);
+
+                let mut branch_inner = ASTDumperVisitor::new(format!("block"));
+                branch_inner.walk_statement_block(block);
+                branch.tree.add_tree_branch(branch_inner.tree);
+
                 branch
             }
         };
@@ -337,6 +599,8 @@ impl ASTVisitor for ASTDumperVisitor {
             obj.name,
             match obj.kind {
                 ASTTypeKind::Class { .. } => "class",
+                ASTTypeKind::Interface { .. } => "interface",
+                ASTTypeKind::Enum { .. } => "enum",
             }
         ));
 
@@ -380,6 +644,29 @@ impl ASTVisitor for ASTDumperVisitor {
                 }
                 branch.tree.add_tree_branch(branch_inner.tree);
             }
+            ASTTypeKind::Interface {
+                super_interfaces,
+                members,
+            } => {
+                let mut branch_inner = ASTDumperVisitor::new(format!("super interfaces"));
+                for super_interface in super_interfaces {
+                    branch_inner.walk_partial_type_info(super_interface);
+                }
+                branch.tree.add_tree_branch(branch_inner.tree);
+
+                let mut branch_inner = ASTDumperVisitor::new(format!("members"));
+                for member in members {
+                    branch_inner.walk_member(member);
+                }
+                branch.tree.add_tree_branch(branch_inner.tree);
+            }
+            ASTTypeKind::Enum { variants } => {
+                let mut branch_inner = ASTDumperVisitor::new(format!("variants"));
+                for variant in variants {
+                    branch_inner.walk_variant(variant);
+                }
+                branch.tree.add_tree_branch(branch_inner.tree);
+            }
         }
 
         self.tree.add_tree_branch(branch.tree);
@@ -1,4 +1,5 @@
 pub mod ast_dumper;
+pub mod source_file;
 pub mod string_tree;
 
 #[derive(Debug, Copy, Clone)]
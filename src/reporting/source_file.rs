@@ -0,0 +1,133 @@
+use crate::lexer::Token;
+use crate::reporting::{CharSpan, TokenSpan};
+use std::fmt::{Display, Formatter};
+
+// Both line and column are 1-based, columns are counted in characters and not in bytes
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct LineCol {
+    pub line: usize,
+    pub column: usize,
+}
+
+// The end is exclusive
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct LineColRange {
+    pub start: LineCol,
+    pub end: LineCol,
+}
+
+impl Display for LineCol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+impl Display for LineColRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+pub struct SourceFile<'a> {
+    pub name: &'a str,
+    pub source: &'a str,
+    // Byte offset of the first character of every line
+    line_starts: Vec<usize>,
+    // Char spans of the lexed tokens, used to map token spans
+    token_spans: Vec<CharSpan>,
+}
+
+impl<'a> SourceFile<'a> {
+    pub fn new(name: &'a str, source: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        for (i, byte) in source.bytes().enumerate() {
+            if byte == b'\n' {
+                line_starts.push(i + 1);
+            }
+        }
+
+        Self {
+            name,
+            source,
+            line_starts,
+            token_spans: vec![],
+        }
+    }
+
+    // Has to be called with the lexer output before token spans can be mapped
+    pub fn register_tokens(&mut self, tokens: &[Token]) {
+        self.token_spans = tokens.iter().map(|t| t.span).collect();
+    }
+
+    pub fn pos_to_line_col(&self, pos: usize) -> LineCol {
+        let pos = pos.min(self.source.len());
+        let line_index = match self.line_starts.binary_search(&pos) {
+            Ok(index) => index,
+            Err(index) => index - 1,
+        };
+        let line_start = self.line_starts[line_index];
+        // Positions inside a multi-byte character are mapped to that character
+        let column = self.source[line_start..]
+            .char_indices()
+            .take_while(|(i, _)| line_start + i < pos)
+            .count();
+
+        LineCol {
+            line: line_index + 1,
+            column: column + 1,
+        }
+    }
+
+    pub fn char_span_to_range(&self, span: CharSpan) -> LineColRange {
+        LineColRange {
+            start: self.pos_to_line_col(span.base),
+            end: self.pos_to_line_col(span.base + span.len),
+        }
+    }
+
+    pub fn token_span_to_range(&self, span: TokenSpan) -> LineColRange {
+        if self.token_spans.is_empty() {
+            let start = self.pos_to_line_col(0);
+            return LineColRange { start, end: start };
+        }
+
+        let last = self.token_spans.len() - 1;
+        let first_token = self.token_spans[span.base.min(last)];
+        if span.len == 0 {
+            let start = self.pos_to_line_col(first_token.base);
+            return LineColRange { start, end: start };
+        }
+        let last_token = self.token_spans[(span.base + span.len - 1).min(last)];
+
+        LineColRange {
+            start: self.pos_to_line_col(first_token.base),
+            end: self.pos_to_line_col(last_token.base + last_token.len),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::Lexer;
+    use crate::reporting::{CharSpan, TokenSpan};
+    use crate::reporting::source_file::SourceFile;
+
+    #[test]
+    fn columns_are_counted_in_characters() {
+        let source = "héllo /* ü */ \"ß\"\n  wörld ×";
+        let source_file = SourceFile::new("test.oo", source);
+        let error = Lexer::new(source).lex().err().unwrap();
+        assert_eq!(source_file.pos_to_line_col(error.pos).to_string(), "2:9");
+        assert_eq!(source_file.char_span_to_range(CharSpan { base: 16, len: 4 }).to_string(), "1:15-1:18");
+    }
+
+    #[test]
+    fn token_spans_are_mapped_through_the_lexed_tokens() {
+        let source = "héllo\n  wörld 'é'";
+        let mut source_file = SourceFile::new("test.oo", source);
+        source_file.register_tokens(&Lexer::new(source).lex().unwrap());
+        assert_eq!(source_file.token_span_to_range(TokenSpan::new(1, 2)).to_string(), "2:3-2:12");
+        // Empty spans point at the start of their token
+        assert_eq!(source_file.token_span_to_range(TokenSpan::new(2, 0)).to_string(), "2:9-2:9");
+    }
+}
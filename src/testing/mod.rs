@@ -0,0 +1,58 @@
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::reporting::TokenSpan;
+use crate::ast::ASTRoot;
+use crate::tir::TIRRoot;
+use crate::tir::ast_lowerer::{ASTtoTIRLowerer, ASTtoTIRLowererError, ASTtoTIRLowererWarning};
+use crate::tir::type_checker::{TypeChecker, TypeCheckerError};
+use crate::type_ref_pool::TypeRefPool;
+use crate::field_ref_pool::FieldRefPool;
+use crate::method_ref_pool::MethodRefPool;
+use crate::codegen::{Codegen, CodegenError, BytecodeFile};
+use oolang_bytecode::BytecodeFormat;
+use oolang_bytecode::instruction_structure::InstructionFormat;
+
+// Runs the passes before the one a test is about, they are expected to succeed
+
+pub type Lowered<'a> = (TIRRoot<'a>, TypeRefPool<'a>, FieldRefPool<'a>, MethodRefPool<'a>, Vec<ASTtoTIRLowererWarning>);
+
+pub fn parse(source: &str) -> ASTRoot<'_> {
+    Parser::new(Lexer::new(source).lex().unwrap()).parse().unwrap()
+}
+
+pub fn lower(source: &str) -> Result<Lowered<'_>, ASTtoTIRLowererError<'_>> {
+    ASTtoTIRLowerer::new(parse(source)).lower()
+}
+
+pub fn lowering_error(source: &str) -> ASTtoTIRLowererError<'_> {
+    match lower(source) {
+        Ok(_) => panic!("lowering succeeded"),
+        Err(err) => err
+    }
+}
+
+// Tokens an error points at, separated by spaces
+pub fn span_text(source: &str, span: TokenSpan) -> String {
+    let tokens = Lexer::new(source).lex().unwrap();
+    tokens[span.base..span.base + span.len].iter().map(|token| token.string).collect::<Vec<&str>>().join(" ")
+}
+
+pub fn type_errors(source: &str) -> Vec<TypeCheckerError<'_>> {
+    let (mut tir, type_ref_pool, field_ref_pool, method_ref_pool, _) = lower(source).unwrap();
+    TypeChecker::new(&type_ref_pool, &field_ref_pool, &method_ref_pool).check(&mut tir)
+}
+
+pub fn compile(source: &str) -> Result<Vec<BytecodeFile<'_>>, CodegenError> {
+    let (mut tir, type_ref_pool, field_ref_pool, method_ref_pool, _) = lower(source).unwrap();
+    let type_errors = TypeChecker::new(&type_ref_pool, &field_ref_pool, &method_ref_pool).check(&mut tir);
+    assert!(type_errors.is_empty(), "{:?}", type_errors);
+    Codegen::new(tir, type_ref_pool, field_ref_pool, method_ref_pool).get_bytecode()
+}
+
+// Whether the compiled file of the type contains the instructions one after the other
+pub fn generates(bytecode_files: &[BytecodeFile], type_name: &str, instructions: &[InstructionFormat]) -> bool {
+    let mut expected = vec![];
+    instructions.iter().for_each(|instruction| instruction.write(&mut expected));
+    let bytecode_file = bytecode_files.iter().find(|f| f.file_path.last() == Some(&type_name)).unwrap();
+    bytecode_file.bytecode.windows(expected.len()).any(|window| window == expected.as_slice())
+}
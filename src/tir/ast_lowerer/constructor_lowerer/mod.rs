@@ -0,0 +1,186 @@
+use crate::tir::ast_lowerer::{ASTtoTIRLowerer, GenericContext, BodyContext, ASTtoTIRLowererError};
+use crate::ast::{ASTExpr, ASTExprKind, ASTNameAndType, ASTStatementBlock, ASTStatementKind, ASTTypeInfo};
+use crate::reporting::TokenSpan;
+use crate::type_ref_pool::TypeRefKind;
+use crate::tir::{PrimitiveType, TIRExpr, TIRExprKind, TIRMember, TIRMemberKind, TIRNameAndType, TIRStatement, TIRStatementBlock, TIRTypeInfo, TIRTypeInfoKind};
+
+// Constructors run the super class constructor first, then the field initializers, then their own body.
+// They are told apart by their parameters, and the one without parameters can be called implicitly.
+// Constructors with the same number of parameters are picked between by the type checker.
+
+impl<'a> ASTtoTIRLowerer<'a> {
+    // Indexes of the constructors of the class that can be called with that many arguments from code in the other type
+    fn resolve_constructors(&self, type_ref_index: usize, arg_count: usize, span: TokenSpan, from_type_ref_index: usize) -> Result<Vec<usize>, ASTtoTIRLowererError<'a>> {
+        let class_type_ref = match &self.type_ref_pool.type_refs[type_ref_index].kind {
+            TypeRefKind::Class(class_type_ref) => class_type_ref,
+            _ => return Err(ASTtoTIRLowererError::InvalidNew(span))
+        };
+        let candidates: Vec<usize> = class_type_ref.constructors.iter().enumerate()
+            .filter(|(_, parameters)| parameters.len() == arg_count)
+            .map(|(constructor_index, _)| constructor_index)
+            .collect();
+        if candidates.is_empty() {
+            return Err(ASTtoTIRLowererError::NoSuchConstructor(span));
+        }
+        let candidates: Vec<usize> = candidates.into_iter()
+            .filter(|constructor_index| self.type_ref_pool.is_accessible(class_type_ref.constructor_visibilities[*constructor_index], type_ref_index, from_type_ref_index))
+            .collect();
+        if candidates.is_empty() {
+            return Err(ASTtoTIRLowererError::InaccessibleConstructor(span));
+        }
+        Ok(candidates)
+    }
+
+    pub fn max_constructor_parameter_count(&self, type_ref_index: usize) -> usize {
+        match &self.type_ref_pool.type_refs[type_ref_index].kind {
+            TypeRefKind::Class(class_type_ref) => class_type_ref.constructors.iter().map(|parameters| parameters.len()).max().unwrap_or(0),
+            _ => 0
+        }
+    }
+
+    pub fn super_class_type_ref_index(&self, type_ref_index: usize) -> Option<usize> {
+        match &self.type_ref_pool.type_refs[type_ref_index].kind {
+            TypeRefKind::Class(class_type_ref) => match &class_type_ref.super_class {
+                Some(TIRTypeInfo { kind: TIRTypeInfoKind::TypeRef { type_ref_index, .. }, .. }) => Some(*type_ref_index),
+                _ => None
+            },
+            _ => unreachable!()
+        }
+    }
+
+    // "super()", only possible if the super class has a constructor without parameters
+    fn implicit_super_call(&self, type_ref_index: usize, span: TokenSpan) -> Result<Option<(Vec<usize>, Vec<TIRExpr<'a>>)>, ASTtoTIRLowererError<'a>> {
+        match self.super_class_type_ref_index(type_ref_index) {
+            Some(super_type_ref_index) => match self.resolve_constructors(super_type_ref_index, 0, span, type_ref_index) {
+                Ok(constructor_indexes) => Ok(Some((constructor_indexes, vec![]))),
+                Err(_) => Err(ASTtoTIRLowererError::MissingSuperCall(span))
+            },
+            None => Ok(None)
+        }
+    }
+
+    pub fn implicit_constructor(&self, type_ref_index: usize, span: TokenSpan) -> Result<TIRMember<'a>, ASTtoTIRLowererError<'a>> {
+        Ok(TIRMember {
+            kind: TIRMemberKind::Constructor {
+                parameters: vec![],
+                super_call: self.implicit_super_call(type_ref_index, span)?,
+                block: TIRStatementBlock {
+                    span,
+                    statements: vec![]
+                },
+                // Only "this"
+                local_count: 1
+            },
+            span,
+            modifiers: vec![]
+        })
+    }
+
+    pub fn lower_ast_constructor(&self, parameters: &[ASTNameAndType<'a>], block: &ASTStatementBlock<'a>, generic_context: &GenericContext<'a, '_>) -> Result<TIRMemberKind<'a>, ASTtoTIRLowererError<'a>> {
+        let parameters = parameters.iter().map(|name_and_type| self.lower_ast_name_and_type(name_and_type, generic_context))
+            .collect::<Result<Vec<TIRNameAndType<'a>>, ASTtoTIRLowererError<'a>>>()?;
+        let mut body_context = BodyContext::new(Some(TIRTypeInfo {
+            kind: TIRTypeInfoKind::Primitive {
+                primitive: PrimitiveType::Void,
+                array_dim: 0
+            },
+            span: block.span
+        }), false);
+        for parameter in &parameters {
+            body_context.declare_local(parameter.name, Some(parameter.type_info.clone()));
+        }
+
+        // An explicit "super(...)" has to be the first statement, it is taken out of the block
+        let explicit_super_call = match block.statements.first().map(|s| &s.kind) {
+            Some(ASTStatementKind::Expression(expr)) => match &expr.kind {
+                ASTExprKind::SuperCall(args) => Some((args, expr.span)),
+                _ => None
+            },
+            _ => None
+        };
+        let statements = match explicit_super_call {
+            Some(_) => &block.statements[1..],
+            None => &block.statements[..]
+        };
+
+        let super_call = match explicit_super_call {
+            Some((args, span)) => {
+                let super_type_ref_index = self.super_class_type_ref_index(generic_context.type_ref_index)
+                    .ok_or(ASTtoTIRLowererError::NoSuperClass(span))?;
+                let constructor_indexes = self.resolve_constructors(super_type_ref_index, args.len(), span, generic_context.type_ref_index)?;
+                // The arguments can use the parameters
+                let args = self.lower_ast_args(args, generic_context, &mut body_context)?;
+                Some((constructor_indexes, args))
+            }
+            None => self.implicit_super_call(generic_context.type_ref_index, block.span)?
+        };
+
+        body_context.push_scope();
+        let statements = statements.iter().map(|s| self.lower_ast_statement(s, generic_context, &mut body_context))
+            .collect::<Result<Vec<TIRStatement<'a>>, ASTtoTIRLowererError<'a>>>()?;
+        body_context.pop_scope();
+
+        Ok(TIRMemberKind::Constructor {
+            parameters,
+            super_call,
+            block: TIRStatementBlock {
+                span: block.span,
+                statements
+            },
+            local_count: body_context.local_count()
+        })
+    }
+
+    pub fn lower_ast_new(&self, type_info: &ASTTypeInfo<'a>, args: &[ASTExpr<'a>], span: TokenSpan, generic_context: &GenericContext<'a, '_>, body_context: &mut BodyContext<'a>) -> Result<TIRExprKind<'a>, ASTtoTIRLowererError<'a>> {
+        let type_info = self.resolve_type_info(type_info, Some(generic_context))?;
+        let (type_ref_index, generics) = match &type_info.kind {
+            TIRTypeInfoKind::TypeRef { type_ref_index, generics, array_dim: 0 } => (*type_ref_index, generics),
+            _ => return Err(ASTtoTIRLowererError::InvalidNew(span))
+        };
+        match &self.type_ref_pool.type_refs[type_ref_index].kind {
+            TypeRefKind::Class(class_type_ref) if class_type_ref.is_abstract => return Err(ASTtoTIRLowererError::AbstractInstantiation(span)),
+            TypeRefKind::Class(_) => {}
+            _ => return Err(ASTtoTIRLowererError::InvalidNew(span))
+        }
+        // Generics that are left out ("new Wrapper(10)") are inferred by the type checker
+        if !generics.is_empty() {
+            self.check_generics(&type_info)?;
+        }
+
+        let mut constructor_indexes = self.resolve_constructors(type_ref_index, args.len(), span, generic_context.type_ref_index)?;
+        let args = self.lower_ast_args(args, generic_context, body_context)?;
+
+        Ok(if constructor_indexes.len() == 1 {
+            TIRExprKind::New(type_info, constructor_indexes.pop().unwrap(), args)
+        } else {
+            TIRExprKind::OverloadedNew(type_info, constructor_indexes, args)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tir::ast_lowerer::ASTtoTIRLowererError;
+    use crate::testing::{lower, lowering_error, compile};
+
+    #[test]
+    fn constructors_chain_to_the_super_class() {
+        let source = "mod a; class Base { value: u64; pub Base(value: u64) { this.value = value; } } class Wrapper: Base { pub Wrapper(value: u64) { super(value); } }";
+        compile(source).unwrap();
+        let err = lowering_error("mod a; class Base { pub Base(value: u64) { } } class Wrapper: Base { pub Wrapper() { } }");
+        assert!(matches!(err, ASTtoTIRLowererError::MissingSuperCall(_)), "{:?}", err);
+        let err = lowering_error("mod a; class Base { } class Wrapper: Base { pub Wrapper() { let a = 1; super(); } }");
+        assert!(matches!(err, ASTtoTIRLowererError::MisplacedSuperCall(_)), "{:?}", err);
+    }
+
+    #[test]
+    fn new_picks_constructors_by_argument_count() {
+        let source = "mod a; class Point { pub Point() { } pub Point(x: u64, y: u64) { } } class A { fn f() -> Point { BODY } }";
+        lower(&source.replace("BODY", "new Point()")).unwrap();
+        lower(&source.replace("BODY", "new Point(1, 2)")).unwrap();
+        let source = source.replace("BODY", "new Point(1)");
+        assert!(matches!(lowering_error(&source), ASTtoTIRLowererError::NoSuchConstructor(_)));
+        let err = lowering_error("mod a; abstract class Shape { } class A { fn f() -> Shape { new Shape() } }");
+        assert!(matches!(err, ASTtoTIRLowererError::AbstractInstantiation(_)), "{:?}", err);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::tir::ast_lowerer::{ASTtoTIRLowerer, GenericContext, BodyContext, ASTtoTIRLowererError};
+use crate::ast::{ASTExpr, ASTExprKind, ASTStatementBlock};
+use crate::reporting::TokenSpan;
+use crate::tir::{TIRExpr, TIRExprKind, TIROperator, TIRStatement, TIRStatementKind, TIRStatementBlock};
+
+// "for" loops are desugared into "loop" and "break" so that codegen never sees them. Loops over ranges are desugared here,
+// the others are desugared by the type checker (see type_checker/for_checker), which knows whether the iterable is an array.
+// The locals introduced for them only get a slot, so they can't clash with user code.
+
+pub fn expr<'a>(kind: TIRExprKind<'a>, span: TokenSpan) -> TIRExpr<'a> {
+    TIRExpr {
+        kind,
+        span,
+        type_info: None
+    }
+}
+
+pub fn variable<'a>(slot: usize, span: TokenSpan) -> TIRExpr<'a> {
+    expr(TIRExprKind::LocalAccess(slot), span)
+}
+
+pub fn binary<'a>(left: TIRExpr<'a>, operator: TIROperator, right: TIRExpr<'a>, span: TokenSpan) -> TIRExpr<'a> {
+    expr(TIRExprKind::BinOp(Box::new(left), operator, Box::new(right)), span)
+}
+
+pub fn method_call<'a>(on: TIRExpr<'a>, method: &'a str, span: TokenSpan) -> TIRExpr<'a> {
+    expr(TIRExprKind::Call(Box::new(expr(TIRExprKind::MemberAccess(Box::new(on), method), span)), vec![]), span)
+}
+
+pub fn local<'a>(slot: usize, value: TIRExpr<'a>, span: TokenSpan) -> TIRStatement<'a> {
+    TIRStatement {
+        kind: TIRStatementKind::Local(slot, None, Some(Box::new(value))),
+        span,
+        ending: false
+    }
+}
+
+pub fn statement<'a>(value: TIRExpr<'a>, span: TokenSpan) -> TIRStatement<'a> {
+    TIRStatement {
+        kind: TIRStatementKind::Expression(Box::new(value)),
+        span,
+        ending: false
+    }
+}
+
+pub fn block<'a>(statements: Vec<TIRStatement<'a>>, span: TokenSpan) -> TIRStatementBlock<'a> {
+    TIRStatementBlock {
+        span,
+        statements
+    }
+}
+
+// "if condition { break; }"
+pub fn break_if<'a>(condition: TIRExpr<'a>, span: TokenSpan) -> TIRStatement<'a> {
+    statement(expr(TIRExprKind::If(Box::new(condition), block(vec![statement(expr(TIRExprKind::Break(None, None), span), span)], span)), span), span)
+}
+
+impl<'a> ASTtoTIRLowerer<'a> {
+    pub fn lower_ast_for(&self, label: Option<&'a str>, name: &'a str, iterable: &ASTExpr<'a>, body: &ASTStatementBlock<'a>, span: TokenSpan, generic_context: &GenericContext<'a, '_>, body_context: &mut BodyContext<'a>) -> Result<TIRExprKind<'a>, ASTtoTIRLowererError<'a>> {
+        // The loop variable is only visible in the body, but it is assigned before it
+        let element = body_context.allocate_local(None);
+
+        let mut statements = vec![];
+        let mut loop_statements = vec![];
+        match &iterable.kind {
+            // let $counter = start; let $end = end;
+            // loop { if $counter >= $end { break; } let name = $counter; $counter += 1; body }
+            ASTExprKind::Range(start, end, false) => {
+                let (counter, limit) = (body_context.allocate_local(None), body_context.allocate_local(None));
+                statements.push(local(counter, self.lower_ast_expr(start, generic_context, body_context)?, span));
+                statements.push(local(limit, self.lower_ast_expr(end, generic_context, body_context)?, span));
+                loop_statements.push(break_if(binary(variable(counter, span), TIROperator::GtEq, variable(limit, span), span), span));
+                loop_statements.push(local(element, variable(counter, span), span));
+                loop_statements.push(statement(binary(variable(counter, span), TIROperator::PlusAssign, expr(TIRExprKind::Num(1, None), span), span), span));
+            }
+            // The counter can't go past the end, which could be the largest value of the type
+            // let $counter = start; let $end = end; let $exhausted = $counter > $end;
+            // loop { if $exhausted { break; } let name = $counter; if $counter == $end { $exhausted = true; } else { $counter += 1; } body }
+            ASTExprKind::Range(start, end, true) => {
+                let (counter, limit, exhausted) = (body_context.allocate_local(None), body_context.allocate_local(None), body_context.allocate_local(None));
+                statements.push(local(counter, self.lower_ast_expr(start, generic_context, body_context)?, span));
+                statements.push(local(limit, self.lower_ast_expr(end, generic_context, body_context)?, span));
+                statements.push(local(exhausted, binary(variable(counter, span), TIROperator::Gt, variable(limit, span), span), span));
+                loop_statements.push(break_if(variable(exhausted, span), span));
+                loop_statements.push(local(element, variable(counter, span), span));
+                loop_statements.push(statement(expr(TIRExprKind::IfElse(
+                    Box::new(binary(variable(counter, span), TIROperator::Eq, variable(limit, span), span)),
+                    block(vec![statement(binary(variable(exhausted, span), TIROperator::Assign, expr(TIRExprKind::Boolean(true), span), span), span)], span),
+                    block(vec![statement(binary(variable(counter, span), TIROperator::PlusAssign, expr(TIRExprKind::Num(1, None), span), span), span)], span)
+                ), span), span));
+            }
+            _ => {
+                let iterable = self.lower_ast_expr(iterable, generic_context, body_context)?;
+                let temporaries = [body_context.allocate_local(None), body_context.allocate_local(None)];
+                let body = self.lower_for_body(label, name, element, body, generic_context, body_context)?;
+                return Ok(TIRExprKind::For(label, Box::new(iterable), element, temporaries, body));
+            }
+        }
+
+        let body = self.lower_for_body(label, name, element, body, generic_context, body_context)?;
+        loop_statements.push(statement(expr(TIRExprKind::Block(body), span), span));
+
+        statements.push(statement(expr(TIRExprKind::Loop(label, block(loop_statements, span)), span), span));
+        Ok(TIRExprKind::Block(block(statements, span)))
+    }
+
+    fn lower_for_body(&self, label: Option<&'a str>, name: &'a str, element: usize, body: &ASTStatementBlock<'a>, generic_context: &GenericContext<'a, '_>, body_context: &mut BodyContext<'a>) -> Result<TIRStatementBlock<'a>, ASTtoTIRLowererError<'a>> {
+        body_context.push_loop(label, false);
+        body_context.push_scope();
+        body_context.bind_local(name, element);
+        let body = self.lower_ast_statement_block(body, generic_context, body_context);
+        body_context.pop_scope();
+        body_context.pop_loop();
+        body
+    }
+}
@@ -0,0 +1,135 @@
+use crate::tir::ast_lowerer::{ASTtoTIRLowerer, ASTtoTIRLowererError, ASTtoTIRLowererWarning};
+use crate::ast::ASTUseKind;
+use crate::reporting::TokenSpan;
+
+// "use" declarations are resolved once every type is registered, before any type info is resolved.
+// Paths are tried as they are, then relative to the current module.
+// Local types can't be shadowed by single imports, but they take precedence over glob imports.
+// Only the types of the file being compiled can be imported for now, there is no way to register the types of other
+// compilation units (or of the standard library) yet, so "use other::pkg::Thing;" fails with NoSuchType.
+
+impl<'a> ASTtoTIRLowerer<'a> {
+    fn import_paths(&self, path: &[&'a str]) -> [Vec<&'a str>; 2] {
+        let mut combined = self.mod_context.clone();
+        combined.extend_from_slice(path);
+        [path.to_vec(), combined]
+    }
+
+    pub fn register_imports(&mut self) -> Result<(), ASTtoTIRLowererError<'a>> {
+        for use_index in 0..self.ast_root.use_decls.len() {
+            let use_decl = &self.ast_root.use_decls[use_index];
+            let path = &use_decl.path.elements;
+            match &use_decl.kind {
+                ASTUseKind::Single(alias) => {
+                    let type_ref_index = match self.import_paths(path).iter().find_map(|full_path| self.type_ref_pool.full_path_to_type_ref_index.get(full_path)) {
+                        Some(type_ref_index) => *type_ref_index,
+                        None => return Err(ASTtoTIRLowererError::NoSuchType(use_decl.span, path.clone()))
+                    };
+                    if !self.type_ref_pool.is_type_accessible(type_ref_index, &self.mod_context) {
+                        return Err(ASTtoTIRLowererError::InaccessibleType(use_decl.span, path.clone()));
+                    }
+                    let name = alias.unwrap_or(path[path.len() - 1]);
+                    // Importing a local type under its own name is allowed, it is only unused
+                    match self.type_ref_pool.full_path_to_type_ref_index.get(&self.name_to_full_path(name)) {
+                        Some(local_type_ref_index) if *local_type_ref_index != type_ref_index =>
+                            return Err(ASTtoTIRLowererError::ImportConflict(use_decl.span, name)),
+                        _ => {}
+                    }
+                    if self.imports.insert(name, (type_ref_index, use_index)).is_some() {
+                        return Err(ASTtoTIRLowererError::ImportConflict(use_decl.span, name));
+                    }
+                }
+                ASTUseKind::Glob => {
+                    let module = self.import_paths(path).iter().find(|module| self.type_ref_pool.type_refs.iter().any(|type_ref| {
+                        type_ref.full_path.len() == module.len() + 1 && type_ref.full_path.starts_with(module)
+                    })).cloned();
+                    match module {
+                        Some(module) => self.glob_imports.push((module, use_index)),
+                        None => return Err(ASTtoTIRLowererError::NoSuchModule(use_decl.span, path.clone()))
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Type a name that isn't a local type refers to through the imports, the span is the one of the name
+    pub fn resolve_import(&self, name: &'a str, span: TokenSpan) -> Result<Option<usize>, ASTtoTIRLowererError<'a>> {
+        if let Some((type_ref_index, use_index)) = self.imports.get(name) {
+            self.used_imports[*use_index].set(true);
+            return Ok(Some(*type_ref_index));
+        }
+
+        let mut found: Option<(usize, usize)> = None;
+        for (module, use_index) in &self.glob_imports {
+            let mut full_path = module.clone();
+            full_path.push(name);
+            // Glob imports only bring in the types that can be used here
+            let type_ref_index = self.type_ref_pool.full_path_to_type_ref_index.get(&full_path)
+                .filter(|type_ref_index| self.type_ref_pool.is_type_accessible(**type_ref_index, &self.mod_context));
+            match (type_ref_index, found) {
+                (Some(type_ref_index), Some((found_type_ref_index, _))) if *type_ref_index != found_type_ref_index =>
+                    return Err(ASTtoTIRLowererError::AmbiguousImport(span, name)),
+                (Some(type_ref_index), None) => found = Some((*type_ref_index, *use_index)),
+                _ => {}
+            }
+        }
+        Ok(found.map(|(type_ref_index, use_index)| {
+            self.used_imports[use_index].set(true);
+            type_ref_index
+        }))
+    }
+
+    pub fn unused_imports(&self) -> Vec<ASTtoTIRLowererWarning> {
+        self.ast_root.use_decls.iter().zip(&self.used_imports)
+            .filter(|(_, used)| !used.get())
+            .map(|(use_decl, _)| ASTtoTIRLowererWarning::UnusedImport(use_decl.span))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tir::ast_lowerer::{ASTtoTIRLowererError, ASTtoTIRLowererWarning};
+    use crate::testing::{lower, lowering_error};
+
+    fn warnings(source: &str) -> Vec<ASTtoTIRLowererWarning> {
+        lower(source).unwrap().4
+    }
+
+    #[test]
+    fn imports_can_rename_types() {
+        assert!(warnings("mod a; use a::Point as Position; class Point { } class A { at: Position; }").is_empty());
+        assert!(warnings("mod a; use Point as Position; class Point { } class A { at: Position; }").is_empty());
+        let err = lowering_error("mod a; use a::Missing; class A { }");
+        assert!(matches!(err, ASTtoTIRLowererError::NoSuchType(_, ref path) if path == &["a", "Missing"]), "{:?}", err);
+        let err = lowering_error("mod a; use b::*; class A { }");
+        assert!(matches!(err, ASTtoTIRLowererError::NoSuchModule(_, ref path) if path == &["b"]), "{:?}", err);
+    }
+
+    #[test]
+    fn imports_cannot_take_a_name_twice() {
+        let err = lowering_error("mod a; use a::Point as A; class Point { } class A { }");
+        assert!(matches!(err, ASTtoTIRLowererError::ImportConflict(_, "A")), "{:?}", err);
+        let err = lowering_error("mod a; use a::Point as P; use a::Line as P; class Point { } class Line { }");
+        assert!(matches!(err, ASTtoTIRLowererError::ImportConflict(_, "P")), "{:?}", err);
+        // A local type imported under its own name is only unused
+        assert!(matches!(warnings("mod a; use a::Point; class Point { }")[..], [ASTtoTIRLowererWarning::UnusedImport(_)]));
+    }
+
+    #[test]
+    fn unused_imports_are_warned_about() {
+        let warnings = warnings("mod a; use a::Point as P; use a::*; class Point { } class A { at: P; }");
+        // Local types take precedence over glob imports, so the glob import is never used
+        assert!(matches!(warnings[..], [ASTtoTIRLowererWarning::UnusedImport(span)] if span.base == 10), "{:?}", warnings);
+    }
+
+    #[test]
+    fn only_types_of_the_compiled_file_can_be_imported() {
+        let err = lowering_error("mod a; use other::pkg::Thing; class A { }");
+        assert!(matches!(err, ASTtoTIRLowererError::NoSuchType(_, ref path) if path == &["other", "pkg", "Thing"]), "{:?}", err);
+        let err = lowering_error("mod a; use oolang::*; class A { }");
+        assert!(matches!(err, ASTtoTIRLowererError::NoSuchModule(_, ref path) if path == &["oolang"]), "{:?}", err);
+    }
+}
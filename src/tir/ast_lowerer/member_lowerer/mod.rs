@@ -1,37 +1,44 @@
-use crate::tir::ast_lowerer::{ASTtoTIRLowerer, GenericContext, ASTtoTIRLowererError};
+use crate::tir::ast_lowerer::{ASTtoTIRLowerer, GenericContext, BodyContext, ASTtoTIRLowererError};
 use crate::ast::{ASTMember, ASTModifier, ASTMemberKind, ASTExpr, ASTNameAndType, ASTExprKind, ASTOperator, ASTStatementBlock, ASTStatement, ASTStatementKind};
-use crate::tir::{TIRMember, TIRExpr, TIRNameAndType, TIRMemberKind, TIRModifier, TIRExprKind, TIROperator, TIRStatementBlock, TIRStatement, TIRStatementKind};
+use crate::reporting::TokenSpan;
+use crate::type_ref_pool::{TypeRefKind, EnumVariantRef, EnumVariantRefKind};
+use crate::tir::{PrimitiveType, TIRTypeInfo, TIRTypeInfoKind, TIRMember, TIRExpr, TIRNameAndType, TIRMemberKind, TIRModifier, TIRExprKind, TIROperator, TIRStatementBlock, TIRStatement, TIRStatementKind};
 
 impl<'a> ASTtoTIRLowerer<'a> {
-    fn lower_ast_statement(&self, statement: &ASTStatement<'a>, generic_context: &GenericContext<'a, '_>) -> Result<TIRStatement<'a>, ASTtoTIRLowererError<'a>> {
+    pub fn lower_ast_statement(&self, statement: &ASTStatement<'a>, generic_context: &GenericContext<'a, '_>, body_context: &mut BodyContext<'a>) -> Result<TIRStatement<'a>, ASTtoTIRLowererError<'a>> {
         Ok(TIRStatement {
             kind: match &statement.kind {
-                ASTStatementKind::Local(name, type_info, expr) =>
-                    TIRStatementKind::Local(
-                        name,
-                        if let Some(type_info) = type_info {
-                            Some(self.resolve_type_info(type_info, Some(generic_context))?)
-                        } else {
-                            None
-                        },
-                        if let Some(expr) = expr {
-                            Some(Box::new(self.lower_ast_expr(expr, generic_context)?))
-                        } else {
-                            None
-                        }
-                    ),
+                ASTStatementKind::Local(name, type_info, expr) => {
+                    let type_info = if let Some(type_info) = type_info {
+                        let type_info = self.resolve_type_info(type_info, Some(generic_context))?;
+                        self.check_generics(&type_info)?;
+                        Some(type_info)
+                    } else {
+                        None
+                    };
+                    // The initializer doesn't see the local it initializes
+                    let expr = if let Some(expr) = expr {
+                        Some(Box::new(self.lower_ast_expr(expr, generic_context, body_context)?))
+                    } else {
+                        None
+                    };
+                    TIRStatementKind::Local(body_context.declare_local(name, type_info.clone()), type_info, expr)
+                }
                 ASTStatementKind::Expression(expr) =>
-                    TIRStatementKind::Expression(Box::new(self.lower_ast_expr(expr, generic_context)?))
+                    TIRStatementKind::Expression(Box::new(self.lower_ast_expr(expr, generic_context, body_context)?))
             },
             span: statement.span.clone(),
             ending: statement.ending
         })
     }
 
-    fn lower_ast_statement_block(&self, block: &ASTStatementBlock<'a>, generic_context: &GenericContext<'a, '_>) -> Result<TIRStatementBlock<'a>, ASTtoTIRLowererError<'a>> {
+    pub fn lower_ast_statement_block(&self, block: &ASTStatementBlock<'a>, generic_context: &GenericContext<'a, '_>, body_context: &mut BodyContext<'a>) -> Result<TIRStatementBlock<'a>, ASTtoTIRLowererError<'a>> {
+        body_context.push_scope();
+        let statements = block.statements.iter().map(|s| self.lower_ast_statement(s, generic_context, body_context))
+            .collect::<Result<Vec<TIRStatement<'a>>, ASTtoTIRLowererError<'a>>>();
+        body_context.pop_scope();
         Ok(TIRStatementBlock {
-            statements: block.statements.iter().map(|s| self.lower_ast_statement(s, generic_context))
-                .collect::<Result<Vec<TIRStatement<'a>>, ASTtoTIRLowererError<'a>>>()?,
+            statements: statements?,
             span: block.span.clone()
         })
     }
@@ -42,10 +49,22 @@ impl<'a> ASTtoTIRLowerer<'a> {
             ASTOperator::Minus => TIROperator::Minus,
             ASTOperator::Mul => TIROperator::Mul,
             ASTOperator::Div => TIROperator::Div,
+            ASTOperator::Mod => TIROperator::Mod,
+            ASTOperator::BitAnd => TIROperator::BitAnd,
+            ASTOperator::BitOr => TIROperator::BitOr,
+            ASTOperator::BitXor => TIROperator::BitXor,
+            ASTOperator::Shl => TIROperator::Shl,
+            ASTOperator::Shr => TIROperator::Shr,
             ASTOperator::PlusAssign => TIROperator::PlusAssign,
             ASTOperator::MinusAssign => TIROperator::MinusAssign,
             ASTOperator::MulAssign => TIROperator::MulAssign,
             ASTOperator::DivAssign => TIROperator::DivAssign,
+            ASTOperator::ModAssign => TIROperator::ModAssign,
+            ASTOperator::BitAndAssign => TIROperator::BitAndAssign,
+            ASTOperator::BitOrAssign => TIROperator::BitOrAssign,
+            ASTOperator::BitXorAssign => TIROperator::BitXorAssign,
+            ASTOperator::ShlAssign => TIROperator::ShlAssign,
+            ASTOperator::ShrAssign => TIROperator::ShrAssign,
             ASTOperator::Assign => TIROperator::Assign,
             ASTOperator::And => TIROperator::And,
             ASTOperator::Or => TIROperator::Or,
@@ -57,89 +76,269 @@ impl<'a> ASTtoTIRLowerer<'a> {
             ASTOperator::LsEq => TIROperator::LsEq,
             ASTOperator::Inc => TIROperator::Inc,
             ASTOperator::Dec => TIROperator::Dec,
-            ASTOperator::Not => TIROperator::Not
+            ASTOperator::Not => TIROperator::Not,
+            ASTOperator::BitNot => TIROperator::BitNot
         }
     }
 
-    fn lower_ast_expr(&self, expression: &ASTExpr<'a>, generic_context: &GenericContext<'a, '_>) -> Result<TIRExpr<'a>, ASTtoTIRLowererError<'a>> {
+    pub fn check_integer_literal(&self, value: u64, suffix: Option<PrimitiveType>, negated: bool, span: TokenSpan) -> Result<(), ASTtoTIRLowererError<'a>> {
+        let primitive = match suffix {
+            Some(primitive) => primitive,
+            // Unsuffixed literals get their type from inference
+            None => return Ok(())
+        };
+        let in_range = primitive.integer_literal_in_range(value, negated);
+        if in_range {
+            Ok(())
+        } else {
+            Err(ASTtoTIRLowererError::LiteralOutOfRange(span, primitive))
+        }
+    }
+
+    pub fn check_float_literal(&self, value: f64, suffix: Option<PrimitiveType>, span: TokenSpan) -> Result<(), ASTtoTIRLowererError<'a>> {
+        let in_range = match suffix {
+            Some(PrimitiveType::F32) => (value as f32).is_finite(),
+            _ => value.is_finite()
+        };
+        if in_range {
+            Ok(())
+        } else {
+            Err(ASTtoTIRLowererError::LiteralOutOfRange(span, suffix.unwrap_or(PrimitiveType::F64)))
+        }
+    }
+
+    // Type ref index and variant index if the expression is "E::A" with E an enum
+    fn resolve_enum_variant(&self, expression: &ASTExpr<'a>) -> Result<Option<(usize, usize)>, ASTtoTIRLowererError<'a>> {
+        let (path, path_span, variant_name) = match &expression.kind {
+            ASTExprKind::StaticAccess(expr, variant_name) => match &expr.kind {
+                ASTExprKind::Ident(ident) => (vec![*ident], expr.span, *variant_name),
+                ASTExprKind::Path(path) => (path.elements.clone(), path.span, *variant_name),
+                _ => return Ok(None)
+            },
+            _ => return Ok(None)
+        };
+        // Not being a type is fine, it can still be a variable
+        let type_ref_index = match self.resolve_type_ref_index(&path, path_span) {
+            Ok(type_ref_index) => type_ref_index,
+            Err(_) => return Ok(None)
+        };
+
+        match &self.type_ref_pool.type_refs[type_ref_index].kind {
+            TypeRefKind::Enum(enum_type_ref) => match enum_type_ref.name_to_variant_index.get(variant_name) {
+                Some(variant_index) => Ok(Some((type_ref_index, *variant_index))),
+                None => Err(ASTtoTIRLowererError::NoSuchVariant(expression.span, variant_name))
+            },
+            _ => Ok(None)
+        }
+    }
+
+    pub fn enum_variant(&self, type_ref_index: usize, variant_index: usize) -> &EnumVariantRef<'a> {
+        match &self.type_ref_pool.type_refs[type_ref_index].kind {
+            TypeRefKind::Enum(enum_type_ref) => &enum_type_ref.variants[variant_index],
+            _ => unreachable!()
+        }
+    }
+
+    fn lower_ast_struct_literal(&self, expr: &ASTExpr<'a>, fields: &[(&'a str, ASTExpr<'a>)], span: TokenSpan, generic_context: &GenericContext<'a, '_>, body_context: &mut BodyContext<'a>) -> Result<TIRExprKind<'a>, ASTtoTIRLowererError<'a>> {
+        let (type_ref_index, variant_index) = self.resolve_enum_variant(expr)?.ok_or(ASTtoTIRLowererError::InvalidStructLiteral(expr.span))?;
+        let variant = self.enum_variant(type_ref_index, variant_index);
+        let names = match &variant.kind {
+            EnumVariantRefKind::Struct(names) => names,
+            _ => return Err(ASTtoTIRLowererError::MismatchedVariantFields(span, variant.name))
+        };
+
+        // Fields are put back in declaration order
+        let mut args: Vec<Option<TIRExpr<'a>>> = names.iter().map(|_| None).collect();
+        for (name, field) in fields {
+            let field_index = names.iter().position(|n| n == name).ok_or(ASTtoTIRLowererError::NoSuchVariantField(field.span, name))?;
+            if args[field_index].is_some() {
+                return Err(ASTtoTIRLowererError::DuplicateVariantField(field.span, name));
+            }
+            args[field_index] = Some(self.lower_ast_expr(field, generic_context, body_context)?);
+        }
+        let args = args.into_iter().zip(names.iter())
+            .map(|(arg, name)| arg.ok_or(ASTtoTIRLowererError::MissingVariantField(span, name)))
+            .collect::<Result<Vec<TIRExpr<'a>>, ASTtoTIRLowererError<'a>>>()?;
+
+        Ok(TIRExprKind::EnumConstruct(type_ref_index, variant_index, args))
+    }
+
+    pub fn lower_ast_expr(&self, expression: &ASTExpr<'a>, generic_context: &GenericContext<'a, '_>, body_context: &mut BodyContext<'a>) -> Result<TIRExpr<'a>, ASTtoTIRLowererError<'a>> {
         Ok(TIRExpr {
             kind: match &expression.kind {
-                ASTExprKind::Path(path) => TIRExprKind::TypeAccess(self.resolve_type_ref_index(&path.elements)?),
-                ASTExprKind::Ident(ident) => TIRExprKind::VariableAccess(ident),
-                ASTExprKind::StringLiteral(string) => TIRExprKind::StringLiteral(string),
-                ASTExprKind::Num(number_literal) => TIRExprKind::Num(number_literal),
-                ASTExprKind::Float(float_literal) => TIRExprKind::Float(float_literal),
+                ASTExprKind::Path(path) => TIRExprKind::TypeAccess(self.resolve_type_ref_index(&path.elements, path.span)?),
+                ASTExprKind::Ident(ident) => self.lower_ast_ident(ident, expression.span, generic_context, body_context)?,
+                ASTExprKind::StringLiteral(string) => TIRExprKind::StringLiteral(string.clone()),
+                ASTExprKind::CharLiteral(character) => TIRExprKind::CharLiteral(*character),
+                ASTExprKind::Num(value, suffix) => {
+                    self.check_integer_literal(*value, *suffix, false, expression.span)?;
+                    TIRExprKind::Num(*value, *suffix)
+                }
+                ASTExprKind::Float(value, suffix) => {
+                    self.check_float_literal(*value, *suffix, expression.span)?;
+                    TIRExprKind::Float(*value, *suffix)
+                }
                 ASTExprKind::Boolean(boolean) => TIRExprKind::Boolean(*boolean),
                 ASTExprKind::Null => TIRExprKind::Null,
+                ASTExprKind::This => if body_context.is_static {
+                    return Err(ASTtoTIRLowererError::ThisInStaticContext(expression.span));
+                } else {
+                    TIRExprKind::This
+                },
+                ASTExprKind::Super => if body_context.is_static {
+                    return Err(ASTtoTIRLowererError::ThisInStaticContext(expression.span));
+                } else if self.super_class_type_ref_index(generic_context.type_ref_index).is_none() {
+                    return Err(ASTtoTIRLowererError::NoSuperClass(expression.span));
+                } else {
+                    TIRExprKind::Super
+                },
                 ASTExprKind::BinOp(left, op, right) =>
                     TIRExprKind::BinOp(
-                        Box::new(self.lower_ast_expr(left, generic_context)?),
+                        Box::new(self.lower_ast_expr(left, generic_context, body_context)?),
                         self.lower_ast_operator(op),
-                        Box::new(self.lower_ast_expr(right, generic_context)?)
+                        Box::new(self.lower_ast_expr(right, generic_context, body_context)?)
                     ),
+                ASTExprKind::PreOp(ASTOperator::Minus, expr) if matches!(expr.kind, ASTExprKind::Num(_, _)) => {
+                    // "-128i8" is in range even though "128i8" is not
+                    let (value, suffix) = match expr.kind {
+                        ASTExprKind::Num(value, suffix) => (value, suffix),
+                        _ => unreachable!()
+                    };
+                    self.check_integer_literal(value, suffix, true, expression.span)?;
+                    TIRExprKind::PreOp(
+                        TIROperator::Minus,
+                        Box::new(TIRExpr {
+                            kind: TIRExprKind::Num(value, suffix),
+                            span: expr.span.clone(),
+                            type_info: None
+                        })
+                    )
+                }
                 ASTExprKind::PreOp(op, expr) =>
                     TIRExprKind::PreOp(
                         self.lower_ast_operator(op),
-                        Box::new(self.lower_ast_expr(expr, generic_context)?)
+                        Box::new(self.lower_ast_expr(expr, generic_context, body_context)?)
                     ),
                 ASTExprKind::PostOp(expr, op) =>
                     TIRExprKind::PostOp(
-                        Box::new(self.lower_ast_expr(expr, generic_context)?),
+                        Box::new(self.lower_ast_expr(expr, generic_context, body_context)?),
                         self.lower_ast_operator(op)
                     ),
-                ASTExprKind::MemberAccess(expr, member) =>
-                    TIRExprKind::MemberAccess(
-                        Box::new(self.lower_ast_expr(expr, generic_context)?),
-                        member
-                    ),
-                ASTExprKind::StaticAccess(expr, member) =>
-                    TIRExprKind::StaticAccess(
-                        Box::new(self.lower_ast_expr(expr, generic_context)?),
-                        member
-                    ),
-                ASTExprKind::Call(expr, args) =>
-                    TIRExprKind::Call(
-                        Box::new(self.lower_ast_expr(expr, generic_context)?),
-                        args.iter().map(|expr| { self.lower_ast_expr(expr, generic_context) })
-                            .collect::<Result<Vec<TIRExpr<'a>>, ASTtoTIRLowererError<'a>>>()?
-                    ),
+                ASTExprKind::MemberAccess(expr, member) => self.lower_ast_member_access(expr, member, expression.span, generic_context, body_context)?,
+                ASTExprKind::StaticAccess(expr, member) => match self.resolve_enum_variant(expression)? {
+                    Some((type_ref_index, variant_index)) => {
+                        let variant = self.enum_variant(type_ref_index, variant_index);
+                        match variant.kind {
+                            EnumVariantRefKind::Unit => TIRExprKind::EnumConstruct(type_ref_index, variant_index, vec![]),
+                            _ => return Err(ASTtoTIRLowererError::MismatchedVariantFields(expression.span, variant.name))
+                        }
+                    }
+                    None => self.lower_ast_static_access(expr, member, expression.span, generic_context, body_context)?
+                },
+                ASTExprKind::Call(expr, args) => if let Some((type_ref_index, variant_index)) = self.resolve_enum_variant(expr)? {
+                    let variant = self.enum_variant(type_ref_index, variant_index);
+                    match variant.kind {
+                        EnumVariantRefKind::Tuple if variant.fields.len() == args.len() => TIRExprKind::EnumConstruct(
+                            type_ref_index,
+                            variant_index,
+                            self.lower_ast_args(args, generic_context, body_context)?
+                        ),
+                        _ => return Err(ASTtoTIRLowererError::MismatchedVariantFields(expression.span, variant.name))
+                    }
+                } else {
+                    self.lower_ast_call(expr, args, generic_context, body_context)?
+                },
+                ASTExprKind::StructLiteral(expr, fields) => self.lower_ast_struct_literal(expr, fields, expression.span, generic_context, body_context)?,
                 ASTExprKind::Indexing(expr, index) =>
                     TIRExprKind::Indexing(
-                        Box::new(self.lower_ast_expr(expr, generic_context)?),
-                        Box::new(self.lower_ast_expr(index, generic_context)?)
+                        Box::new(self.lower_ast_expr(expr, generic_context, body_context)?),
+                        Box::new(self.lower_ast_expr(index, generic_context, body_context)?)
                     ),
                 ASTExprKind::Block(statement_block) =>
-                    TIRExprKind::Block(self.lower_ast_statement_block(statement_block, generic_context)?),
+                    TIRExprKind::Block(self.lower_ast_statement_block(statement_block, generic_context, body_context)?),
                 ASTExprKind::IfElse(cond, then_block, else_block) =>
                     TIRExprKind::IfElse(
-                        Box::new(self.lower_ast_expr(cond, generic_context)?),
-                        self.lower_ast_statement_block(then_block, generic_context)?,
-                        self.lower_ast_statement_block(else_block, generic_context)?
+                        Box::new(self.lower_ast_expr(cond, generic_context, body_context)?),
+                        self.lower_ast_statement_block(then_block, generic_context, body_context)?,
+                        self.lower_ast_statement_block(else_block, generic_context, body_context)?
                     ),
                 ASTExprKind::If(cond, then_block) =>
                     TIRExprKind::If(
-                        Box::new(self.lower_ast_expr(cond, generic_context)?),
-                        self.lower_ast_statement_block(then_block, generic_context)?
+                        Box::new(self.lower_ast_expr(cond, generic_context, body_context)?),
+                        self.lower_ast_statement_block(then_block, generic_context, body_context)?
                     ),
-                ASTExprKind::Loop(block) =>
-                    TIRExprKind::Loop(self.lower_ast_statement_block(block, generic_context)?),
-                ASTExprKind::While(cond, block) =>
-                    TIRExprKind::While(
-                        Box::new(self.lower_ast_expr(cond, generic_context)?),
-                        self.lower_ast_statement_block(block, generic_context)?
+                ASTExprKind::Loop(label, block) => {
+                    body_context.push_loop(*label, true);
+                    let block = self.lower_ast_statement_block(block, generic_context, body_context);
+                    body_context.pop_loop();
+                    TIRExprKind::Loop(*label, block?)
+                }
+                ASTExprKind::While(label, cond, block) => {
+                    // "break" in the condition exits this loop too
+                    body_context.push_loop(*label, false);
+                    let cond = self.lower_ast_expr(cond, generic_context, body_context);
+                    let block = self.lower_ast_statement_block(block, generic_context, body_context);
+                    body_context.pop_loop();
+                    TIRExprKind::While(*label, Box::new(cond?), block?)
+                }
+                ASTExprKind::Range(start, end, inclusive) =>
+                    TIRExprKind::Range(
+                        Box::new(self.lower_ast_expr(start, generic_context, body_context)?),
+                        Box::new(self.lower_ast_expr(end, generic_context, body_context)?),
+                        *inclusive
                     ),
-                ASTExprKind::Match() => {
-                    todo!()
+                ASTExprKind::New(type_info, args) => self.lower_ast_new(type_info, args, expression.span, generic_context, body_context)?,
+                // Constructors take the super call out of their body before lowering it
+                ASTExprKind::SuperCall(_) => return Err(ASTtoTIRLowererError::MisplacedSuperCall(expression.span)),
+                ASTExprKind::Match(scrutinee, arms) => self.lower_ast_match(scrutinee, arms, generic_context, body_context)?,
+                ASTExprKind::For(label, name, iterable, block) => self.lower_ast_for(*label, name, iterable, block, expression.span, generic_context, body_context)?,
+                ASTExprKind::Break(label, value) => {
+                    let allows_break_value = match body_context.find_loop(*label) {
+                        Some(loop_context) => loop_context.allows_break_value,
+                        None => return Err(match label {
+                            Some(label) => ASTtoTIRLowererError::NoSuchLabel(expression.span, label),
+                            None => ASTtoTIRLowererError::BreakOutsideLoop(expression.span)
+                        })
+                    };
+                    if value.is_some() && !allows_break_value {
+                        return Err(ASTtoTIRLowererError::BreakWithValueInNonLoop(expression.span));
+                    }
+                    TIRExprKind::Break(*label, match value {
+                        Some(value) => Some(Box::new(self.lower_ast_expr(value, generic_context, body_context)?)),
+                        None => None
+                    })
                 }
-                ASTExprKind::For() => {
-                    todo!()
+                ASTExprKind::Continue(label) => {
+                    if body_context.find_loop(*label).is_none() {
+                        return Err(match label {
+                            Some(label) => ASTtoTIRLowererError::NoSuchLabel(expression.span, label),
+                            None => ASTtoTIRLowererError::ContinueOutsideLoop(expression.span)
+                        });
+                    }
+                    TIRExprKind::Continue(*label)
+                }
+                ASTExprKind::Return(value) => {
+                    let returns_value = match &body_context.return_type {
+                        Some(TIRTypeInfo { kind: TIRTypeInfoKind::Primitive { primitive: PrimitiveType::Void, array_dim: 0 }, .. }) => false,
+                        Some(_) => true,
+                        None => return Err(ASTtoTIRLowererError::ReturnOutsideMethod(expression.span))
+                    };
+                    if returns_value != value.is_some() {
+                        return Err(ASTtoTIRLowererError::MismatchedReturn(expression.span));
+                    }
+                    // The value is checked against the return type by the type checker
+                    TIRExprKind::Return(match value {
+                        Some(value) => Some(Box::new(self.lower_ast_expr(value, generic_context, body_context)?)),
+                        None => None
+                    })
                 }
             },
-            span: expression.span.clone()
+            span: expression.span.clone(),
+            type_info: None
         })
     }
 
-    fn lower_ast_name_and_type(&self, name_and_type: &ASTNameAndType<'a>, generic_context: &GenericContext<'a, '_>) -> Result<TIRNameAndType<'a>, ASTtoTIRLowererError<'a>> {
+    pub fn lower_ast_name_and_type(&self, name_and_type: &ASTNameAndType<'a>, generic_context: &GenericContext<'a, '_>) -> Result<TIRNameAndType<'a>, ASTtoTIRLowererError<'a>> {
         Ok(TIRNameAndType {
             name: name_and_type.name,
             type_info: self.resolve_type_info(&name_and_type.type_info, Some(generic_context))?,
@@ -148,35 +347,50 @@ impl<'a> ASTtoTIRLowerer<'a> {
     }
 
     pub fn lower_ast_member(&self, member: &ASTMember<'a>, generic_context: &GenericContext<'a, '_>) -> Result<TIRMember<'a>, ASTtoTIRLowererError<'a>> {
+        let is_static = member.modifiers.iter().any(|m| matches!(m, ASTModifier::Static));
         Ok(TIRMember {
             kind: match &member.kind {
                 ASTMemberKind::Field { expression, name_and_type } => {
+                    let mut body_context = BodyContext::new(None, is_static);
+                    // Instance field initializers run at the start of every constructor, their locals go after the parameters
+                    if !is_static {
+                        for _ in 0..self.max_constructor_parameter_count(generic_context.type_ref_index) {
+                            body_context.allocate_local(None);
+                        }
+                    }
                     let expression = if let Some(expression) = expression {
-                        Some(self.lower_ast_expr(expression, generic_context)?)
+                        Some(self.lower_ast_expr(expression, generic_context, &mut body_context)?)
                     } else {
                         None
                     };
                     let name_and_type = self.lower_ast_name_and_type(name_and_type, generic_context)?;
                     TIRMemberKind::Field {
                         name_and_type,
-                        expression
+                        expression,
+                        local_count: body_context.local_count()
                     }
                 },
                 ASTMemberKind::Method { block, parameters, name_and_type } => {
+                    let name_and_type = self.lower_ast_name_and_type(name_and_type, generic_context)?;
+                    let parameters = parameters.iter().map(|name_and_type| self.lower_ast_name_and_type(name_and_type, generic_context))
+                        .collect::<Result<Vec<TIRNameAndType<'a>>, ASTtoTIRLowererError<'a>>>()?;
+                    let mut body_context = BodyContext::new(Some(name_and_type.type_info.clone()), is_static);
+                    for parameter in &parameters {
+                        body_context.declare_local(parameter.name, Some(parameter.type_info.clone()));
+                    }
                     let block = if let Some(block) = block {
-                        Some(self.lower_ast_statement_block(block, generic_context)?)
+                        Some(self.lower_ast_statement_block(block, generic_context, &mut body_context)?)
                     } else {
                         None
                     };
-                    let parameters = parameters.iter().map(|name_and_type| self.lower_ast_name_and_type(name_and_type, generic_context))
-                        .collect::<Result<Vec<TIRNameAndType<'a>>, ASTtoTIRLowererError<'a>>>()?;
-                    let name_and_type = self.lower_ast_name_and_type(name_and_type, generic_context)?;
                     TIRMemberKind::Method {
                         name_and_type,
                         block,
-                        parameters
+                        parameters,
+                        local_count: body_context.local_count()
                     }
                 }
+                ASTMemberKind::Constructor { parameters, block, .. } => self.lower_ast_constructor(parameters, block, generic_context)?
             },
             span: member.span.clone(),
             modifiers: member.modifiers.iter().map(|m| { match m {
@@ -186,4 +400,43 @@ impl<'a> ASTtoTIRLowerer<'a> {
             } }).collect()
         })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tir::ast_lowerer::ASTtoTIRLowererError;
+    use crate::testing::{lower, lowering_error};
+
+    fn method_error(body: &str) -> String {
+        let source = format!("mod a; class A {{ fn f() -> u64 {{ {} }} }}", body);
+        format!("{:?}", lowering_error(&source))
+    }
+
+    #[test]
+    fn jumps_need_a_matching_loop() {
+        assert!(method_error("break;").starts_with("BreakOutsideLoop"));
+        assert!(method_error("loop { continue 'outer; }").starts_with("NoSuchLabel"));
+        assert!(method_error("while true { break 1; }").starts_with("BreakWithValueInNonLoop"));
+        lower("mod a; class A { fn f() -> u64 { 'outer: loop { while true { break 'outer 1; } } } }").unwrap();
+    }
+
+    #[test]
+    fn returns_match_the_method() {
+        assert!(method_error("return;").starts_with("MismatchedReturn"));
+        assert!(matches!(lowering_error("mod a; class A { fn f() { return 1; } }"), ASTtoTIRLowererError::MismatchedReturn(_)));
+        assert!(matches!(lowering_error("mod a; class A { x: u64 = return 1; }"), ASTtoTIRLowererError::ReturnOutsideMethod(_)));
+    }
+
+    #[test]
+    fn enum_variants_are_constructed_with_their_own_shape() {
+        let source = "mod a; enum Event { Tick, Damage(u32), Chat { from: u64, text: u64 } } class A { fn f() -> Event { BODY } }";
+        lower(&source.replace("BODY", "Event::Chat { text: 1, from: 2 }")).unwrap();
+        lower(&source.replace("BODY", "Event::Damage(3)")).unwrap();
+        let bodies = ["Event::Explode", "Event::Chat { from: 2 }", "Event::Chat { from: 2, from: 3, text: 1 }", "Event::Damage { amount: 3 }"];
+        let sources: Vec<String> = bodies.iter().map(|body| source.replace("BODY", body)).collect();
+        assert!(matches!(lowering_error(&sources[0]), ASTtoTIRLowererError::NoSuchVariant(_, "Explode")));
+        assert!(matches!(lowering_error(&sources[1]), ASTtoTIRLowererError::MissingVariantField(_, "text")));
+        assert!(matches!(lowering_error(&sources[2]), ASTtoTIRLowererError::DuplicateVariantField(_, "from")));
+        assert!(matches!(lowering_error(&sources[3]), ASTtoTIRLowererError::MismatchedVariantFields(_, "Damage")));
+    }
+}
@@ -1,30 +1,194 @@
-use crate::ast::{ASTRoot, ASTTypeKind, ASTTypeInfo, ASTModifier};
-use crate::tir::{TIRRoot, TIRTypeInfo, TIRTypeInfoKind, TIRType, TIRTypeKind, PrimitiveType};
-use crate::type_ref_pool::{TypeRefPool, TypeRef, TypeRefKind, ClassTypeRef, TypeRefGeneric};
+use crate::ast::{ASTRoot, ASTType, ASTTypeKind, ASTTypeInfo, ASTModifier, ASTMemberKind, ASTPartialTypeInfo, ASTVariantKind};
+use crate::tir::{TIRRoot, TIRTypeInfo, TIRTypeInfoKind, TIRType, TIRTypeKind, PrimitiveType, TIRMemberKind, TIRVariant, TIRVariantKind};
+use crate::field_ref_pool::{FieldRefPool, FieldRef};
+use crate::method_ref_pool::{MethodRefPool, MethodRef};
+use crate::type_ref_pool::{TypeRefPool, TypeRef, TypeRefKind, ClassTypeRef, TypeRefGeneric, InterfaceTypeRef, EnumTypeRef, EnumVariantRef, EnumVariantRefKind};
 use std::collections::HashMap;
+use std::cell::Cell;
 use crate::tir::TIRTypeInfoKind::Primitive;
+use crate::reporting::TokenSpan;
+use crate::lexer::is_reserved_word;
 
 pub mod member_lowerer;
+pub mod pattern_lowerer;
+pub mod for_lowerer;
+pub mod constructor_lowerer;
+pub mod name_resolver;
+pub mod import_resolver;
+pub mod type_alias_resolver;
+
+// A cycle in the graph given by the edges of every node, as the path going around it (first node repeated at the end)
+fn find_cycle(node_count: usize, edges: impl Fn(usize) -> Vec<usize>) -> Option<Vec<usize>> {
+    // 0 : not visited, 1 : on the current path, 2 : done
+    let mut states = vec![0u8; node_count];
+    for start in 0..node_count {
+        if states[start] != 0 {
+            continue;
+        }
+        states[start] = 1;
+        // Nodes on the current path with the edges left to walk from them
+        let mut path = vec![(start, edges(start))];
+        while let Some((node, remaining)) = path.last_mut() {
+            let node = *node;
+            match remaining.pop() {
+                Some(next) => match states[next] {
+                    0 => {
+                        states[next] = 1;
+                        path.push((next, edges(next)));
+                    }
+                    1 => {
+                        let cycle_start = path.iter().position(|(n, _)| *n == next).unwrap();
+                        let mut cycle: Vec<usize> = path[cycle_start..].iter().map(|(n, _)| *n).collect();
+                        cycle.push(next);
+                        return Some(cycle);
+                    }
+                    _ => {}
+                }
+                None => {
+                    states[node] = 2;
+                    path.pop();
+                }
+            }
+        }
+    }
+    None
+}
 
 pub struct GenericContext<'a, 'b> {
     type_ref_index: usize,
     name_to_generic_index: &'b HashMap<&'a str, usize>
 }
 
+pub struct LoopContext<'a> {
+    pub label: Option<&'a str>,
+    // Only "loop" can be broken out of with a value
+    pub allows_break_value: bool
+}
+
+// Locals and loops in scope while lowering a method body or a field initializer
+pub struct BodyContext<'a> {
+    // Innermost scope last, with the slot of every local
+    scopes: Vec<Vec<(&'a str, usize)>>,
+    // Declared type of every slot, slots are never reused within a body so shadowing a local just gives it a new one
+    slots: Vec<Option<TIRTypeInfo>>,
+    // Innermost loop last
+    loops: Vec<LoopContext<'a>>,
+    // None in field initializers, where "return" is not allowed
+    pub return_type: Option<TIRTypeInfo>,
+    // Static methods and static field initializers have no "this"
+    pub is_static: bool
+}
+
+impl<'a> BodyContext<'a> {
+    pub fn new(return_type: Option<TIRTypeInfo>, is_static: bool) -> Self {
+        Self {
+            scopes: vec![vec![]],
+            // "this" is in the first slot
+            slots: if is_static { vec![] } else { vec![None] },
+            loops: vec![],
+            return_type,
+            is_static
+        }
+    }
+
+    pub fn push_loop(&mut self, label: Option<&'a str>, allows_break_value: bool) {
+        self.loops.push(LoopContext {
+            label,
+            allows_break_value
+        });
+    }
+
+    pub fn pop_loop(&mut self) {
+        self.loops.pop();
+    }
+
+    // Innermost loop without a label, or the innermost loop with that label
+    pub fn find_loop(&self, label: Option<&str>) -> Option<&LoopContext<'a>> {
+        match label {
+            Some(label) => self.loops.iter().rev().find(|l| l.label == Some(label)),
+            None => self.loops.last()
+        }
+    }
+
+    pub fn push_scope(&mut self) {
+        self.scopes.push(vec![]);
+    }
+
+    pub fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    // Unnamed slot, for the locals introduced by desugaring
+    pub fn allocate_local(&mut self, type_info: Option<TIRTypeInfo>) -> usize {
+        self.slots.push(type_info);
+        self.slots.len() - 1
+    }
+
+    // Makes the slot visible under that name in the innermost scope
+    pub fn bind_local(&mut self, name: &'a str, slot: usize) {
+        self.scopes.last_mut().unwrap().push((name, slot));
+    }
+
+    pub fn declare_local(&mut self, name: &'a str, type_info: Option<TIRTypeInfo>) -> usize {
+        let slot = self.allocate_local(type_info);
+        self.bind_local(name, slot);
+        slot
+    }
+
+    pub fn local(&self, name: &str) -> Option<usize> {
+        self.scopes.iter().rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|(local, _)| *local == name)
+            .map(|(_, slot)| *slot)
+    }
+
+    pub fn slot_type(&self, slot: usize) -> Option<&TIRTypeInfo> {
+        self.slots[slot].as_ref()
+    }
+
+    pub fn local_count(&self) -> usize {
+        self.slots.len()
+    }
+}
+
+struct MethodSignature<'a> {
+    name: &'a str,
+    parameters: Vec<TIRTypeInfo>,
+    return_type: TIRTypeInfo
+}
+
 pub struct ASTtoTIRLowerer<'a> {
     mod_context: Vec<&'a str>,
     ast_root: ASTRoot<'a>,
 
-    type_ref_pool: TypeRefPool<'a>
+    type_ref_pool: TypeRefPool<'a>,
+    field_ref_pool: FieldRefPool<'a>,
+    method_ref_pool: MethodRefPool<'a>,
+
+    // Types brought in by single "use" declarations, by the name they are used with, with the index of the declaration
+    imports: HashMap<&'a str, (usize, usize)>,
+    // Modules brought in by glob "use" declarations, with the index of the declaration
+    glob_imports: Vec<(Vec<&'a str>, usize)>,
+    // Whether a type was resolved through each "use" declaration
+    used_imports: Vec<Cell<bool>>,
+    // Index of every type alias declaration by its name
+    type_aliases: HashMap<&'a str, usize>
 }
 
 impl<'a> ASTtoTIRLowerer<'a> {
     pub fn new(ast_root: ASTRoot<'a>) -> Self {
         Self {
             mod_context: ast_root.mod_decl.path.elements.clone(),
+            used_imports: ast_root.use_decls.iter().map(|_| Cell::new(false)).collect(),
             ast_root,
 
-            type_ref_pool: TypeRefPool::new()
+            type_ref_pool: TypeRefPool::new(),
+            field_ref_pool: FieldRefPool::new(),
+            method_ref_pool: MethodRefPool::new(),
+
+            imports: HashMap::new(),
+            glob_imports: vec![],
+            type_aliases: HashMap::new()
         }
     }
 
@@ -34,14 +198,16 @@ impl<'a> ASTtoTIRLowerer<'a> {
                 let type_ref_index = *type_ref_index;
                 let type_ref_generics = &self.type_ref_pool.type_refs[type_ref_index].generics;
                 if generics.len() != type_ref_generics.len() {
-                    return Err(ASTtoTIRLowererError::MismatchedGenerics(type_ref_index, generics.len(), type_ref_generics.len()));
+                    return Err(ASTtoTIRLowererError::MismatchedGenerics(type_info.span, type_ref_index, generics.len(), type_ref_generics.len()));
                 }
                 for i in 0..generics.len() {
                     // We recursively check for nested generics
                     self.check_generics(&generics[i])?;
                     for type_reg_generic_requirement in &type_ref_generics[i].super_requirements {
-                        if !self.type_ref_pool.check_assignable_to(&generics[i], type_reg_generic_requirement) {
-                            return Err(ASTtoTIRLowererError::TypeMismatch)
+                        // Requirements can mention the other generics ("U: Wrapper<T>")
+                        let type_reg_generic_requirement = self.type_ref_pool.substitute_generics(type_reg_generic_requirement, type_ref_index, generics);
+                        if !self.type_ref_pool.check_assignable_to(&generics[i], &type_reg_generic_requirement) {
+                            return Err(ASTtoTIRLowererError::TypeMismatch(generics[i].span))
                         }
                     }
                 }
@@ -53,26 +219,15 @@ impl<'a> ASTtoTIRLowerer<'a> {
     }
 
     fn resolve_type_info(&self, type_info: &ASTTypeInfo<'a>, generic_context: Option<&GenericContext<'a, '_>>) -> Result<TIRTypeInfo, ASTtoTIRLowererError<'a>> {
+        self.resolve_type_info_in(type_info, generic_context, None)
+    }
+
+    // Alias generics are only there while resolving the target of a type alias
+    fn resolve_type_info_in(&self, type_info: &ASTTypeInfo<'a>, generic_context: Option<&GenericContext<'a, '_>>, alias_generics: Option<&HashMap<&'a str, TIRTypeInfo>>) -> Result<TIRTypeInfo, ASTtoTIRLowererError<'a>> {
         if type_info.path.elements.len() == 1 {
-            'primitive_test: loop {
-                let primitive = match type_info.path.elements[0] {
-                    "void" => PrimitiveType::Void,
-                    "i64" => PrimitiveType::I64,
-                    "i32" => PrimitiveType::I32,
-                    "i16" => PrimitiveType::I16,
-                    "i8" => PrimitiveType::I8,
-                    "u64" => PrimitiveType::U64,
-                    "u32" => PrimitiveType::U32,
-                    "u16" => PrimitiveType::U16,
-                    "u8" => PrimitiveType::U8,
-                    "F64" => PrimitiveType::F64,
-                    "F32" => PrimitiveType::F32,
-                    "bool" => PrimitiveType::Boolean,
-                    "char" => PrimitiveType::Character,
-                    _ => break 'primitive_test
-                };
+            if let Some(primitive) = PrimitiveType::from_name(type_info.path.elements[0]) {
                 if !type_info.generics.is_empty() {
-                    return Err(ASTtoTIRLowererError::GenericOnPrimitive);
+                    return Err(ASTtoTIRLowererError::GenericOnPrimitive(type_info.span));
                 }
                 return Ok(TIRTypeInfo {
                     kind: TIRTypeInfoKind::Primitive {
@@ -86,7 +241,7 @@ impl<'a> ASTtoTIRLowerer<'a> {
             if let Some(g) = generic_context {
                 if let Some(generic_index) = g.name_to_generic_index.get(type_info.path.elements[0]) {
                     if !type_info.generics.is_empty() {
-                        return Err(ASTtoTIRLowererError::GenericOnGeneric(type_info.path.elements[0]));
+                        return Err(ASTtoTIRLowererError::GenericOnGeneric(type_info.span, type_info.path.elements[0]));
                     }
                     return Ok(TIRTypeInfo {
                         kind: TIRTypeInfoKind::Generic {
@@ -98,20 +253,57 @@ impl<'a> ASTtoTIRLowerer<'a> {
                     })
                 }
             }
+
+            if let Some(alias_generic) = alias_generics.and_then(|a| a.get(type_info.path.elements[0])) {
+                if !type_info.generics.is_empty() {
+                    return Err(ASTtoTIRLowererError::GenericOnGeneric(type_info.span, type_info.path.elements[0]));
+                }
+                let mut resolved = alias_generic.add_array_dim(type_info.array_dim);
+                resolved.span = type_info.span;
+                return Ok(resolved);
+            }
+
+            if let Some(type_alias_index) = self.type_aliases.get(type_info.path.elements[0]) {
+                return self.resolve_type_alias(*type_alias_index, type_info, generic_context, alias_generics);
+            }
+
+            // Boxed names stand for their primitive unless a type with that name is in scope
+            if let Some(primitive) = PrimitiveType::from_boxed_name(type_info.path.elements[0]) {
+                if let Err(ASTtoTIRLowererError::NoSuchType(..)) = self.find_type_ref_index(&type_info.path.elements, type_info.path.span) {
+                    if !type_info.generics.is_empty() {
+                        return Err(ASTtoTIRLowererError::GenericOnPrimitive(type_info.span));
+                    }
+                    return Ok(TIRTypeInfo {
+                        kind: TIRTypeInfoKind::Primitive {
+                            primitive,
+                            array_dim: type_info.array_dim
+                        },
+                        span: type_info.span
+                    })
+                }
+            }
         }
-        let type_ref_index = self.resolve_type_ref_index(&type_info.path.elements)?;
+        let type_ref_index = self.resolve_type_ref_index(&type_info.path.elements, type_info.path.span)?;
         Ok(TIRTypeInfo {
             kind: TIRTypeInfoKind::TypeRef {
                 type_ref_index,
-                generics: type_info.generics.iter().map(|g| { self.resolve_type_info(g, generic_context) }).collect::<Result<Vec<TIRTypeInfo>, ASTtoTIRLowererError<'a>>>()?,
+                generics: type_info.generics.iter().map(|g| { self.resolve_type_info_in(g, generic_context, alias_generics) }).collect::<Result<Vec<TIRTypeInfo>, ASTtoTIRLowererError<'a>>>()?,
                 array_dim: type_info.array_dim
             },
             span: type_info.span
         })
     }
 
-    fn resolve_type_ref_index(&self, path: &[&'a str]) -> Result<usize, ASTtoTIRLowererError<'a>> {
-        // TODO : Check visibility
+    // The span is the one of the path, for errors
+    fn resolve_type_ref_index(&self, path: &[&'a str], span: TokenSpan) -> Result<usize, ASTtoTIRLowererError<'a>> {
+        let type_ref_index = self.find_type_ref_index(path, span)?;
+        if !self.type_ref_pool.is_type_accessible(type_ref_index, &self.mod_context) {
+            return Err(ASTtoTIRLowererError::InaccessibleType(span, path.to_vec()));
+        }
+        Ok(type_ref_index)
+    }
+
+    fn find_type_ref_index(&self, path: &[&'a str], span: TokenSpan) -> Result<usize, ASTtoTIRLowererError<'a>> {
         if let Some(index) = self.type_ref_pool.full_path_to_type_ref_index.get(path) {
             Ok(*index)
         }
@@ -121,44 +313,79 @@ impl<'a> ASTtoTIRLowerer<'a> {
             if let Some(index) = self.type_ref_pool.full_path_to_type_ref_index.get(&combined) {
                 Ok(*index)
             }
+            else if let [name] = path {
+                match self.resolve_import(name, span)? {
+                    Some(index) => Ok(index),
+                    None => Err(ASTtoTIRLowererError::NoSuchType(span, path.to_vec()))
+                }
+            }
             else {
-                Err(ASTtoTIRLowererError::NoSuchType(path.to_vec()))
+                Err(ASTtoTIRLowererError::NoSuchType(span, path.to_vec()))
             }
         }
     }
 
+    // Kind of the type ref the type info points to, if it points to one (arrays don't count)
+    fn type_ref_kind_of(&self, type_info: &TIRTypeInfo) -> Option<&TypeRefKind<'a>> {
+        match &type_info.kind {
+            TIRTypeInfoKind::TypeRef { type_ref_index, array_dim: 0, .. } => Some(&self.type_ref_pool.type_refs[*type_ref_index].kind),
+            _ => None
+        }
+    }
+
+    fn resolve_super_type_info(&self, super_type: &ASTPartialTypeInfo<'a>, type_ref_index: usize) -> Result<TIRTypeInfo, ASTtoTIRLowererError<'a>> {
+        let type_info = super_type.into_type_info();
+        self.resolve_type_info(&type_info, Some(&GenericContext {
+            type_ref_index,
+            name_to_generic_index: &self.type_ref_pool.type_refs[type_ref_index].name_to_generic_index
+        }))
+    }
+
+    // Declaration the type ref was registered for
+    fn type_decl_of(&self, type_ref_index: usize) -> &ASTType<'a> {
+        let (type_decl_index, _) = self.type_ref_pool.type_decl_index_to_type_ref_index.iter().find(|(_, index)| **index == type_ref_index).unwrap();
+        &self.ast_root.types[*type_decl_index]
+    }
+
     fn name_to_full_path(&self, name: &'a str) -> Vec<&'a str> {
         let mut result = self.mod_context.clone();
         result.push(name);
         result
     }
 
+    // Primitive names and keywords can't name types, generics or members, they would never be resolved to them
+    fn check_name(&self, name: &'a str, span: TokenSpan) -> Result<(), ASTtoTIRLowererError<'a>> {
+        if PrimitiveType::from_name(name).is_some() || is_reserved_word(name) {
+            return Err(ASTtoTIRLowererError::ReservedName(span, name));
+        }
+        Ok(())
+    }
+
     #[inline(always)]
     fn register_types(&mut self) -> Result<(), ASTtoTIRLowererError<'a>> {
-        // TODO : check if type has the same name as primitive (not allowed)
-
         for type_decl_index in 0..self.ast_root.types.len() {
             let type_decl = &self.ast_root.types[type_decl_index];
+            self.check_name(type_decl.name, type_decl.span)?;
             let full_path = self.name_to_full_path(type_decl.name);
             let type_ref_index = self.type_ref_pool.type_refs.len();
 
             self.type_ref_pool.type_decl_index_to_type_ref_index.insert(type_decl_index, type_ref_index);
+            if let Some(_) = self.type_ref_pool.full_path_to_type_ref_index.insert(full_path.clone(), type_ref_index) {
+                return Err(ASTtoTIRLowererError::DuplicateTypeDecl(type_decl.span, full_path.clone()));
+            }
+
             match type_decl.kind {
                 ASTTypeKind::Class { .. } => {
-                    if let Some(_) = self.type_ref_pool.full_path_to_type_ref_index.insert(full_path.clone(), type_ref_index) {
-                        return Err(ASTtoTIRLowererError::DuplicateTypeDecl(full_path.clone()));
-                    }
-
                     let mut is_abstract = false;
                     for modifier in &type_decl.modifiers {
                         match modifier {
-                            ASTModifier::Static => return Err(ASTtoTIRLowererError::ModifierNotCompatibleForClass(ASTModifier::Static)),
+                            ASTModifier::Static => return Err(ASTtoTIRLowererError::ModifierNotCompatibleForClass(type_decl.span, ASTModifier::Static)),
                             ASTModifier::Abstract => if is_abstract {
-                                return Err(ASTtoTIRLowererError::DuplicateModifier(ASTModifier::Abstract));
+                                return Err(ASTtoTIRLowererError::DuplicateModifier(type_decl.span, ASTModifier::Abstract));
                             } else {
                                 is_abstract = true
                             },
-                            ASTModifier::Native => return Err(ASTtoTIRLowererError::ModifierNotCompatibleForClass(ASTModifier::Native)),
+                            ASTModifier::Native => return Err(ASTtoTIRLowererError::ModifierNotCompatibleForClass(type_decl.span, ASTModifier::Native)),
                         }
                     }
 
@@ -166,9 +393,50 @@ impl<'a> ASTtoTIRLowerer<'a> {
                         kind: TypeRefKind::Class(ClassTypeRef {
                             // Will be filled in later (register_supers)
                             super_class: None,
+                            impls: vec![],
+                            // Will be filled in later (register_members)
+                            constructors: vec![],
+                            constructor_visibilities: vec![],
                             is_abstract
                         }),
                         full_path,
+                        visibility: type_decl.visibility,
+                        // Will be filled in later (register_generics_boundless)
+                        generics: vec![],
+                        name_to_generic_index: HashMap::new()
+                    });
+                }
+                ASTTypeKind::Enum { .. } => {
+                    if let Some(modifier) = type_decl.modifiers.first() {
+                        return Err(ASTtoTIRLowererError::ModifierNotCompatibleForEnum(type_decl.span, modifier.clone()));
+                    }
+
+                    self.type_ref_pool.type_refs.push(TypeRef {
+                        kind: TypeRefKind::Enum(EnumTypeRef {
+                            // Will be filled in later (register_variants)
+                            variants: vec![],
+                            name_to_variant_index: HashMap::new()
+                        }),
+                        full_path,
+                        visibility: type_decl.visibility,
+                        // Will be filled in later (register_generics_boundless)
+                        generics: vec![],
+                        name_to_generic_index: HashMap::new()
+                    });
+                }
+                ASTTypeKind::Interface { .. } => {
+                    // Interfaces are always abstract
+                    if let Some(modifier) = type_decl.modifiers.first() {
+                        return Err(ASTtoTIRLowererError::ModifierNotCompatibleForInterface(type_decl.span, modifier.clone()));
+                    }
+
+                    self.type_ref_pool.type_refs.push(TypeRef {
+                        kind: TypeRefKind::Interface(InterfaceTypeRef {
+                            // Will be filled in later (register_supers)
+                            super_interfaces: vec![]
+                        }),
+                        full_path,
+                        visibility: type_decl.visibility,
                         // Will be filled in later (register_generics_boundless)
                         generics: vec![],
                         name_to_generic_index: HashMap::new()
@@ -187,11 +455,12 @@ impl<'a> ASTtoTIRLowerer<'a> {
             let type_ref_index = self.type_ref_pool.type_decl_index_to_type_ref_index[&type_decl_index];
 
             for generic in &type_decl.generics {
+                self.check_name(generic.name, generic.span)?;
                 let type_ref = &mut self.type_ref_pool.type_refs[type_ref_index];
                 let generic_index = type_ref.generics.len();
                 if let Some(_) = type_ref.name_to_generic_index.insert(generic.name, generic_index) {
                     let full_path = self.name_to_full_path(type_decl.name);
-                    return Err(ASTtoTIRLowererError::DuplicateGeneric(full_path, generic.name))
+                    return Err(ASTtoTIRLowererError::DuplicateGeneric(generic.span, full_path, generic.name))
                 }
                 type_ref.generics.push(TypeRefGeneric {
                     name: generic.name,
@@ -211,22 +480,55 @@ impl<'a> ASTtoTIRLowerer<'a> {
             let type_ref_index = self.type_ref_pool.type_decl_index_to_type_ref_index[&type_decl_index];
 
             match &type_decl.kind {
-                ASTTypeKind::Class { super_class, .. } => {
-                    if let Some(super_class) = super_class {
-                        let type_info = super_class.into_type_info();
-                        let type_info = self.resolve_type_info(&type_info, None)?;
-                        match type_info.kind {
-                            TIRTypeInfoKind::TypeRef { .. } => {}
-                            _ => return Err(ASTtoTIRLowererError::InvalidSuperClass(type_decl.name))
+                ASTTypeKind::Class { super_class, impls, .. } => {
+                    let super_class = if let Some(super_class) = super_class {
+                        let type_info = self.resolve_super_type_info(super_class, type_ref_index)?;
+                        match self.type_ref_kind_of(&type_info) {
+                            Some(TypeRefKind::Class(_)) => {}
+                            _ => return Err(ASTtoTIRLowererError::InvalidSuperClass(super_class.span, type_decl.name))
                         }
-                        match &mut self.type_ref_pool.type_refs[type_ref_index].kind {
-                            TypeRefKind::Class(class_type_ref) => {
-                                class_type_ref.super_class = Some(type_info);
-                            },
-                            _ => unreachable!()
+                        Some(type_info)
+                    } else {
+                        None
+                    };
+
+                    let mut resolved_impls = vec![];
+                    for impl_decl in impls {
+                        let type_info = self.resolve_super_type_info(impl_decl, type_ref_index)?;
+                        match self.type_ref_kind_of(&type_info) {
+                            Some(TypeRefKind::Interface(_)) => {}
+                            _ => return Err(ASTtoTIRLowererError::InvalidImpl(impl_decl.span, type_decl.name))
+                        }
+                        resolved_impls.push(type_info);
+                    }
+
+                    match &mut self.type_ref_pool.type_refs[type_ref_index].kind {
+                        TypeRefKind::Class(class_type_ref) => {
+                            class_type_ref.super_class = super_class;
+                            class_type_ref.impls = resolved_impls;
+                        },
+                        _ => unreachable!()
+                    }
+                }
+                ASTTypeKind::Interface { super_interfaces, .. } => {
+                    let mut resolved_super_interfaces = vec![];
+                    for super_interface in super_interfaces {
+                        let type_info = self.resolve_super_type_info(super_interface, type_ref_index)?;
+                        match self.type_ref_kind_of(&type_info) {
+                            Some(TypeRefKind::Interface(_)) => {}
+                            _ => return Err(ASTtoTIRLowererError::InvalidSuperInterface(super_interface.span, type_decl.name))
                         }
+                        resolved_super_interfaces.push(type_info);
+                    }
+
+                    match &mut self.type_ref_pool.type_refs[type_ref_index].kind {
+                        TypeRefKind::Interface(interface_type_ref) => {
+                            interface_type_ref.super_interfaces = resolved_super_interfaces;
+                        },
+                        _ => unreachable!()
                     }
                 }
+                ASTTypeKind::Enum { .. } => {}
             }
         }
 
@@ -259,9 +561,257 @@ impl<'a> ASTtoTIRLowerer<'a> {
         Ok(())
     }
 
+    // Types can't be their own supers and generics can't be their own requirements
+    #[inline(always)]
+    fn check_cycles(&self) -> Result<(), ASTtoTIRLowererError<'a>> {
+        let type_refs = &self.type_ref_pool.type_refs;
+        if let Some(cycle) = find_cycle(type_refs.len(), |type_ref_index| {
+            let mut supers = self.type_ref_pool.direct_supers(type_ref_index);
+            // Edges are walked from the last one, this reports the cycle through the first declared super
+            supers.reverse();
+            supers
+        }) {
+            let path: Vec<String> = cycle.iter().map(|type_ref_index| type_refs[*type_ref_index].full_path.join("::")).collect();
+            return Err(ASTtoTIRLowererError::CyclicInheritance(self.type_decl_of(cycle[0]).span, path.join(" -> ")));
+        }
+
+        for (type_ref_index, type_ref) in type_refs.iter().enumerate() {
+            if let Some(cycle) = find_cycle(type_ref.generics.len(), |generic_index| {
+                type_ref.generics[generic_index].super_requirements.iter().rev().filter_map(|requirement| match &requirement.kind {
+                    TIRTypeInfoKind::Generic { generic_index, array_dim: 0, .. } => Some(*generic_index),
+                    _ => None
+                }).collect()
+            }) {
+                let path: Vec<&str> = cycle.iter().map(|generic_index| type_ref.generics[*generic_index].name).collect();
+                let span = self.type_decl_of(type_ref_index).generics[cycle[0]].span;
+                return Err(ASTtoTIRLowererError::CyclicGenericBounds(span, type_ref.full_path.clone(), path.join(" -> ")));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn register_variants(&mut self) -> Result<(), ASTtoTIRLowererError<'a>> {
+        for type_decl_index in 0..self.ast_root.types.len() {
+            let type_decl = &self.ast_root.types[type_decl_index];
+            let type_ref_index = self.type_ref_pool.type_decl_index_to_type_ref_index[&type_decl_index];
+
+            let ast_variants = match &type_decl.kind {
+                ASTTypeKind::Enum { variants } => variants,
+                _ => continue
+            };
+
+            let type_ref = &self.type_ref_pool.type_refs[type_ref_index];
+            let generic_context = GenericContext {
+                type_ref_index,
+                name_to_generic_index: &type_ref.name_to_generic_index
+            };
+            let mut variants = vec![];
+            let mut name_to_variant_index = HashMap::new();
+            for ast_variant in ast_variants {
+                if let Some(_) = name_to_variant_index.insert(ast_variant.name, variants.len()) {
+                    return Err(ASTtoTIRLowererError::DuplicateVariant(ast_variant.span, ast_variant.name));
+                }
+
+                let (kind, fields) = match &ast_variant.kind {
+                    ASTVariantKind::Unit => (EnumVariantRefKind::Unit, vec![]),
+                    ASTVariantKind::Tuple(fields) => (
+                        EnumVariantRefKind::Tuple,
+                        fields.iter().map(|f| self.resolve_type_info(f, Some(&generic_context)))
+                            .collect::<Result<Vec<TIRTypeInfo>, ASTtoTIRLowererError<'a>>>()?
+                    ),
+                    ASTVariantKind::Struct(fields) => {
+                        let mut names = vec![];
+                        for field in fields {
+                            if names.contains(&field.name) {
+                                return Err(ASTtoTIRLowererError::DuplicateVariantField(field.span, field.name));
+                            }
+                            names.push(field.name);
+                        }
+                        (
+                            EnumVariantRefKind::Struct(names),
+                            fields.iter().map(|f| self.resolve_type_info(&f.type_info, Some(&generic_context)))
+                                .collect::<Result<Vec<TIRTypeInfo>, ASTtoTIRLowererError<'a>>>()?
+                        )
+                    }
+                };
+                variants.push(EnumVariantRef {
+                    name: ast_variant.name,
+                    kind,
+                    fields
+                });
+            }
+
+            match &mut self.type_ref_pool.type_refs[type_ref_index].kind {
+                TypeRefKind::Enum(enum_type_ref) => {
+                    enum_type_ref.variants = variants;
+                    enum_type_ref.name_to_variant_index = name_to_variant_index;
+                }
+                _ => unreachable!()
+            }
+        }
+
+        Ok(())
+    }
+
+    // Whether the field is static
+    // The span is the one of the member, for errors
+    fn field_modifiers(&self, modifiers: &[ASTModifier], span: TokenSpan) -> Result<bool, ASTtoTIRLowererError<'a>> {
+        let mut is_static = false;
+        for modifier in modifiers {
+            match modifier {
+                ASTModifier::Static => if is_static {
+                    return Err(ASTtoTIRLowererError::DuplicateModifier(span, ASTModifier::Static));
+                } else {
+                    is_static = true
+                },
+                ASTModifier::Abstract => return Err(ASTtoTIRLowererError::ModifierNotCompatibleForField(span, ASTModifier::Abstract)),
+                ASTModifier::Native => return Err(ASTtoTIRLowererError::ModifierNotCompatibleForField(span, ASTModifier::Native))
+            }
+        }
+        Ok(is_static)
+    }
+
+    // Whether the method is static, abstract and native
+    fn method_modifiers(&self, modifiers: &[ASTModifier], span: TokenSpan) -> Result<(bool, bool, bool), ASTtoTIRLowererError<'a>> {
+        let mut is_static = false;
+        let mut is_abstract = false;
+        let mut is_native = false;
+        for modifier in modifiers {
+            let flag = match modifier {
+                ASTModifier::Static => &mut is_static,
+                ASTModifier::Abstract => &mut is_abstract,
+                ASTModifier::Native => &mut is_native
+            };
+            if *flag {
+                return Err(ASTtoTIRLowererError::DuplicateModifier(span, modifier.clone()));
+            }
+            *flag = true;
+        }
+        Ok((is_static, is_abstract, is_native))
+    }
+
+    #[inline(always)]
+    fn register_members(&mut self) -> Result<(), ASTtoTIRLowererError<'a>> {
+        for type_decl_index in 0..self.ast_root.types.len() {
+            let type_decl = &self.ast_root.types[type_decl_index];
+            let type_ref_index = self.type_ref_pool.type_decl_index_to_type_ref_index[&type_decl_index];
+
+            // Fields and constructors in interfaces are reported when lowering them
+            let (members, is_interface) = match &type_decl.kind {
+                ASTTypeKind::Class { members, .. } => (members, false),
+                ASTTypeKind::Interface { members, .. } => (members, true),
+                _ => continue
+            };
+
+            let type_ref = &self.type_ref_pool.type_refs[type_ref_index];
+            let generic_context = GenericContext {
+                type_ref_index,
+                name_to_generic_index: &type_ref.name_to_generic_index
+            };
+            let mut field_index = 0;
+            let mut method_index = 0;
+            let mut constructors: Vec<Vec<TIRTypeInfo>> = vec![];
+            let mut constructor_visibilities = vec![];
+            for (index_in_all_members, member) in members.iter().enumerate() {
+                match &member.kind {
+                    ASTMemberKind::Field { name_and_type, .. } if !is_interface => {
+                        self.check_name(name_and_type.name, member.span)?;
+                        let key = (type_ref_index, name_and_type.name);
+                        if self.field_ref_pool.type_ref_index_and_name_to_field_ref_index.contains_key(&key) ||
+                            self.method_ref_pool.name_and_type_ref_index_to_method_ref_indexes.contains_key(&key) {
+                            return Err(ASTtoTIRLowererError::FieldNameTaken(member.span, name_and_type.name));
+                        }
+                        let is_static = self.field_modifiers(&member.modifiers, member.span)?;
+                        let type_info = self.resolve_type_info(&name_and_type.type_info, Some(&generic_context))?;
+                        self.check_generics(&type_info)?;
+
+                        self.field_ref_pool.type_ref_index_and_name_to_field_ref_index.insert(key, self.field_ref_pool.field_refs.len());
+                        self.field_ref_pool.field_refs.push(FieldRef {
+                            associated_type_ref_index: type_ref_index,
+                            type_info,
+                            name: name_and_type.name,
+                            index: field_index,
+                            index_in_all_members,
+                            visibility: member.visibility,
+                            is_static
+                        });
+                        field_index += 1;
+                    }
+                    ASTMemberKind::Method { name_and_type, parameters, .. } => {
+                        self.check_name(name_and_type.name, member.span)?;
+                        let key = (type_ref_index, name_and_type.name);
+                        if self.field_ref_pool.type_ref_index_and_name_to_field_ref_index.contains_key(&key) {
+                            return Err(ASTtoTIRLowererError::MethodNameTaken(member.span, name_and_type.name));
+                        }
+                        let (is_static, is_abstract, is_native) = self.method_modifiers(&member.modifiers, member.span)?;
+                        let return_type = self.resolve_type_info(&name_and_type.type_info, Some(&generic_context))?;
+                        let parameters = parameters.iter().map(|p| self.resolve_type_info(&p.type_info, Some(&generic_context)))
+                            .collect::<Result<Vec<TIRTypeInfo>, ASTtoTIRLowererError<'a>>>()?;
+                        self.check_generics(&return_type)?;
+                        parameters.iter().try_for_each(|p| self.check_generics(p))?;
+
+                        let method_ref_index = self.method_ref_pool.method_refs.len();
+                        let overloads = self.method_ref_pool.name_and_type_ref_index_to_method_ref_indexes.entry(key).or_insert_with(HashMap::new);
+                        if let Some(_) = overloads.insert(parameters.clone(), method_ref_index) {
+                            return Err(ASTtoTIRLowererError::MethodNameTaken(member.span, name_and_type.name));
+                        }
+                        self.method_ref_pool.method_refs.push(MethodRef {
+                            associated_type_ref_index: type_ref_index,
+                            return_type,
+                            name: name_and_type.name,
+                            parameters,
+                            index: method_index,
+                            index_in_all_members,
+                            visibility: member.visibility,
+                            // Interface methods never have a body
+                            is_abstract: is_abstract || is_interface,
+                            is_static,
+                            is_native
+                        });
+                        method_index += 1;
+                    }
+                    ASTMemberKind::Constructor { name, parameters, .. } if !is_interface => {
+                        if *name != type_decl.name {
+                            return Err(ASTtoTIRLowererError::ConstructorNameMismatch(member.span, name));
+                        }
+                        if let Some(modifier) = member.modifiers.first() {
+                            return Err(ASTtoTIRLowererError::ModifierNotCompatibleForConstructor(member.span, modifier.clone()));
+                        }
+
+                        let parameters = parameters.iter().map(|p| self.resolve_type_info(&p.type_info, Some(&generic_context)))
+                            .collect::<Result<Vec<TIRTypeInfo>, ASTtoTIRLowererError<'a>>>()?;
+                        parameters.iter().try_for_each(|p| self.check_generics(p))?;
+                        if constructors.contains(&parameters) {
+                            return Err(ASTtoTIRLowererError::DuplicateConstructor(member.span, name));
+                        }
+                        constructors.push(parameters);
+                        constructor_visibilities.push(member.visibility);
+                    }
+                    _ => {}
+                }
+            }
+            // Classes without constructors get one without parameters, as visible as the class
+            if constructors.is_empty() {
+                constructors.push(vec![]);
+                constructor_visibilities.push(type_decl.visibility);
+            }
+
+            match &mut self.type_ref_pool.type_refs[type_ref_index].kind {
+                TypeRefKind::Class(class_type_ref) => {
+                    class_type_ref.constructors = constructors;
+                    class_type_ref.constructor_visibilities = constructor_visibilities;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
     #[inline(always)]
     fn lower_ast_root(&self) -> Result<TIRRoot<'a>, ASTtoTIRLowererError<'a>> {
-        // TODO check for cycles in supers & generic super requirements
         // TODO check for generic super requirements having multiple super classes (only multiple interface impls are allowed)
 
         let mut types = vec![];
@@ -288,6 +838,9 @@ impl<'a> ASTtoTIRLowerer<'a> {
                     else {
                         None
                     };
+                    for impl_type_info in &class_type_ref.impls {
+                        self.check_generics(impl_type_info)?;
+                    }
 
                     // Lowering members
                     let mut lowered_members = vec![];
@@ -302,12 +855,92 @@ impl<'a> ASTtoTIRLowerer<'a> {
                         }
                         _ => unreachable!()
                     }
+                    if !lowered_members.iter().any(|m| matches!(m.kind, TIRMemberKind::Constructor { .. })) {
+                        lowered_members.push(self.implicit_constructor(type_ref_index, type_decl.span)?);
+                    }
 
                     types.push(TIRType {
                         type_ref_index,
                         kind: TIRTypeKind::Class {
                             members: lowered_members,
-                            super_class
+                            super_class,
+                            impls: class_type_ref.impls.clone()
+                        },
+                        span: type_decl.span.clone()
+                    })
+                }
+                TypeRefKind::Interface(interface_type_ref) => {
+                    for super_interface in &interface_type_ref.super_interfaces {
+                        self.check_generics(super_interface)?;
+                    }
+
+                    // Lowering members, interfaces only hold method signatures
+                    let mut lowered_members = vec![];
+                    match &type_decl.kind {
+                        ASTTypeKind::Interface { members, .. } => {
+                            for member in members {
+                                match &member.kind {
+                                    ASTMemberKind::Field { name_and_type, .. } =>
+                                        return Err(ASTtoTIRLowererError::FieldInInterface(member.span, name_and_type.name)),
+                                    ASTMemberKind::Method { name_and_type, block: Some(_), .. } =>
+                                        return Err(ASTtoTIRLowererError::MethodBodyInInterface(member.span, name_and_type.name)),
+                                    ASTMemberKind::Constructor { name, .. } =>
+                                        return Err(ASTtoTIRLowererError::ConstructorInInterface(member.span, name)),
+                                    _ => {}
+                                }
+                                lowered_members.push(self.lower_ast_member(member, &GenericContext {
+                                    type_ref_index,
+                                    name_to_generic_index: &type_ref.name_to_generic_index
+                                })?);
+                            }
+                        }
+                        _ => unreachable!()
+                    }
+
+                    types.push(TIRType {
+                        type_ref_index,
+                        kind: TIRTypeKind::Interface {
+                            members: lowered_members,
+                            super_interfaces: interface_type_ref.super_interfaces.clone()
+                        },
+                        span: type_decl.span.clone()
+                    })
+                }
+                TypeRefKind::Enum(_) => {
+                    let generic_context = GenericContext {
+                        type_ref_index,
+                        name_to_generic_index: &type_ref.name_to_generic_index
+                    };
+
+                    let mut lowered_variants = vec![];
+                    match &type_decl.kind {
+                        ASTTypeKind::Enum { variants } => {
+                            for variant in variants {
+                                let kind = match &variant.kind {
+                                    ASTVariantKind::Unit => TIRVariantKind::Unit,
+                                    ASTVariantKind::Tuple(fields) => TIRVariantKind::Tuple(
+                                        fields.iter().map(|f| self.resolve_type_info(f, Some(&generic_context)))
+                                            .collect::<Result<Vec<TIRTypeInfo>, ASTtoTIRLowererError<'a>>>()?
+                                    ),
+                                    ASTVariantKind::Struct(fields) => TIRVariantKind::Struct(
+                                        fields.iter().map(|f| self.lower_ast_name_and_type(f, &generic_context))
+                                            .collect::<Result<Vec<_>, ASTtoTIRLowererError<'a>>>()?
+                                    )
+                                };
+                                lowered_variants.push(TIRVariant {
+                                    span: variant.span,
+                                    name: variant.name,
+                                    kind
+                                });
+                            }
+                        }
+                        _ => unreachable!()
+                    }
+
+                    types.push(TIRType {
+                        type_ref_index,
+                        kind: TIRTypeKind::Enum {
+                            variants: lowered_variants
                         },
                         span: type_decl.span.clone()
                     })
@@ -315,31 +948,363 @@ impl<'a> ASTtoTIRLowerer<'a> {
             }
         }
 
+        self.check_interface_impls(&types)?;
+
         Ok(TIRRoot {
             types,
             span: self.ast_root.span.clone()
         })
     }
 
-    pub fn lower(mut self) -> Result<(TIRRoot<'a>, TypeRefPool<'a>), ASTtoTIRLowererError<'a>> {
+    // Methods of the type and its supers, with the generics of the type info substituted
+    fn collect_method_signatures(&self, type_info: &TIRTypeInfo, types: &HashMap<usize, &TIRType<'a>>, signatures: &mut Vec<MethodSignature<'a>>) {
+        let (type_ref_index, generics) = match &type_info.kind {
+            TIRTypeInfoKind::TypeRef { type_ref_index, generics, .. } => (*type_ref_index, generics),
+            _ => return
+        };
+        let (members, supers) = match &types[&type_ref_index].kind {
+            TIRTypeKind::Class { members, super_class, .. } => (members, super_class.iter().collect::<Vec<&TIRTypeInfo>>()),
+            TIRTypeKind::Interface { members, super_interfaces } => (members, super_interfaces.iter().collect()),
+            TIRTypeKind::Enum { .. } => return
+        };
+
+        for member in members {
+            if let TIRMemberKind::Method { name_and_type, parameters, .. } = &member.kind {
+                signatures.push(MethodSignature {
+                    name: name_and_type.name,
+                    parameters: parameters.iter().map(|p| self.type_ref_pool.substitute_generics(&p.type_info, type_ref_index, generics)).collect(),
+                    return_type: self.type_ref_pool.substitute_generics(&name_and_type.type_info, type_ref_index, generics)
+                });
+            }
+        }
+        for super_type in supers {
+            let super_type = self.type_ref_pool.substitute_generics(super_type, type_ref_index, generics);
+            self.collect_method_signatures(&super_type, types, signatures);
+        }
+    }
+
+    fn check_interface_impls(&self, types: &[TIRType<'a>]) -> Result<(), ASTtoTIRLowererError<'a>> {
+        let types_by_type_ref_index: HashMap<usize, &TIRType<'a>> = types.iter().map(|t| (t.type_ref_index, t)).collect();
+
+        for tir_type in types {
+            let type_ref = &self.type_ref_pool.type_refs[tir_type.type_ref_index];
+            let impls = match &type_ref.kind {
+                // Abstract classes can leave the implementation to their subclasses
+                TypeRefKind::Class(class_type_ref) if !class_type_ref.is_abstract => &class_type_ref.impls,
+                _ => continue
+            };
+
+            // The class as seen from inside, with its own generics
+            let this_type_info = TIRTypeInfo {
+                kind: TIRTypeInfoKind::TypeRef {
+                    type_ref_index: tir_type.type_ref_index,
+                    generics: (0..type_ref.generics.len()).map(|generic_index| TIRTypeInfo {
+                        kind: TIRTypeInfoKind::Generic {
+                            type_ref_index: tir_type.type_ref_index,
+                            generic_index,
+                            array_dim: 0
+                        },
+                        span: tir_type.span
+                    }).collect(),
+                    array_dim: 0
+                },
+                span: tir_type.span
+            };
+            let mut provided = vec![];
+            self.collect_method_signatures(&this_type_info, &types_by_type_ref_index, &mut provided);
+
+            for impl_type_info in impls {
+                let mut required = vec![];
+                self.collect_method_signatures(impl_type_info, &types_by_type_ref_index, &mut required);
+                for signature in &required {
+                    if !provided.iter().any(|p| p.name == signature.name && p.parameters == signature.parameters && p.return_type == signature.return_type) {
+                        let type_name = type_ref.full_path[type_ref.full_path.len() - 1];
+                        return Err(ASTtoTIRLowererError::MissingInterfaceMethod(impl_type_info.span, type_name, signature.name));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn lower(mut self) -> Result<(TIRRoot<'a>, TypeRefPool<'a>, FieldRefPool<'a>, MethodRefPool<'a>, Vec<ASTtoTIRLowererWarning>), ASTtoTIRLowererError<'a>> {
         self.register_types()?;
+        self.register_imports()?;
+        self.register_type_aliases()?;
         self.register_generics_boundless()?;
         self.register_supers()?;
         self.register_generic_bounds()?;
-       Ok((self.lower_ast_root()?, self.type_ref_pool))
+        self.check_cycles()?;
+        self.register_variants()?;
+        self.register_members()?;
+        let tir_root = self.lower_ast_root()?;
+        let warnings = self.unused_imports();
+        Ok((tir_root, self.type_ref_pool, self.field_ref_pool, self.method_ref_pool, warnings))
     }
 }
 
 #[derive(Debug)]
 pub enum ASTtoTIRLowererError<'a> {
-    DuplicateTypeDecl(Vec<&'a str>),
-    DuplicateGeneric(Vec<&'a str>, &'a str),
-    NoSuchType(Vec<&'a str>),
-    GenericOnGeneric(&'a str),
-    MismatchedGenerics(usize, usize, usize),
-    GenericOnPrimitive,
-    TypeMismatch,
-    ModifierNotCompatibleForClass(ASTModifier),
-    DuplicateModifier(ASTModifier),
-    InvalidSuperClass(&'a str)
-}
\ No newline at end of file
+    DuplicateTypeDecl(TokenSpan, Vec<&'a str>),
+    DuplicateGeneric(TokenSpan, Vec<&'a str>, &'a str),
+    NoSuchType(TokenSpan, Vec<&'a str>),
+    GenericOnGeneric(TokenSpan, &'a str),
+    MismatchedGenerics(TokenSpan, usize, usize, usize),
+    GenericOnPrimitive(TokenSpan),
+    TypeMismatch(TokenSpan),
+    ModifierNotCompatibleForClass(TokenSpan, ASTModifier),
+    ModifierNotCompatibleForInterface(TokenSpan, ASTModifier),
+    ModifierNotCompatibleForEnum(TokenSpan, ASTModifier),
+    DuplicateModifier(TokenSpan, ASTModifier),
+    InvalidSuperClass(TokenSpan, &'a str),
+    InvalidSuperInterface(TokenSpan, &'a str),
+    InvalidImpl(TokenSpan, &'a str),
+    // A single import has the name of a local type or of another single import
+    ImportConflict(TokenSpan, &'a str),
+    NoSuchModule(TokenSpan, Vec<&'a str>),
+    InaccessibleType(TokenSpan, Vec<&'a str>),
+    // A type, generic or member named like a primitive or a keyword
+    ReservedName(TokenSpan, &'a str),
+    InaccessibleField(TokenSpan, &'a str),
+    InaccessibleMethod(TokenSpan, &'a str),
+    InaccessibleConstructor(TokenSpan),
+    // The name is brought in by more than one glob import
+    AmbiguousImport(TokenSpan, &'a str),
+    // The string is the path around the cycle (e.g. "a::A -> a::B -> a::A")
+    CyclicInheritance(TokenSpan, String),
+    // Generics of the type that are requirements of each other (e.g. "T -> U -> T")
+    CyclicGenericBounds(TokenSpan, Vec<&'a str>, String),
+    // Type aliases that expand to each other (e.g. "A -> B -> A")
+    CyclicTypeAlias(TokenSpan, String),
+    // The type alias was used with the wrong number of generics
+    MismatchedAliasGenerics(TokenSpan, &'a str),
+    FieldInInterface(TokenSpan, &'a str),
+    MethodBodyInInterface(TokenSpan, &'a str),
+    MissingInterfaceMethod(TokenSpan, &'a str, &'a str),
+    DuplicateVariant(TokenSpan, &'a str),
+    DuplicateVariantField(TokenSpan, &'a str),
+    NoSuchVariant(TokenSpan, &'a str),
+    NoSuchVariantField(TokenSpan, &'a str),
+    MissingVariantField(TokenSpan, &'a str),
+    // The variant was constructed with the wrong shape (e.g. "E::Unit(1)" or "E::Tuple { x: 1 }")
+    MismatchedVariantFields(TokenSpan, &'a str),
+    InvalidStructLiteral(TokenSpan),
+    LiteralOutOfRange(TokenSpan, PrimitiveType),
+    DuplicateBinding(TokenSpan, &'a str),
+    // Alternatives of an or-pattern don't bind the same locals
+    MismatchedOrBindings(TokenSpan),
+    InvalidRangePattern(TokenSpan),
+    // Strings can't be compared without the standard library's String
+    StringPattern(TokenSpan),
+    BreakOutsideLoop(TokenSpan),
+    ContinueOutsideLoop(TokenSpan),
+    NoSuchLabel(TokenSpan, &'a str),
+    // "break value" in a "while" or "for" loop
+    BreakWithValueInNonLoop(TokenSpan),
+    ReturnOutsideMethod(TokenSpan),
+    // "return;" in a method returning a value, or "return value;" in a void method
+    MismatchedReturn(TokenSpan),
+    ConstructorNameMismatch(TokenSpan, &'a str),
+    ModifierNotCompatibleForConstructor(TokenSpan, ASTModifier),
+    // Two constructors with the same parameter types
+    DuplicateConstructor(TokenSpan, &'a str),
+    ConstructorInInterface(TokenSpan, &'a str),
+    // Only classes can be constructed with "new"
+    InvalidNew(TokenSpan),
+    AbstractInstantiation(TokenSpan),
+    NoSuchConstructor(TokenSpan),
+    // "super(...)" anywhere but as the first statement of a constructor
+    MisplacedSuperCall(TokenSpan),
+    NoSuperClass(TokenSpan),
+    // The super class has no constructor without parameters, so it has to be called explicitly
+    MissingSuperCall(TokenSpan),
+    // "this", "super" or an instance member used in a static method or a static field initializer
+    ThisInStaticContext(TokenSpan),
+    NoSuchName(TokenSpan, &'a str),
+    // A field and a method, or two methods with the same parameters, share a name
+    FieldNameTaken(TokenSpan, &'a str),
+    MethodNameTaken(TokenSpan, &'a str),
+    ModifierNotCompatibleForField(TokenSpan, ASTModifier),
+    NoSuchField(TokenSpan, &'a str),
+    NoSuchMethod(TokenSpan, &'a str),
+    // Methods can only be called, they aren't values
+    MethodWithoutCall(TokenSpan, &'a str),
+    // "Class::member" with an instance member
+    InstanceMemberAccessedStatically(TokenSpan, &'a str),
+    // "value.member" with a static member
+    StaticMemberAccessedOnInstance(TokenSpan, &'a str),
+    // "::" used on something that isn't a type
+    InvalidStaticAccess(TokenSpan)
+}
+impl<'a> ASTtoTIRLowererError<'a> {
+    pub fn span(&self) -> TokenSpan {
+        match self {
+            ASTtoTIRLowererError::DuplicateTypeDecl(span, _) |
+            ASTtoTIRLowererError::DuplicateGeneric(span, _, _) |
+            ASTtoTIRLowererError::NoSuchType(span, _) |
+            ASTtoTIRLowererError::GenericOnGeneric(span, _) |
+            ASTtoTIRLowererError::MismatchedGenerics(span, _, _, _) |
+            ASTtoTIRLowererError::GenericOnPrimitive(span) |
+            ASTtoTIRLowererError::TypeMismatch(span) |
+            ASTtoTIRLowererError::ModifierNotCompatibleForClass(span, _) |
+            ASTtoTIRLowererError::ModifierNotCompatibleForInterface(span, _) |
+            ASTtoTIRLowererError::ModifierNotCompatibleForEnum(span, _) |
+            ASTtoTIRLowererError::DuplicateModifier(span, _) |
+            ASTtoTIRLowererError::InvalidSuperClass(span, _) |
+            ASTtoTIRLowererError::InvalidSuperInterface(span, _) |
+            ASTtoTIRLowererError::InvalidImpl(span, _) |
+            ASTtoTIRLowererError::ImportConflict(span, _) |
+            ASTtoTIRLowererError::NoSuchModule(span, _) |
+            ASTtoTIRLowererError::InaccessibleType(span, _) |
+            ASTtoTIRLowererError::ReservedName(span, _) |
+            ASTtoTIRLowererError::InaccessibleField(span, _) |
+            ASTtoTIRLowererError::InaccessibleMethod(span, _) |
+            ASTtoTIRLowererError::InaccessibleConstructor(span) |
+            ASTtoTIRLowererError::AmbiguousImport(span, _) |
+            ASTtoTIRLowererError::CyclicInheritance(span, _) |
+            ASTtoTIRLowererError::CyclicGenericBounds(span, _, _) |
+            ASTtoTIRLowererError::CyclicTypeAlias(span, _) |
+            ASTtoTIRLowererError::MismatchedAliasGenerics(span, _) |
+            ASTtoTIRLowererError::FieldInInterface(span, _) |
+            ASTtoTIRLowererError::MethodBodyInInterface(span, _) |
+            ASTtoTIRLowererError::MissingInterfaceMethod(span, _, _) |
+            ASTtoTIRLowererError::DuplicateVariant(span, _) |
+            ASTtoTIRLowererError::DuplicateVariantField(span, _) |
+            ASTtoTIRLowererError::NoSuchVariant(span, _) |
+            ASTtoTIRLowererError::NoSuchVariantField(span, _) |
+            ASTtoTIRLowererError::MissingVariantField(span, _) |
+            ASTtoTIRLowererError::MismatchedVariantFields(span, _) |
+            ASTtoTIRLowererError::InvalidStructLiteral(span) |
+            ASTtoTIRLowererError::LiteralOutOfRange(span, _) |
+            ASTtoTIRLowererError::DuplicateBinding(span, _) |
+            ASTtoTIRLowererError::MismatchedOrBindings(span) |
+            ASTtoTIRLowererError::InvalidRangePattern(span) |
+            ASTtoTIRLowererError::StringPattern(span) |
+            ASTtoTIRLowererError::BreakOutsideLoop(span) |
+            ASTtoTIRLowererError::ContinueOutsideLoop(span) |
+            ASTtoTIRLowererError::NoSuchLabel(span, _) |
+            ASTtoTIRLowererError::BreakWithValueInNonLoop(span) |
+            ASTtoTIRLowererError::ReturnOutsideMethod(span) |
+            ASTtoTIRLowererError::MismatchedReturn(span) |
+            ASTtoTIRLowererError::ConstructorNameMismatch(span, _) |
+            ASTtoTIRLowererError::ModifierNotCompatibleForConstructor(span, _) |
+            ASTtoTIRLowererError::DuplicateConstructor(span, _) |
+            ASTtoTIRLowererError::ConstructorInInterface(span, _) |
+            ASTtoTIRLowererError::InvalidNew(span) |
+            ASTtoTIRLowererError::AbstractInstantiation(span) |
+            ASTtoTIRLowererError::NoSuchConstructor(span) |
+            ASTtoTIRLowererError::MisplacedSuperCall(span) |
+            ASTtoTIRLowererError::NoSuperClass(span) |
+            ASTtoTIRLowererError::MissingSuperCall(span) |
+            ASTtoTIRLowererError::ThisInStaticContext(span) |
+            ASTtoTIRLowererError::NoSuchName(span, _) |
+            ASTtoTIRLowererError::FieldNameTaken(span, _) |
+            ASTtoTIRLowererError::MethodNameTaken(span, _) |
+            ASTtoTIRLowererError::ModifierNotCompatibleForField(span, _) |
+            ASTtoTIRLowererError::NoSuchField(span, _) |
+            ASTtoTIRLowererError::NoSuchMethod(span, _) |
+            ASTtoTIRLowererError::MethodWithoutCall(span, _) |
+            ASTtoTIRLowererError::InstanceMemberAccessedStatically(span, _) |
+            ASTtoTIRLowererError::StaticMemberAccessedOnInstance(span, _) |
+            ASTtoTIRLowererError::InvalidStaticAccess(span) => *span
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ASTtoTIRLowererWarning {
+    UnusedImport(TokenSpan)
+}
+
+impl ASTtoTIRLowererWarning {
+    pub fn span(&self) -> TokenSpan {
+        match self {
+            ASTtoTIRLowererWarning::UnusedImport(span) => *span
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tir::ast_lowerer::ASTtoTIRLowererError;
+    use crate::testing::{lower, lowering_error, span_text};
+
+    #[test]
+    fn classes_implement_every_interface_method() {
+        lower("mod a; inter Shape { fn area() -> u64; } inter Square: Shape { fn side() -> u64; } class Tile impl Square { fn area() -> u64 { 1 } fn side() -> u64 { 1 } }").unwrap();
+        let err = lowering_error("mod a; inter Shape { fn area() -> u64; } inter Square: Shape { fn side() -> u64; } class Tile impl Square { fn side() -> u64 { 1 } }");
+        assert!(matches!(err, ASTtoTIRLowererError::MissingInterfaceMethod(_, "Tile", "area")), "{:?}", err);
+    }
+
+    #[test]
+    fn interfaces_only_declare_methods() {
+        assert!(matches!(lowering_error("mod a; inter Shape { area: u64; }"), ASTtoTIRLowererError::FieldInInterface(_, "area")));
+        assert!(matches!(lowering_error("mod a; inter Shape { fn area() -> u64 { 1 } }"), ASTtoTIRLowererError::MethodBodyInInterface(_, "area")));
+    }
+
+    #[test]
+    fn enum_variants_and_their_fields_are_unique() {
+        lower("mod a; enum Event { Tick, Damage(u32), Chat { from: u64, text: u64 } }").unwrap();
+        assert!(matches!(lowering_error("mod a; enum Event { Tick, Tick }"), ASTtoTIRLowererError::DuplicateVariant(_, "Tick")));
+        assert!(matches!(lowering_error("mod a; enum Event { Chat { from: u64, from: u64 } }"), ASTtoTIRLowererError::DuplicateVariantField(_, "from")));
+    }
+
+    #[test]
+    fn inheritance_cycles_are_reported_with_their_path() {
+        let err = lowering_error("mod a; class A: B { } class B: C { } class C: A { }");
+        assert!(matches!(err, ASTtoTIRLowererError::CyclicInheritance(_, ref path) if path == "a::A -> a::B -> a::C -> a::A"), "{:?}", err);
+        let err = lowering_error("mod a; inter I: I { }");
+        assert!(matches!(err, ASTtoTIRLowererError::CyclicInheritance(_, ref path) if path == "a::I -> a::I"), "{:?}", err);
+        // Diamonds are not cycles
+        lower("mod a; inter Top { } inter Left: Top { } inter Right: Top { } class Bottom impl Left, Right { }").unwrap();
+    }
+
+    #[test]
+    fn generic_bound_cycles_are_reported_with_their_path() {
+        let err = lowering_error("mod a; class A<T: U, U: T> { }");
+        assert!(matches!(err, ASTtoTIRLowererError::CyclicGenericBounds(_, _, ref path) if path == "T -> U -> T"), "{:?}", err);
+        lower("mod a; class Wrapper<T> { } class A<T, U: Wrapper<T>> { }").unwrap();
+    }
+
+    #[test]
+    fn primitive_names_are_reserved() {
+        for (source, name) in [
+            ("mod a; class u8 { }", "u8"),
+            ("mod a; class A<u64> { }", "u64"),
+            ("mod a; class A { bool: u64; }", "bool"),
+            ("mod a; class A { fn f32() { } }", "f32"),
+            ("mod a; type i8 = u64;", "i8"),
+            ("mod a; type Pair<char> = u64;", "char")
+        ] {
+            let err = lowering_error(source);
+            assert!(matches!(err, ASTtoTIRLowererError::ReservedName(_, reserved) if reserved == name), "{}: {:?}", source, err);
+        }
+        // The span is the whole declaration
+        assert!(matches!(lowering_error("mod a; class A { } class u8 { }"), ASTtoTIRLowererError::ReservedName(span, _) if span.base == 7 && span.len == 4));
+    }
+
+    #[test]
+    fn declaration_errors_point_at_what_is_wrong() {
+        for (source, text) in [
+            ("mod a; class A { } class A { }", "class A { }"),
+            ("mod a; class A<T, T> { }", "T"),
+            ("mod a; class A { x: Missing; }", "Missing"),
+            ("mod a; class W<T> { } class A { x: W<u8, u8>; }", "W < u8 , u8 >"),
+            ("mod a; class B { } class W<T: B> { } class A { x: W<A>; }", "A"),
+            ("mod a; class A { x: u8<A>; }", "u8 < A >"),
+            ("mod a; static class A { }", "static class A { }"),
+            ("mod a; enum E { } class A: E { }", "E"),
+            ("mod a; class A: B { } class B: A { }", "class A : B { }"),
+            ("mod a; class A<T: U, U: T> { }", "T : U"),
+            ("mod a; class A { fn f() { } f: u64; }", "f : u64 ;"),
+            ("mod a; class A { static static x: u64; }", "static static x : u64 ;"),
+            ("mod a; class A { B() { } }", "B ( ) { }"),
+            ("mod a; inter I { fn f(); } class A impl I { }", "I"),
+            ("mod a; enum E { X, X }", "X")
+        ] {
+            let err = lowering_error(source);
+            assert_eq!(span_text(source, err.span()), text, "{:?}", err);
+        }
+    }
+}
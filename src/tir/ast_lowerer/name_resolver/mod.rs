@@ -0,0 +1,275 @@
+use crate::tir::ast_lowerer::{ASTtoTIRLowerer, GenericContext, BodyContext, ASTtoTIRLowererError};
+use crate::ast::{ASTExpr, ASTExprKind};
+use crate::reporting::TokenSpan;
+use crate::type_ref_pool::type_ref_index_of;
+use crate::tir::{TIRExpr, TIRExprKind};
+
+// Identifiers are resolved to local slots, field refs and method refs while lowering.
+// Members of an expression can only be resolved when the class of the expression is already known,
+// the others are left as names until expressions are typed. Calls to overloaded methods are resolved to every
+// overload that takes as many arguments, the type checker picks between them.
+
+impl<'a> ASTtoTIRLowerer<'a> {
+    fn find_field(&self, type_ref_index: usize, name: &str) -> Option<usize> {
+        self.field_ref_pool.find_field(&self.type_ref_pool.type_hierarchy(type_ref_index), name)
+    }
+
+    fn find_methods(&self, type_ref_index: usize, name: &str) -> Vec<usize> {
+        self.method_ref_pool.find_methods(&self.type_ref_pool.type_hierarchy(type_ref_index), name)
+    }
+
+    // Members with the default visibility can be used in their module, "priv" ones only in the type declaring them
+    fn check_field_access(&self, field_ref_index: usize, name: &'a str, span: TokenSpan, generic_context: &GenericContext<'a, '_>) -> Result<usize, ASTtoTIRLowererError<'a>> {
+        let field_ref = &self.field_ref_pool.field_refs[field_ref_index];
+        if self.type_ref_pool.is_accessible(field_ref.visibility, field_ref.associated_type_ref_index, generic_context.type_ref_index) {
+            Ok(field_ref_index)
+        } else {
+            Err(ASTtoTIRLowererError::InaccessibleField(span, name))
+        }
+    }
+
+    // Methods with that name that can be called with that many arguments, the static ones or the instance ones only if "is_static" is given
+    fn resolve_methods(&self, type_ref_index: usize, name: &'a str, arg_count: usize, is_static: Option<bool>, span: TokenSpan, generic_context: &GenericContext<'a, '_>) -> Result<Vec<usize>, ASTtoTIRLowererError<'a>> {
+        let method_refs = &self.method_ref_pool.method_refs;
+        let candidates: Vec<usize> = self.find_methods(type_ref_index, name).into_iter()
+            .filter(|m| method_refs[*m].parameters.len() == arg_count)
+            .collect();
+        if candidates.is_empty() {
+            return Err(ASTtoTIRLowererError::NoSuchMethod(span, name));
+        }
+        let candidates: Vec<usize> = candidates.into_iter()
+            .filter(|m| self.type_ref_pool.is_accessible(method_refs[*m].visibility, method_refs[*m].associated_type_ref_index, generic_context.type_ref_index))
+            .collect();
+        if candidates.is_empty() {
+            return Err(ASTtoTIRLowererError::InaccessibleMethod(span, name));
+        }
+        let candidates: Vec<usize> = candidates.into_iter()
+            .filter(|m| is_static.map_or(true, |is_static| method_refs[*m].is_static == is_static))
+            .collect();
+        match is_static {
+            _ if !candidates.is_empty() => Ok(candidates),
+            Some(true) => Err(ASTtoTIRLowererError::InstanceMemberAccessedStatically(span, name)),
+            Some(false) => Err(ASTtoTIRLowererError::StaticMemberAccessedOnInstance(span, name)),
+            None => unreachable!()
+        }
+    }
+
+    // Class of the expression when it is known without typing it
+    fn known_type_ref_index(&self, expr: &TIRExpr<'a>, generic_context: &GenericContext<'a, '_>, body_context: &BodyContext<'a>) -> Option<usize> {
+        match &expr.kind {
+            TIRExprKind::This => Some(generic_context.type_ref_index),
+            TIRExprKind::Super => self.super_class_type_ref_index(generic_context.type_ref_index),
+            TIRExprKind::TypeAccess(type_ref_index) => Some(*type_ref_index),
+            TIRExprKind::LocalAccess(slot) => body_context.slot_type(*slot).and_then(type_ref_index_of),
+            TIRExprKind::FieldAccess(_, field_ref_index) | TIRExprKind::StaticFieldAccess(field_ref_index) =>
+                type_ref_index_of(&self.field_ref_pool.field_refs[*field_ref_index].type_info),
+            TIRExprKind::MethodCall(_, method_ref_index, _) | TIRExprKind::StaticMethodCall(method_ref_index, _) =>
+                type_ref_index_of(&self.method_ref_pool.method_refs[*method_ref_index].return_type),
+            TIRExprKind::New(type_info, _, _) | TIRExprKind::OverloadedNew(type_info, _, _) => type_ref_index_of(type_info),
+            _ => None
+        }
+    }
+
+    // "this" when the member is an instance member, after checking that there is one
+    fn implicit_this(&self, span: TokenSpan, body_context: &BodyContext<'a>) -> Result<Box<TIRExpr<'a>>, ASTtoTIRLowererError<'a>> {
+        if body_context.is_static {
+            return Err(ASTtoTIRLowererError::ThisInStaticContext(span));
+        }
+        Ok(Box::new(TIRExpr {
+            kind: TIRExprKind::This,
+            span,
+            type_info: None
+        }))
+    }
+
+    // A bare identifier is a local or a parameter, then a member of the class ("this." or "Class::" is implied), then a type
+    pub fn lower_ast_ident(&self, name: &'a str, span: TokenSpan, generic_context: &GenericContext<'a, '_>, body_context: &BodyContext<'a>) -> Result<TIRExprKind<'a>, ASTtoTIRLowererError<'a>> {
+        if let Some(slot) = body_context.local(name) {
+            return Ok(TIRExprKind::LocalAccess(slot));
+        }
+        if let Some(field_ref_index) = self.find_field(generic_context.type_ref_index, name) {
+            let field_ref_index = self.check_field_access(field_ref_index, name, span, generic_context)?;
+            return Ok(if self.field_ref_pool.field_refs[field_ref_index].is_static {
+                TIRExprKind::StaticFieldAccess(field_ref_index)
+            } else {
+                TIRExprKind::FieldAccess(self.implicit_this(span, body_context)?, field_ref_index)
+            });
+        }
+        if !self.find_methods(generic_context.type_ref_index, name).is_empty() {
+            return Err(ASTtoTIRLowererError::MethodWithoutCall(span, name));
+        }
+        match self.resolve_type_ref_index(&[name], span) {
+            Ok(type_ref_index) => Ok(TIRExprKind::TypeAccess(type_ref_index)),
+            Err(_) => Err(ASTtoTIRLowererError::NoSuchName(span, name))
+        }
+    }
+
+    // "expr.name"
+    pub fn lower_ast_member_access(&self, expr: &ASTExpr<'a>, name: &'a str, span: TokenSpan, generic_context: &GenericContext<'a, '_>, body_context: &mut BodyContext<'a>) -> Result<TIRExprKind<'a>, ASTtoTIRLowererError<'a>> {
+        let expr = self.lower_ast_expr(expr, generic_context, body_context)?;
+        if let TIRExprKind::TypeAccess(_) = expr.kind {
+            return Err(ASTtoTIRLowererError::InstanceMemberAccessedStatically(span, name));
+        }
+        let type_ref_index = match self.known_type_ref_index(&expr, generic_context, body_context) {
+            Some(type_ref_index) => type_ref_index,
+            None => return Ok(TIRExprKind::MemberAccess(Box::new(expr), name))
+        };
+        match self.find_field(type_ref_index, name).map(|f| self.check_field_access(f, name, span, generic_context)).transpose()? {
+            Some(field_ref_index) if self.field_ref_pool.field_refs[field_ref_index].is_static =>
+                Err(ASTtoTIRLowererError::StaticMemberAccessedOnInstance(span, name)),
+            Some(field_ref_index) => Ok(TIRExprKind::FieldAccess(Box::new(expr), field_ref_index)),
+            None if !self.find_methods(type_ref_index, name).is_empty() => Err(ASTtoTIRLowererError::MethodWithoutCall(span, name)),
+            None => Err(ASTtoTIRLowererError::NoSuchField(span, name))
+        }
+    }
+
+    // "Type::name", enum variants are handled before
+    pub fn lower_ast_static_access(&self, expr: &ASTExpr<'a>, name: &'a str, span: TokenSpan, generic_context: &GenericContext<'a, '_>, body_context: &mut BodyContext<'a>) -> Result<TIRExprKind<'a>, ASTtoTIRLowererError<'a>> {
+        let type_ref_index = match self.lower_ast_expr(expr, generic_context, body_context)?.kind {
+            TIRExprKind::TypeAccess(type_ref_index) => type_ref_index,
+            _ => return Err(ASTtoTIRLowererError::InvalidStaticAccess(span))
+        };
+        match self.find_field(type_ref_index, name).map(|f| self.check_field_access(f, name, span, generic_context)).transpose()? {
+            Some(field_ref_index) if self.field_ref_pool.field_refs[field_ref_index].is_static => Ok(TIRExprKind::StaticFieldAccess(field_ref_index)),
+            Some(_) => Err(ASTtoTIRLowererError::InstanceMemberAccessedStatically(span, name)),
+            None if !self.find_methods(type_ref_index, name).is_empty() => Err(ASTtoTIRLowererError::MethodWithoutCall(span, name)),
+            None => Err(ASTtoTIRLowererError::NoSuchField(span, name))
+        }
+    }
+
+    // "name(args)", "expr.name(args)" and "Type::name(args)", enum variants are handled before
+    pub fn lower_ast_call(&self, callee: &ASTExpr<'a>, args: &[ASTExpr<'a>], generic_context: &GenericContext<'a, '_>, body_context: &mut BodyContext<'a>) -> Result<TIRExprKind<'a>, ASTtoTIRLowererError<'a>> {
+        let span = callee.span;
+        // The candidate method ref indexes, and what they are called on if one of them is an instance method
+        let (mut method_ref_indexes, on) = match &callee.kind {
+            ASTExprKind::Ident(name) if body_context.local(name).is_none() => {
+                let method_ref_indexes = self.resolve_methods(generic_context.type_ref_index, name, args.len(), None, span, generic_context)?;
+                let method_refs = &self.method_ref_pool.method_refs;
+                // Only the static overloads can be called without "this"
+                let static_method_ref_indexes: Vec<usize> = method_ref_indexes.iter().copied().filter(|m| method_refs[*m].is_static).collect();
+                if static_method_ref_indexes.len() == method_ref_indexes.len() {
+                    (method_ref_indexes, None)
+                } else if body_context.is_static && !static_method_ref_indexes.is_empty() {
+                    (static_method_ref_indexes, None)
+                } else {
+                    (method_ref_indexes, Some(self.implicit_this(span, body_context)?))
+                }
+            }
+            ASTExprKind::MemberAccess(expr, name) => {
+                let expr = self.lower_ast_expr(expr, generic_context, body_context)?;
+                if let TIRExprKind::TypeAccess(_) = expr.kind {
+                    return Err(ASTtoTIRLowererError::InstanceMemberAccessedStatically(span, name));
+                }
+                let method_ref_indexes = match self.known_type_ref_index(&expr, generic_context, body_context) {
+                    Some(type_ref_index) => self.resolve_methods(type_ref_index, name, args.len(), Some(false), span, generic_context)?,
+                    None => {
+                        let args = self.lower_ast_args(args, generic_context, body_context)?;
+                        let callee = TIRExpr {
+                            kind: TIRExprKind::MemberAccess(Box::new(expr), name),
+                            span,
+                            type_info: None
+                        };
+                        return Ok(TIRExprKind::Call(Box::new(callee), args));
+                    }
+                };
+                (method_ref_indexes, Some(Box::new(expr)))
+            }
+            ASTExprKind::StaticAccess(expr, name) => {
+                let type_ref_index = match self.lower_ast_expr(expr, generic_context, body_context)?.kind {
+                    TIRExprKind::TypeAccess(type_ref_index) => type_ref_index,
+                    _ => return Err(ASTtoTIRLowererError::InvalidStaticAccess(span))
+                };
+                (self.resolve_methods(type_ref_index, name, args.len(), Some(true), span, generic_context)?, None)
+            }
+            _ => {
+                let callee = self.lower_ast_expr(callee, generic_context, body_context)?;
+                let args = self.lower_ast_args(args, generic_context, body_context)?;
+                return Ok(TIRExprKind::Call(Box::new(callee), args));
+            }
+        };
+
+        let args = self.lower_ast_args(args, generic_context, body_context)?;
+        if method_ref_indexes.len() > 1 {
+            return Ok(TIRExprKind::OverloadedCall(on, method_ref_indexes, args));
+        }
+        let method_ref_index = method_ref_indexes.pop().unwrap();
+        Ok(match on {
+            Some(on) if !self.method_ref_pool.method_refs[method_ref_index].is_static => TIRExprKind::MethodCall(on, method_ref_index, args),
+            _ => TIRExprKind::StaticMethodCall(method_ref_index, args)
+        })
+    }
+
+    pub fn lower_ast_args(&self, args: &[ASTExpr<'a>], generic_context: &GenericContext<'a, '_>, body_context: &mut BodyContext<'a>) -> Result<Vec<TIRExpr<'a>>, ASTtoTIRLowererError<'a>> {
+        args.iter().map(|arg| self.lower_ast_expr(arg, generic_context, body_context))
+            .collect::<Result<Vec<TIRExpr<'a>>, ASTtoTIRLowererError<'a>>>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tir::ast_lowerer::ASTtoTIRLowererError;
+    use crate::tir::{TIRMemberKind, TIRExprKind, TIRStatementKind, TIRTypeKind};
+    use crate::testing::{lower, lowering_error};
+
+    #[test]
+    fn bare_identifiers_resolve_to_locals_before_members() {
+        let (tir, ..) = lower("mod a; class A { count: u64; fn f(count: u64) -> u64 { count } fn g() -> u64 { count } }").unwrap();
+        let members = match &tir.types[0].kind {
+            TIRTypeKind::Class { members, .. } => members,
+            _ => unreachable!()
+        };
+        let ending_kind = |member_index: usize| match &members[member_index].kind {
+            TIRMemberKind::Method { block: Some(block), .. } => match &block.statements[0].kind {
+                TIRStatementKind::Expression(expr) => expr.kind.clone(),
+                _ => unreachable!()
+            },
+            _ => unreachable!()
+        };
+        // The parameter comes after "this"
+        assert!(matches!(ending_kind(1), TIRExprKind::LocalAccess(1)));
+        assert!(matches!(ending_kind(2), TIRExprKind::FieldAccess(on, 0) if matches!(on.kind, TIRExprKind::This)));
+    }
+
+    #[test]
+    fn this_is_not_available_in_static_methods() {
+        assert!(matches!(lowering_error("mod a; class A { static fn f() { this; } }"), ASTtoTIRLowererError::ThisInStaticContext(_)));
+        assert!(matches!(lowering_error("mod a; class A { count: u64; static fn f() -> u64 { count } }"), ASTtoTIRLowererError::ThisInStaticContext(_)));
+        assert!(matches!(lowering_error("mod a; class A { static fn f() -> u64 { missing } }"), ASTtoTIRLowererError::NoSuchName(_, "missing")));
+    }
+
+    #[test]
+    fn locals_are_scoped_to_their_block() {
+        lower("mod a; class A { fn f() -> u64 { let x = 1; { let x = true; } x } }").unwrap();
+        let err = lowering_error("mod a; class A { fn f() -> u64 { { let y = 1; } y } }");
+        assert!(matches!(err, ASTtoTIRLowererError::NoSuchName(_, "y")), "{:?}", err);
+    }
+
+    #[test]
+    fn members_are_resolved_through_the_class_hierarchy() {
+        let source = "mod a; class Base { count: u64; fn get() -> u64 { count } static fn make() -> u64 { 1 } } class A: Base { fn f() -> u64 { BODY } }";
+        for body in ["this.count", "this.get()", "get()", "Base::make()", "count + super.get()"] {
+            lower(&source.replace("BODY", body)).unwrap();
+        }
+        for (body, expected) in [
+            ("this.missing", "NoSuchField"),
+            ("this.missing()", "NoSuchMethod"),
+            ("this.get(1)", "NoSuchMethod"),
+            ("Base::get()", "InstanceMemberAccessedStatically"),
+            ("this.make()", "StaticMemberAccessedOnInstance")
+        ] {
+            let err = format!("{:?}", lowering_error(&source.replace("BODY", body)));
+            assert!(err.starts_with(expected), "{}: {}", body, err);
+        }
+    }
+
+    #[test]
+    fn private_members_are_only_visible_in_their_type() {
+        let source = "mod a; class B { priv secret: u64; priv B(secret: u64) { } pub B() { } priv fn hidden() { } fn shown() { this.hidden(); secret; } } class A { fn f(b: B) { BODY } }";
+        lower(&source.replace("BODY", "b.shown(); new B();")).unwrap();
+        for (body, expected) in [("b.secret;", "InaccessibleField"), ("b.hidden();", "InaccessibleMethod"), ("new B(1);", "InaccessibleConstructor")] {
+            let err = format!("{:?}", lowering_error(&source.replace("BODY", body)));
+            assert!(err.starts_with(expected), "{}: {}", body, err);
+        }
+    }
+}
+
@@ -0,0 +1,201 @@
+use crate::tir::ast_lowerer::{ASTtoTIRLowerer, GenericContext, BodyContext, ASTtoTIRLowererError};
+use crate::ast::{ASTExpr, ASTExprKind, ASTOperator, ASTPattern, ASTPatternKind, ASTPath, ASTMatchArm};
+use crate::reporting::TokenSpan;
+use crate::type_ref_pool::{TypeRefKind, EnumVariantRefKind};
+use crate::tir::{TIRExprKind, TIRPattern, TIRPatternKind, TIRPatternLiteral, TIRMatchArm};
+use std::collections::HashMap;
+
+impl<'a> ASTtoTIRLowerer<'a> {
+    fn lower_pattern_literal(&self, expression: &ASTExpr<'a>) -> Result<TIRPatternLiteral, ASTtoTIRLowererError<'a>> {
+        Ok(match &expression.kind {
+            ASTExprKind::Num(value, suffix) => {
+                self.check_integer_literal(*value, *suffix, false, expression.span)?;
+                TIRPatternLiteral::Integer(*value as i128, *suffix)
+            }
+            ASTExprKind::Float(value, suffix) => {
+                self.check_float_literal(*value, *suffix, expression.span)?;
+                TIRPatternLiteral::Float(*value, *suffix)
+            }
+            ASTExprKind::PreOp(ASTOperator::Minus, expr) => match expr.kind {
+                ASTExprKind::Num(value, suffix) => {
+                    self.check_integer_literal(value, suffix, true, expression.span)?;
+                    TIRPatternLiteral::Integer(-(value as i128), suffix)
+                }
+                ASTExprKind::Float(value, suffix) => {
+                    self.check_float_literal(value, suffix, expression.span)?;
+                    TIRPatternLiteral::Float(-value, suffix)
+                }
+                _ => unreachable!()
+            },
+            ASTExprKind::CharLiteral(character) => TIRPatternLiteral::Character(*character),
+            ASTExprKind::StringLiteral(_) => return Err(ASTtoTIRLowererError::StringPattern(expression.span)),
+            ASTExprKind::Boolean(boolean) => TIRPatternLiteral::Boolean(*boolean),
+            ASTExprKind::Null => TIRPatternLiteral::Null,
+            // The parser only produces literals in patterns
+            _ => unreachable!()
+        })
+    }
+
+    // Type ref index and variant index of "E::A" in a pattern
+    fn resolve_variant_path(&self, path: &ASTPath<'a>) -> Result<(usize, usize), ASTtoTIRLowererError<'a>> {
+        let (variant_name, type_path) = path.elements.split_last().unwrap();
+        let type_ref_index = self.resolve_type_ref_index(type_path, path.span)?;
+        match &self.type_ref_pool.type_refs[type_ref_index].kind {
+            TypeRefKind::Enum(enum_type_ref) => match enum_type_ref.name_to_variant_index.get(variant_name) {
+                Some(variant_index) => Ok((type_ref_index, *variant_index)),
+                None => Err(ASTtoTIRLowererError::NoSuchVariant(path.span, variant_name))
+            },
+            _ => Err(ASTtoTIRLowererError::NoSuchVariant(path.span, variant_name))
+        }
+    }
+
+    // Alternatives of an or-pattern bind their locals to the same slots
+    fn bind(&self, name: &'a str, span: TokenSpan, bindings: &mut Vec<(&'a str, usize)>, slots: &mut HashMap<&'a str, usize>, body_context: &mut BodyContext<'a>) -> Result<usize, ASTtoTIRLowererError<'a>> {
+        if bindings.iter().any(|(binding, _)| *binding == name) {
+            return Err(ASTtoTIRLowererError::DuplicateBinding(span, name));
+        }
+        let slot = *slots.entry(name).or_insert_with(|| body_context.allocate_local(None));
+        bindings.push((name, slot));
+        Ok(slot)
+    }
+
+    fn lower_ast_pattern(&self, pattern: &ASTPattern<'a>, generic_context: &GenericContext<'a, '_>, body_context: &mut BodyContext<'a>, bindings: &mut Vec<(&'a str, usize)>, slots: &mut HashMap<&'a str, usize>) -> Result<TIRPattern, ASTtoTIRLowererError<'a>> {
+        Ok(TIRPattern {
+            kind: match &pattern.kind {
+                ASTPatternKind::Wildcard => TIRPatternKind::Wildcard,
+                ASTPatternKind::Binding(name) => TIRPatternKind::Binding(self.bind(name, pattern.span, bindings, slots, body_context)?),
+                ASTPatternKind::Literal(literal) => TIRPatternKind::Literal(self.lower_pattern_literal(literal)?),
+                ASTPatternKind::Range(start, end, inclusive) => {
+                    let start = self.lower_pattern_literal(start)?;
+                    let end = self.lower_pattern_literal(end)?;
+                    // Only integers and characters can be ranged over, and the range can't be empty
+                    let valid = match (&start, &end) {
+                        (TIRPatternLiteral::Integer(s, _), TIRPatternLiteral::Integer(e, _)) => if *inclusive { s <= e } else { s < e },
+                        (TIRPatternLiteral::Character(s), TIRPatternLiteral::Character(e)) => if *inclusive { s <= e } else { s < e },
+                        _ => false
+                    };
+                    if !valid {
+                        return Err(ASTtoTIRLowererError::InvalidRangePattern(pattern.span));
+                    }
+                    TIRPatternKind::Range(start, end, *inclusive)
+                }
+                ASTPatternKind::UnitVariant(path) => {
+                    let (type_ref_index, variant_index) = self.resolve_variant_path(path)?;
+                    let variant = self.enum_variant(type_ref_index, variant_index);
+                    match variant.kind {
+                        EnumVariantRefKind::Unit => TIRPatternKind::EnumVariant(type_ref_index, variant_index, vec![]),
+                        _ => return Err(ASTtoTIRLowererError::MismatchedVariantFields(pattern.span, variant.name))
+                    }
+                }
+                ASTPatternKind::TupleVariant(path, fields) => {
+                    let (type_ref_index, variant_index) = self.resolve_variant_path(path)?;
+                    let variant = self.enum_variant(type_ref_index, variant_index);
+                    match variant.kind {
+                        EnumVariantRefKind::Tuple if variant.fields.len() == fields.len() => TIRPatternKind::EnumVariant(
+                            type_ref_index,
+                            variant_index,
+                            fields.iter().map(|field| self.lower_ast_pattern(field, generic_context, body_context, bindings, slots))
+                                .collect::<Result<Vec<TIRPattern>, ASTtoTIRLowererError<'a>>>()?
+                        ),
+                        _ => return Err(ASTtoTIRLowererError::MismatchedVariantFields(pattern.span, variant.name))
+                    }
+                }
+                ASTPatternKind::StructVariant(path, fields, has_rest) => {
+                    let (type_ref_index, variant_index) = self.resolve_variant_path(path)?;
+                    let variant = self.enum_variant(type_ref_index, variant_index);
+                    let names = match &variant.kind {
+                        EnumVariantRefKind::Struct(names) => names,
+                        _ => return Err(ASTtoTIRLowererError::MismatchedVariantFields(pattern.span, variant.name))
+                    };
+
+                    // Fields are put back in declaration order, the ones left out by ".." match anything
+                    let mut lowered_fields: Vec<Option<TIRPattern>> = names.iter().map(|_| None).collect();
+                    for (name, field) in fields {
+                        let field_index = names.iter().position(|n| n == name).ok_or(ASTtoTIRLowererError::NoSuchVariantField(field.span, name))?;
+                        if lowered_fields[field_index].is_some() {
+                            return Err(ASTtoTIRLowererError::DuplicateVariantField(field.span, name));
+                        }
+                        lowered_fields[field_index] = Some(self.lower_ast_pattern(field, generic_context, body_context, bindings, slots)?);
+                    }
+                    let lowered_fields = lowered_fields.into_iter().zip(names.iter())
+                        .map(|(field, name)| match field {
+                            Some(field) => Ok(field),
+                            None if *has_rest => Ok(TIRPattern { kind: TIRPatternKind::Wildcard, span: pattern.span }),
+                            None => Err(ASTtoTIRLowererError::MissingVariantField(pattern.span, name))
+                        })
+                        .collect::<Result<Vec<TIRPattern>, ASTtoTIRLowererError<'a>>>()?;
+
+                    TIRPatternKind::EnumVariant(type_ref_index, variant_index, lowered_fields)
+                }
+                ASTPatternKind::TypeTest(name, type_info) => {
+                    let slot = match name {
+                        Some(name) => Some(self.bind(name, pattern.span, bindings, slots, body_context)?),
+                        None => None
+                    };
+                    TIRPatternKind::TypeTest(slot, self.resolve_type_info(type_info, Some(generic_context))?)
+                }
+                ASTPatternKind::Or(alternatives) => {
+                    // Every alternative has to bind the same locals
+                    let mut alternative_bindings: Option<Vec<(&'a str, usize)>> = None;
+                    let mut lowered_alternatives = vec![];
+                    for alternative in alternatives {
+                        let mut current_bindings = vec![];
+                        lowered_alternatives.push(self.lower_ast_pattern(alternative, generic_context, body_context, &mut current_bindings, slots)?);
+                        current_bindings.sort();
+                        match &alternative_bindings {
+                            Some(alternative_bindings) if *alternative_bindings != current_bindings =>
+                                return Err(ASTtoTIRLowererError::MismatchedOrBindings(alternative.span)),
+                            _ => alternative_bindings = Some(current_bindings)
+                        }
+                    }
+                    for (name, _) in alternative_bindings.unwrap_or_default() {
+                        self.bind(name, pattern.span, bindings, slots, body_context)?;
+                    }
+                    TIRPatternKind::Or(lowered_alternatives)
+                }
+            },
+            span: pattern.span
+        })
+    }
+
+    pub fn lower_ast_match(&self, scrutinee: &ASTExpr<'a>, arms: &[ASTMatchArm<'a>], generic_context: &GenericContext<'a, '_>, body_context: &mut BodyContext<'a>) -> Result<TIRExprKind<'a>, ASTtoTIRLowererError<'a>> {
+        let scrutinee = self.lower_ast_expr(scrutinee, generic_context, body_context)?;
+        let mut lowered_arms = vec![];
+        for arm in arms {
+            let mut bindings = vec![];
+            let pattern = self.lower_ast_pattern(&arm.pattern, generic_context, body_context, &mut bindings, &mut HashMap::new())?;
+
+            body_context.push_scope();
+            for (name, slot) in bindings {
+                body_context.bind_local(name, slot);
+            }
+            let guard = match &arm.guard {
+                Some(guard) => self.lower_ast_expr(guard, generic_context, body_context).map(Some),
+                None => Ok(None)
+            };
+            let body = self.lower_ast_expr(&arm.body, generic_context, body_context);
+            body_context.pop_scope();
+
+            lowered_arms.push(TIRMatchArm {
+                span: arm.span,
+                pattern,
+                guard: guard?,
+                body: body?
+            });
+        }
+
+        Ok(TIRExprKind::Match(Box::new(scrutinee), lowered_arms))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tir::ast_lowerer::ASTtoTIRLowererError;
+    use crate::testing::lowering_error;
+
+    #[test]
+    fn strings_are_not_patterns() {
+        let error = lowering_error("mod a; class A { fn f(x: u64) -> u64 { match x { \"a\" => 1, _ => 2 } } }");
+        assert!(matches!(error, ASTtoTIRLowererError::StringPattern(_)), "{:?}", error);
+    }
+}
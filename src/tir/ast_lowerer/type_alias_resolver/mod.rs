@@ -0,0 +1,116 @@
+use crate::tir::ast_lowerer::{find_cycle, ASTtoTIRLowerer, ASTtoTIRLowererError, GenericContext};
+use crate::tir::TIRTypeInfo;
+use crate::ast::ASTTypeInfo;
+use std::collections::HashMap;
+
+// "type" declarations are registered once the imports are, and expanded every time they are used.
+// An alias can't have the name of a local type or of a single import, and it takes precedence over glob imports.
+// Alias targets are resolved outside of any class, so they only see their own generics.
+
+impl<'a> ASTtoTIRLowerer<'a> {
+    // Aliases a type info mentions by name, its own generics don't count
+    fn mentioned_aliases(&self, type_info: &ASTTypeInfo<'a>, generics: &[&'a str], result: &mut Vec<usize>) {
+        if let [name] = type_info.path.elements[..] {
+            if !generics.contains(&name) {
+                if let Some(type_alias_index) = self.type_aliases.get(name) {
+                    result.push(*type_alias_index);
+                }
+            }
+        }
+        for generic in &type_info.generics {
+            self.mentioned_aliases(generic, generics, result);
+        }
+    }
+
+    pub fn register_type_aliases(&mut self) -> Result<(), ASTtoTIRLowererError<'a>> {
+        for type_alias_index in 0..self.ast_root.type_aliases.len() {
+            let type_alias = &self.ast_root.type_aliases[type_alias_index];
+            self.check_name(type_alias.name, type_alias.span)?;
+            let full_path = self.name_to_full_path(type_alias.name);
+            if self.type_ref_pool.full_path_to_type_ref_index.contains_key(&full_path) {
+                return Err(ASTtoTIRLowererError::DuplicateTypeDecl(type_alias.span, full_path));
+            }
+            if self.imports.contains_key(type_alias.name) {
+                return Err(ASTtoTIRLowererError::ImportConflict(type_alias.span, type_alias.name));
+            }
+            for i in 0..type_alias.generics.len() {
+                self.check_name(type_alias.generics[i], type_alias.span)?;
+                if type_alias.generics[..i].contains(&type_alias.generics[i]) {
+                    return Err(ASTtoTIRLowererError::DuplicateGeneric(type_alias.span, full_path, type_alias.generics[i]));
+                }
+            }
+            if self.type_aliases.insert(type_alias.name, type_alias_index).is_some() {
+                return Err(ASTtoTIRLowererError::DuplicateTypeDecl(type_alias.span, full_path));
+            }
+        }
+
+        let type_aliases = &self.ast_root.type_aliases;
+        if let Some(cycle) = find_cycle(type_aliases.len(), |type_alias_index| {
+            let mut mentioned = vec![];
+            self.mentioned_aliases(&type_aliases[type_alias_index].type_info, &type_aliases[type_alias_index].generics, &mut mentioned);
+            mentioned.reverse();
+            mentioned
+        }) {
+            let path: Vec<&str> = cycle.iter().map(|type_alias_index| type_aliases[*type_alias_index].name).collect();
+            return Err(ASTtoTIRLowererError::CyclicTypeAlias(type_aliases[cycle[0]].span, path.join(" -> ")));
+        }
+
+        Ok(())
+    }
+
+    // Expands a use of the alias, its generics are resolved where it is used
+    pub fn resolve_type_alias(&self, type_alias_index: usize, type_info: &ASTTypeInfo<'a>, generic_context: Option<&GenericContext<'a, '_>>, alias_generics: Option<&HashMap<&'a str, TIRTypeInfo>>) -> Result<TIRTypeInfo, ASTtoTIRLowererError<'a>> {
+        let type_alias = &self.ast_root.type_aliases[type_alias_index];
+        if type_info.generics.len() != type_alias.generics.len() {
+            return Err(ASTtoTIRLowererError::MismatchedAliasGenerics(type_info.span, type_alias.name));
+        }
+        let mut generics = HashMap::new();
+        for (name, generic) in type_alias.generics.iter().zip(&type_info.generics) {
+            generics.insert(*name, self.resolve_type_info_in(generic, generic_context, alias_generics)?);
+        }
+        let mut resolved = self.resolve_type_info_in(&type_alias.type_info, None, Some(&generics))?.add_array_dim(type_info.array_dim);
+        resolved.span = type_info.span;
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tir::ast_lowerer::ASTtoTIRLowererError;
+    use crate::testing::{lowering_error, type_errors};
+
+    #[test]
+    fn aliases_stand_for_their_target() {
+        let source = "
+            mod a;
+            class Holder<K, V> { }
+            type Id = u64;
+            type Pair<T> = Holder<T, T>;
+            type Ids = Pair<Id>[];
+            class A { fn f(id: Id, pair: Pair<u8>, ids: Ids) { let a: u64 = id; let b: Holder<u8, u8> = pair; let c: Holder<u64, u64>[] = ids; } }
+        ";
+        let errors = type_errors(source);
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn aliases_are_checked_like_types() {
+        let err = lowering_error("mod a; class Holder<K, V> { } type Pair<T> = Holder<T, T>; class A { pair: Pair<u8, u8>; }");
+        assert!(matches!(err, ASTtoTIRLowererError::MismatchedAliasGenerics(_, "Pair")), "{:?}", err);
+        let err = lowering_error("mod a; type First = Second[]; type Second = First; class A { }");
+        assert!(matches!(err, ASTtoTIRLowererError::CyclicTypeAlias(_, ref path) if path == "First -> Second -> First"), "{:?}", err);
+        let err = lowering_error("mod a; type A = u64; class A { }");
+        assert!(matches!(err, ASTtoTIRLowererError::DuplicateTypeDecl(_, ref path) if path == &["a", "A"]), "{:?}", err);
+    }
+
+    #[test]
+    fn boxed_names_are_their_primitive() {
+        let source = "
+            mod a;
+            class Wrapper<T> { pub inside: T; pub Wrapper(inside: T) { this.inside = inside; } }
+            class A { fn f(x: F64, flag: Bool) { let y: f64 = x; let z: bool = flag; let w = new Wrapper<u64>(10); let v: U64 = w.inside; } }
+        ";
+        let errors = type_errors(source);
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+}
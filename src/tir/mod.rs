@@ -2,6 +2,7 @@ use crate::reporting::TokenSpan;
 use std::hash::{Hash, Hasher};
 
 pub mod ast_lowerer;
+pub mod type_checker;
 
 pub struct TIRRoot<'a> {
     pub span: TokenSpan,
@@ -19,7 +20,27 @@ pub enum TIRTypeKind<'a> {
     Class {
         members: Vec<TIRMember<'a>>,
         super_class: Option<TIRTypeInfo>,
+        impls: Vec<TIRTypeInfo>,
     },
+    Interface {
+        members: Vec<TIRMember<'a>>,
+        super_interfaces: Vec<TIRTypeInfo>,
+    },
+    Enum {
+        variants: Vec<TIRVariant<'a>>,
+    },
+}
+
+pub enum TIRVariantKind<'a> {
+    Unit,
+    Tuple(Vec<TIRTypeInfo>),
+    Struct(Vec<TIRNameAndType<'a>>),
+}
+
+pub struct TIRVariant<'a> {
+    pub span: TokenSpan,
+    pub name: &'a str,
+    pub kind: TIRVariantKind<'a>,
 }
 
 pub struct TIRType<'a> {
@@ -32,11 +53,23 @@ pub enum TIRMemberKind<'a> {
     Field {
         name_and_type: TIRNameAndType<'a>,
         expression: Option<TIRExpr<'a>>,
+        // Number of local slots used by the expression
+        local_count: usize,
     },
     Method {
         name_and_type: TIRNameAndType<'a>,
         parameters: Vec<TIRNameAndType<'a>>,
         block: Option<TIRStatementBlock<'a>>,
+        // Number of local slots used by the body, "this" and the parameters included
+        local_count: usize,
+    },
+    Constructor {
+        parameters: Vec<TIRNameAndType<'a>>,
+        // Indexes of the super class constructors that can be called with the arguments, None if there is no super class.
+        // Only the one that is called is left once the arguments are typed.
+        super_call: Option<(Vec<usize>, Vec<TIRExpr<'a>>)>,
+        block: TIRStatementBlock<'a>,
+        local_count: usize,
     },
 }
 
@@ -67,6 +100,112 @@ pub enum PrimitiveType {
     F32,
     Boolean,
     Character,
+    // Type of expressions that never produce a value, like "break" or "return"
+    Never,
+}
+
+impl PrimitiveType {
+    // Primitive a type name refers to, these names can't be used for anything else
+    pub fn from_name(name: &str) -> Option<PrimitiveType> {
+        Some(match name {
+            "void" => PrimitiveType::Void,
+            "i64" => PrimitiveType::I64,
+            "i32" => PrimitiveType::I32,
+            "i16" => PrimitiveType::I16,
+            "i8" => PrimitiveType::I8,
+            "u64" => PrimitiveType::U64,
+            "u32" => PrimitiveType::U32,
+            "u16" => PrimitiveType::U16,
+            "u8" => PrimitiveType::U8,
+            "f64" => PrimitiveType::F64,
+            "f32" => PrimitiveType::F32,
+            "bool" => PrimitiveType::Boolean,
+            "char" => PrimitiveType::Character,
+            _ => return None
+        })
+    }
+
+    // Standard library class the primitive is boxed into when it is used as a generic ("SimpleWrapper<u64>" holds U64 instances).
+    // The boxed name can be written instead of the primitive one, both are the same type for the type checker.
+    pub fn boxed_name(&self) -> Option<&'static str> {
+        Some(match self {
+            PrimitiveType::I64 => "I64",
+            PrimitiveType::I32 => "I32",
+            PrimitiveType::I16 => "I16",
+            PrimitiveType::I8 => "I8",
+            PrimitiveType::U64 => "U64",
+            PrimitiveType::U32 => "U32",
+            PrimitiveType::U16 => "U16",
+            PrimitiveType::U8 => "U8",
+            PrimitiveType::F64 => "F64",
+            PrimitiveType::F32 => "F32",
+            PrimitiveType::Boolean => "Bool",
+            PrimitiveType::Character => "Char",
+            PrimitiveType::Void | PrimitiveType::Never => return None
+        })
+    }
+
+    pub fn from_boxed_name(name: &str) -> Option<PrimitiveType> {
+        [
+            PrimitiveType::I64, PrimitiveType::I32, PrimitiveType::I16, PrimitiveType::I8,
+            PrimitiveType::U64, PrimitiveType::U32, PrimitiveType::U16, PrimitiveType::U8,
+            PrimitiveType::F64, PrimitiveType::F32, PrimitiveType::Boolean, PrimitiveType::Character
+        ].iter().copied().find(|primitive| primitive.boxed_name() == Some(name))
+    }
+
+    pub fn is_integer(&self) -> bool {
+        match self {
+            PrimitiveType::I64 | PrimitiveType::I32 | PrimitiveType::I16 | PrimitiveType::I8 |
+            PrimitiveType::U64 | PrimitiveType::U32 | PrimitiveType::U16 | PrimitiveType::U8 => true,
+            _ => false
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        match self {
+            PrimitiveType::F64 | PrimitiveType::F32 => true,
+            _ => false
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_float()
+    }
+
+    // Conversions to a bigger type that never lose information, arguments are converted implicitly
+    pub fn widens_to(&self, other: PrimitiveType) -> bool {
+        match (self, other) {
+            (PrimitiveType::I8, PrimitiveType::I16 | PrimitiveType::I32 | PrimitiveType::I64 | PrimitiveType::F32 | PrimitiveType::F64) |
+            (PrimitiveType::I16, PrimitiveType::I32 | PrimitiveType::I64 | PrimitiveType::F32 | PrimitiveType::F64) |
+            (PrimitiveType::I32, PrimitiveType::I64 | PrimitiveType::F64) |
+            (PrimitiveType::U8, PrimitiveType::U16 | PrimitiveType::U32 | PrimitiveType::U64 | PrimitiveType::I16 | PrimitiveType::I32 | PrimitiveType::I64 | PrimitiveType::F32 | PrimitiveType::F64) |
+            (PrimitiveType::U16, PrimitiveType::U32 | PrimitiveType::U64 | PrimitiveType::I32 | PrimitiveType::I64 | PrimitiveType::F32 | PrimitiveType::F64) |
+            (PrimitiveType::U32, PrimitiveType::U64 | PrimitiveType::I64 | PrimitiveType::F64) |
+            (PrimitiveType::F32, PrimitiveType::F64) => true,
+            _ => false
+        }
+    }
+
+    // Whether an integer literal fits in this integer type, the sign is separate so that "-128i8" fits.
+    // Non-integer types never hold an integer literal
+    pub fn integer_literal_in_range(&self, value: u64, negated: bool) -> bool {
+        match (self, negated) {
+            (PrimitiveType::I64, false) => value <= i64::MAX as u64,
+            (PrimitiveType::I32, false) => value <= i32::MAX as u64,
+            (PrimitiveType::I16, false) => value <= i16::MAX as u64,
+            (PrimitiveType::I8, false) => value <= i8::MAX as u64,
+            (PrimitiveType::I64, true) => value <= i64::MAX as u64 + 1,
+            (PrimitiveType::I32, true) => value <= i32::MAX as u64 + 1,
+            (PrimitiveType::I16, true) => value <= i16::MAX as u64 + 1,
+            (PrimitiveType::I8, true) => value <= i8::MAX as u64 + 1,
+            (PrimitiveType::U64, false) => true,
+            (PrimitiveType::U32, false) => value <= u32::MAX as u64,
+            (PrimitiveType::U16, false) => value <= u16::MAX as u64,
+            (PrimitiveType::U8, false) => value <= u8::MAX as u64,
+            (PrimitiveType::U64 | PrimitiveType::U32 | PrimitiveType::U16 | PrimitiveType::U8, true) => value == 0,
+            _ => false
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
@@ -101,6 +240,53 @@ impl PartialEq<Self> for TIRTypeInfo {
 
 impl Eq for TIRTypeInfo {}
 
+impl TIRTypeInfo {
+    pub fn array_dim(&self) -> usize {
+        match &self.kind {
+            TIRTypeInfoKind::TypeRef { array_dim, .. } |
+            TIRTypeInfoKind::Generic { array_dim, .. } |
+            TIRTypeInfoKind::Primitive { array_dim, .. } => *array_dim
+        }
+    }
+
+    // Type of the elements of the array, "removed" dimensions down
+    pub fn remove_array_dim(&self, removed: usize) -> Option<TIRTypeInfo> {
+        let mut type_info = self.clone();
+        match &mut type_info.kind {
+            TIRTypeInfoKind::TypeRef { array_dim, .. } |
+            TIRTypeInfoKind::Generic { array_dim, .. } |
+            TIRTypeInfoKind::Primitive { array_dim, .. } => *array_dim = array_dim.checked_sub(removed)?
+        }
+        Some(type_info)
+    }
+
+    pub fn add_array_dim(&self, added: usize) -> TIRTypeInfo {
+        let mut type_info = self.clone();
+        match &mut type_info.kind {
+            TIRTypeInfoKind::TypeRef { array_dim, .. } |
+            TIRTypeInfoKind::Generic { array_dim, .. } |
+            TIRTypeInfoKind::Primitive { array_dim, .. } => *array_dim += added
+        }
+        type_info
+    }
+
+    // Values that can be null: instances, generics and arrays
+    pub fn is_reference(&self) -> bool {
+        match &self.kind {
+            TIRTypeInfoKind::Primitive { array_dim, .. } => *array_dim > 0,
+            _ => true
+        }
+    }
+
+    // Arrays whose elements are references themselves
+    pub fn is_reference_array(&self) -> bool {
+        match &self.kind {
+            TIRTypeInfoKind::Primitive { array_dim, .. } => *array_dim > 1,
+            _ => self.array_dim() > 0
+        }
+    }
+}
+
 impl Hash for TIRTypeInfo {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.kind.hash(state);
@@ -119,10 +305,22 @@ pub enum TIROperator {
     Minus,
     Mul,
     Div,
+    Mod,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
     PlusAssign,
     MinusAssign,
     MulAssign,
     DivAssign,
+    ModAssign,
+    BitAndAssign,
+    BitOrAssign,
+    BitXorAssign,
+    ShlAssign,
+    ShrAssign,
     Assign,
     And,
     Or,
@@ -136,11 +334,68 @@ pub enum TIROperator {
     Inc,
     Dec,
     Not,
+    BitNot,
+}
+
+impl TIROperator {
+    pub fn is_comparison(&self) -> bool {
+        match self {
+            TIROperator::Eq | TIROperator::NotEq | TIROperator::Gt | TIROperator::GtEq | TIROperator::Ls | TIROperator::LsEq => true,
+            _ => false
+        }
+    }
+
+    // The operator a compound assignment applies ("+" for "+=")
+    pub fn compound_operator(&self) -> Option<TIROperator> {
+        match self {
+            TIROperator::PlusAssign => Some(TIROperator::Plus),
+            TIROperator::MinusAssign => Some(TIROperator::Minus),
+            TIROperator::MulAssign => Some(TIROperator::Mul),
+            TIROperator::DivAssign => Some(TIROperator::Div),
+            TIROperator::ModAssign => Some(TIROperator::Mod),
+            TIROperator::BitAndAssign => Some(TIROperator::BitAnd),
+            TIROperator::BitOrAssign => Some(TIROperator::BitOr),
+            TIROperator::BitXorAssign => Some(TIROperator::BitXor),
+            TIROperator::ShlAssign => Some(TIROperator::Shl),
+            TIROperator::ShrAssign => Some(TIROperator::Shr),
+            _ => None
+        }
+    }
+
+    // Type of the result of a binary operator on primitives, None if it can't be applied to them
+    pub fn binary_result(&self, left: PrimitiveType, right: PrimitiveType) -> Option<PrimitiveType> {
+        if left != right {
+            return None;
+        }
+        let valid = match self {
+            TIROperator::Plus | TIROperator::Minus | TIROperator::Mul | TIROperator::Div | TIROperator::Mod => left.is_numeric(),
+            TIROperator::BitAnd | TIROperator::BitOr | TIROperator::BitXor => left.is_integer() || left == PrimitiveType::Boolean,
+            TIROperator::Shl | TIROperator::Shr => left.is_integer(),
+            TIROperator::Eq | TIROperator::NotEq => left != PrimitiveType::Void,
+            TIROperator::Gt | TIROperator::GtEq | TIROperator::Ls | TIROperator::LsEq => left.is_numeric() || left == PrimitiveType::Character,
+            _ => false
+        };
+        if !valid {
+            return None;
+        }
+        Some(if self.is_comparison() { PrimitiveType::Boolean } else { left })
+    }
+
+    // Type of the result of "-", "!" and "~", None if it can't be applied to the operand
+    pub fn unary_result(&self, operand: PrimitiveType) -> Option<PrimitiveType> {
+        match self {
+            TIROperator::Minus if operand.is_numeric() => Some(operand),
+            TIROperator::Not if operand == PrimitiveType::Boolean => Some(operand),
+            TIROperator::BitNot if operand.is_integer() => Some(operand),
+            _ => None
+        }
+    }
 }
 
 #[derive(Clone)]
 pub enum TIRStatementKind<'a> {
-    Local(&'a str, Option<TIRTypeInfo>, Option<Box<TIRExpr<'a>>>),
+    // Slot of the local
+    Local(usize, Option<TIRTypeInfo>, Option<Box<TIRExpr<'a>>>),
     Expression(Box<TIRExpr<'a>>),
 }
 
@@ -159,23 +414,45 @@ pub struct TIRStatementBlock<'a> {
 
 #[derive(Clone)]
 pub enum TIRExprKind<'a> {
-    StringLiteral(&'a str),
-    Num(&'a str),
-    Float(&'a str),
+    StringLiteral(String),
+    CharLiteral(char),
+    Num(u64, Option<PrimitiveType>),
+    Float(f64, Option<PrimitiveType>),
     Boolean(bool),
     Null,
+    This,
+    Super,
 
     BinOp(Box<TIRExpr<'a>>, TIROperator, Box<TIRExpr<'a>>),
     PreOp(TIROperator, Box<TIRExpr<'a>>),
     PostOp(Box<TIRExpr<'a>>, TIROperator),
 
     TypeAccess(usize),
-    VariableAccess(&'a str),
+    // Slot of the local
+    LocalAccess(usize),
+    // Field ref index of the field
+    FieldAccess(Box<TIRExpr<'a>>, usize),
+    StaticFieldAccess(usize),
+    // Method ref index of the method and the arguments
+    MethodCall(Box<TIRExpr<'a>>, usize, Vec<TIRExpr<'a>>),
+    StaticMethodCall(usize, Vec<TIRExpr<'a>>),
 
+    // Members of expressions whose type is only known once expressions are typed
     MemberAccess(Box<TIRExpr<'a>>, &'a str),
-    StaticAccess(Box<TIRExpr<'a>>, &'a str),
     Call(Box<TIRExpr<'a>>, Vec<TIRExpr<'a>>),
+    // Overloads with as many parameters as there are arguments, picked once the arguments are typed.
+    // What the method is called on (None for static calls), the method ref indexes of the candidates and the arguments
+    OverloadedCall(Option<Box<TIRExpr<'a>>>, Vec<usize>, Vec<TIRExpr<'a>>),
     Indexing(Box<TIRExpr<'a>>, Box<TIRExpr<'a>>),
+    // Type ref index of the enum, variant index and the fields in declaration order
+    EnumConstruct(usize, usize, Vec<TIRExpr<'a>>),
+    // Start, end and whether the end is included, "for" loops over ranges are desugared instead
+    Range(Box<TIRExpr<'a>>, Box<TIRExpr<'a>>, bool),
+    ArrayLength(Box<TIRExpr<'a>>),
+    // Class type, index of the constructor and the arguments
+    New(TIRTypeInfo, usize, Vec<TIRExpr<'a>>),
+    // Class type, indexes of the candidate constructors and the arguments, see OverloadedCall
+    OverloadedNew(TIRTypeInfo, Vec<usize>, Vec<TIRExpr<'a>>),
 
     Block(TIRStatementBlock<'a>),
     IfElse(
@@ -184,14 +461,80 @@ pub enum TIRExprKind<'a> {
         TIRStatementBlock<'a>,
     ),
     If(Box<TIRExpr<'a>>, TIRStatementBlock<'a>),
-    Loop(TIRStatementBlock<'a>),
-    While(Box<TIRExpr<'a>>, TIRStatementBlock<'a>),
-    Match(/* TODO */),
-    For(/* TODO */),
+    // Loops start with their optional label
+    Loop(Option<&'a str>, TIRStatementBlock<'a>),
+    While(Option<&'a str>, Box<TIRExpr<'a>>, TIRStatementBlock<'a>),
+    // "for" loops over anything but a range, desugared by the type checker once it knows whether the iterable is an array.
+    // Label, iterable, slot of the loop variable, slots of the two locals the desugaring needs and the body
+    For(Option<&'a str>, Box<TIRExpr<'a>>, usize, [usize; 2], TIRStatementBlock<'a>),
+    Match(Box<TIRExpr<'a>>, Vec<TIRMatchArm<'a>>),
+    // Jumps are typed as never
+    Break(Option<&'a str>, Option<Box<TIRExpr<'a>>>),
+    Continue(Option<&'a str>),
+    Return(Option<Box<TIRExpr<'a>>>),
 }
 
 #[derive(Clone)]
 pub struct TIRExpr<'a> {
     pub kind: TIRExprKind<'a>,
     pub span: TokenSpan,
-}
\ No newline at end of file
+    // Filled in by the type checker, stays None when the type couldn't be determined
+    pub type_info: Option<TIRTypeInfo>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TIRPatternLiteral {
+    // The sign is folded into the value so that ranges can be compared
+    Integer(i128, Option<PrimitiveType>),
+    Float(f64, Option<PrimitiveType>),
+    Character(char),
+    Boolean(bool),
+    Null,
+}
+
+#[derive(Clone)]
+pub enum TIRPatternKind {
+    Wildcard,
+    // Slot of the local
+    Binding(usize),
+    Literal(TIRPatternLiteral),
+    // Start, end and whether the end is included, only for integers and characters
+    Range(TIRPatternLiteral, TIRPatternLiteral, bool),
+    // Type ref index of the enum, variant index and a pattern for every field in declaration order
+    EnumVariant(usize, usize, Vec<TIRPattern>),
+    TypeTest(Option<usize>, TIRTypeInfo),
+    Or(Vec<TIRPattern>),
+}
+
+#[derive(Clone)]
+pub struct TIRPattern {
+    pub kind: TIRPatternKind,
+    pub span: TokenSpan,
+}
+
+#[derive(Clone)]
+pub struct TIRMatchArm<'a> {
+    pub span: TokenSpan,
+    pub pattern: TIRPattern,
+    pub guard: Option<TIRExpr<'a>>,
+    pub body: TIRExpr<'a>,
+}
+#[cfg(test)]
+mod tests {
+    use super::PrimitiveType;
+
+    #[test]
+    fn integer_literals_are_checked_against_their_type() {
+        assert!(PrimitiveType::I8.integer_literal_in_range(127, false));
+        assert!(!PrimitiveType::I8.integer_literal_in_range(128, false));
+        assert!(PrimitiveType::I8.integer_literal_in_range(128, true));
+        assert!(PrimitiveType::U64.integer_literal_in_range(u64::MAX, false));
+        assert!(PrimitiveType::U8.integer_literal_in_range(0, true));
+        assert!(!PrimitiveType::U8.integer_literal_in_range(1, true));
+        // Non-integers answer instead of panicking
+        for primitive in [PrimitiveType::F64, PrimitiveType::F32, PrimitiveType::Boolean, PrimitiveType::Character, PrimitiveType::Void] {
+            assert!(!primitive.integer_literal_in_range(0, false));
+            assert!(!primitive.integer_literal_in_range(0, true));
+        }
+    }
+}
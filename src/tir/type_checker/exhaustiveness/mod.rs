@@ -0,0 +1,411 @@
+use crate::tir::type_checker::{TypeChecker, TypeCheckerError};
+use crate::tir::{TIRMatchArm, TIRPattern, TIRPatternKind, TIRPatternLiteral, TIRTypeInfo, TIRTypeInfoKind, PrimitiveType};
+use crate::type_ref_pool::{TypeRefKind, EnumVariantRef, EnumVariantRefKind, type_ref_index_of};
+use crate::reporting::TokenSpan;
+
+// Exhaustiveness and reachability are both answered by the usefulness algorithm
+// described in "Warnings for pattern matching" (Maranget, 2007).
+// This runs once the scrutinee is typed, so that the values of a column are the ones of its type
+// (an u8 is covered by "0..=255"). Columns whose type isn't known take it from their patterns.
+
+#[derive(Clone, PartialEq)]
+enum ColumnType {
+    // With the generics the enum is used with, if they are known
+    Enum(TIRTypeInfo),
+    Boolean,
+    // Inclusive bounds
+    Integer(i128, i128),
+    Character,
+    // Values can't be enumerated (floats, strings, objects...)
+    Unknown
+}
+
+#[derive(Clone, PartialEq)]
+enum Constructor {
+    Wildcard,
+    // Type ref index and variant index
+    Variant(usize, usize),
+    Boolean(bool),
+    // Inclusive range of integers or character code points
+    Range(i128, i128),
+    // Only covers the exact same literal or type test
+    Opaque(String),
+    // The alternatives are stored as fields
+    Or
+}
+
+#[derive(Clone)]
+struct DeconstructedPattern {
+    constructor: Constructor,
+    fields: Vec<DeconstructedPattern>,
+    column_type: ColumnType
+}
+
+impl DeconstructedPattern {
+    fn wildcard() -> Self {
+        Self {
+            constructor: Constructor::Wildcard,
+            fields: vec![],
+            column_type: ColumnType::Unknown
+        }
+    }
+}
+
+fn integer_bounds(primitive: PrimitiveType) -> (i128, i128) {
+    match primitive {
+        PrimitiveType::I64 => (i64::MIN as i128, i64::MAX as i128),
+        PrimitiveType::I32 => (i32::MIN as i128, i32::MAX as i128),
+        PrimitiveType::I16 => (i16::MIN as i128, i16::MAX as i128),
+        PrimitiveType::I8 => (i8::MIN as i128, i8::MAX as i128),
+        PrimitiveType::U64 => (0, u64::MAX as i128),
+        PrimitiveType::U32 => (0, u32::MAX as i128),
+        PrimitiveType::U16 => (0, u16::MAX as i128),
+        PrimitiveType::U8 => (0, u8::MAX as i128),
+        _ => unreachable!()
+    }
+}
+
+fn covers(constructor: &Constructor, other: &Constructor) -> bool {
+    match (constructor, other) {
+        (Constructor::Wildcard, _) => true,
+        (Constructor::Variant(t1, v1), Constructor::Variant(t2, v2)) => t1 == t2 && v1 == v2,
+        (Constructor::Boolean(b1), Constructor::Boolean(b2)) => b1 == b2,
+        (Constructor::Range(start1, end1), Constructor::Range(start2, end2)) => start1 <= start2 && end2 <= end1,
+        (Constructor::Opaque(o1), Constructor::Opaque(o2)) => o1 == o2,
+        _ => false
+    }
+}
+
+// Splits [start, end] into intervals that are either fully inside or fully outside of every range in the column
+fn split_range(start: i128, end: i128, column: &[&Constructor]) -> Vec<Constructor> {
+    let mut borders = vec![start, end + 1];
+    for constructor in column {
+        if let Constructor::Range(s, e) = constructor {
+            for border in [*s, *e + 1] {
+                if border > start && border <= end {
+                    borders.push(border);
+                }
+            }
+        }
+    }
+    borders.sort();
+    borders.dedup();
+    borders.windows(2).map(|w| Constructor::Range(w[0], w[1] - 1)).collect()
+}
+
+// Removes the first column, keeping the rows matching the constructor and replacing it with its fields
+fn specialize(row: &[DeconstructedPattern], constructor: &Constructor, arity: usize) -> Option<Vec<DeconstructedPattern>> {
+    let mut specialized = if row[0].constructor == Constructor::Wildcard {
+        vec![DeconstructedPattern::wildcard(); arity]
+    } else if covers(&row[0].constructor, constructor) {
+        row[0].fields.clone()
+    } else {
+        return None;
+    };
+    specialized.extend_from_slice(&row[1..]);
+    Some(specialized)
+}
+
+fn expand_or(row: &[DeconstructedPattern]) -> Vec<Vec<DeconstructedPattern>> {
+    if row[0].constructor != Constructor::Or {
+        return vec![row.to_vec()];
+    }
+    row[0].fields.iter().flat_map(|alternative| {
+        let mut expanded = vec![alternative.clone()];
+        expanded.extend_from_slice(&row[1..]);
+        expand_or(&expanded)
+    }).collect()
+}
+
+// Enum used with unknown generics, the fields that mention them get an unknown type
+fn enum_type_info(type_ref_index: usize, span: TokenSpan) -> TIRTypeInfo {
+    TIRTypeInfo {
+        kind: TIRTypeInfoKind::TypeRef {
+            type_ref_index,
+            generics: vec![],
+            array_dim: 0
+        },
+        span
+    }
+}
+
+impl<'a, 'b> TypeChecker<'a, 'b> {
+    fn enum_variant(&self, type_ref_index: usize, variant_index: usize) -> &'b EnumVariantRef<'a> {
+        match &self.type_ref_pool.type_refs[type_ref_index].kind {
+            TypeRefKind::Enum(enum_type_ref) => &enum_type_ref.variants[variant_index],
+            _ => unreachable!()
+        }
+    }
+
+    fn type_info_column_type(&self, type_info: &TIRTypeInfo) -> ColumnType {
+        match &type_info.kind {
+            TIRTypeInfoKind::Primitive { primitive, array_dim: 0 } => match primitive {
+                PrimitiveType::Boolean => ColumnType::Boolean,
+                PrimitiveType::Character => ColumnType::Character,
+                p if p.is_integer() => {
+                    let (start, end) = integer_bounds(*p);
+                    ColumnType::Integer(start, end)
+                }
+                _ => ColumnType::Unknown
+            },
+            TIRTypeInfoKind::TypeRef { type_ref_index, array_dim: 0, .. } => match &self.type_ref_pool.type_refs[*type_ref_index].kind {
+                TypeRefKind::Enum(_) => ColumnType::Enum(type_info.clone()),
+                _ => ColumnType::Unknown
+            },
+            _ => ColumnType::Unknown
+        }
+    }
+
+    fn deconstruct_pattern(&self, pattern: &TIRPattern) -> DeconstructedPattern {
+        let (constructor, fields, column_type) = match &pattern.kind {
+            TIRPatternKind::Wildcard | TIRPatternKind::Binding(_) => return DeconstructedPattern::wildcard(),
+            TIRPatternKind::Literal(TIRPatternLiteral::Integer(value, suffix)) => {
+                // Unsuffixed literals are assumed to be i64 until inference tells otherwise
+                let (start, end) = integer_bounds(suffix.unwrap_or(PrimitiveType::I64));
+                (Constructor::Range(*value, *value), vec![], ColumnType::Integer(start, end))
+            }
+            TIRPatternKind::Literal(TIRPatternLiteral::Character(character)) =>
+                (Constructor::Range(*character as i128, *character as i128), vec![], ColumnType::Character),
+            TIRPatternKind::Literal(TIRPatternLiteral::Boolean(boolean)) =>
+                (Constructor::Boolean(*boolean), vec![], ColumnType::Boolean),
+            TIRPatternKind::Literal(literal) =>
+                (Constructor::Opaque(format!("{:?}", literal)), vec![], ColumnType::Unknown),
+            TIRPatternKind::Range(TIRPatternLiteral::Integer(start, suffix), TIRPatternLiteral::Integer(end, _), inclusive) => {
+                let (type_start, type_end) = integer_bounds(suffix.unwrap_or(PrimitiveType::I64));
+                let end = if *inclusive { *end } else { *end - 1 };
+                (Constructor::Range(*start, end), vec![], ColumnType::Integer(type_start, type_end))
+            }
+            TIRPatternKind::Range(TIRPatternLiteral::Character(start), TIRPatternLiteral::Character(end), inclusive) => {
+                let end = if *inclusive { *end as i128 } else { *end as i128 - 1 };
+                (Constructor::Range(*start as i128, end), vec![], ColumnType::Character)
+            }
+            TIRPatternKind::Range(_, _, _) => unreachable!(),
+            TIRPatternKind::EnumVariant(type_ref_index, variant_index, fields) => (
+                Constructor::Variant(*type_ref_index, *variant_index),
+                fields.iter().map(|f| self.deconstruct_pattern(f)).collect(),
+                ColumnType::Enum(enum_type_info(*type_ref_index, pattern.span))
+            ),
+            TIRPatternKind::TypeTest(_, type_info) =>
+                (Constructor::Opaque(format!("{:?}", type_info.kind)), vec![], ColumnType::Unknown),
+            TIRPatternKind::Or(alternatives) =>
+                (Constructor::Or, alternatives.iter().map(|a| self.deconstruct_pattern(a)).collect(), ColumnType::Unknown)
+        };
+
+        DeconstructedPattern {
+            constructor,
+            fields,
+            column_type
+        }
+    }
+
+    fn field_column_types(&self, constructor: &Constructor, column_type: &ColumnType) -> Vec<ColumnType> {
+        match constructor {
+            Constructor::Variant(type_ref_index, variant_index) => {
+                let enum_type = match column_type {
+                    ColumnType::Enum(enum_type) if type_ref_index_of(enum_type) == Some(*type_ref_index) => Some(enum_type),
+                    _ => None
+                };
+                self.enum_variant(*type_ref_index, *variant_index).fields.iter()
+                    .map(|f| enum_type.and_then(|e| self.member_type(f, *type_ref_index, Some(e))).map_or(ColumnType::Unknown, |f| self.type_info_column_type(&f)))
+                    .collect()
+            }
+            _ => vec![]
+        }
+    }
+
+    // Every constructor of the type, or None if they can't be enumerated
+    fn all_constructors(&self, column_type: &ColumnType, column: &[&Constructor]) -> Option<Vec<Constructor>> {
+        match column_type {
+            ColumnType::Enum(enum_type) => {
+                let type_ref_index = type_ref_index_of(enum_type).unwrap();
+                match &self.type_ref_pool.type_refs[type_ref_index].kind {
+                    TypeRefKind::Enum(enum_type_ref) => Some((0..enum_type_ref.variants.len()).map(|v| Constructor::Variant(type_ref_index, v)).collect()),
+                    _ => unreachable!()
+                }
+            }
+            ColumnType::Boolean => Some(vec![Constructor::Boolean(false), Constructor::Boolean(true)]),
+            ColumnType::Integer(start, end) => Some(split_range(*start, *end, column)),
+            // Surrogates aren't valid characters
+            ColumnType::Character => {
+                let mut constructors = split_range(0, 0xD7FF, column);
+                constructors.extend(split_range(0xE000, 0x10FFFF, column));
+                Some(constructors)
+            }
+            ColumnType::Unknown => None
+        }
+    }
+
+    // Returns an example of a value matched by the row but by none of the rows of the matrix
+    fn is_useful(&self, matrix: &[Vec<DeconstructedPattern>], row: &[DeconstructedPattern], types: &[ColumnType]) -> Option<Vec<DeconstructedPattern>> {
+        if row.is_empty() {
+            return if matrix.is_empty() { Some(vec![]) } else { None };
+        }
+
+        let head = &row[0];
+        if head.constructor == Constructor::Or {
+            return head.fields.iter().find_map(|alternative| {
+                let mut expanded = vec![alternative.clone()];
+                expanded.extend_from_slice(&row[1..]);
+                self.is_useful(matrix, &expanded, types)
+            });
+        }
+
+        let matrix: Vec<Vec<DeconstructedPattern>> = matrix.iter().flat_map(|r| expand_or(r)).collect();
+        let column: Vec<&Constructor> = matrix.iter().map(|r| &r[0].constructor)
+            .filter(|c| **c != Constructor::Wildcard)
+            .collect();
+        let column_type = if types[0] != ColumnType::Unknown {
+            types[0].clone()
+        } else {
+            matrix.iter().map(|r| &r[0].column_type).chain(std::iter::once(&head.column_type))
+                .find(|t| **t != ColumnType::Unknown)
+                .cloned()
+                .unwrap_or(ColumnType::Unknown)
+        };
+
+        let constructors = match &head.constructor {
+            Constructor::Range(start, end) => split_range(*start, *end, &column),
+            Constructor::Wildcard => match self.all_constructors(&column_type, &column) {
+                Some(all) if all.iter().all(|c| column.iter().any(|h| covers(h, c))) => all,
+                // Some constructors are missing from the column, so only the rows starting with a wildcard can still match
+                all => {
+                    let default: Vec<Vec<DeconstructedPattern>> = matrix.iter()
+                        .filter(|r| r[0].constructor == Constructor::Wildcard)
+                        .map(|r| r[1..].to_vec())
+                        .collect();
+                    let mut witness = self.is_useful(&default, &row[1..], &types[1..])?;
+                    // With nothing in the column, "_" is a better example than any constructor
+                    let all = all.filter(|_| !column.is_empty());
+                    let missing = match all.and_then(|all| all.into_iter().find(|c| !column.iter().any(|h| covers(h, c)))) {
+                        Some(constructor) => DeconstructedPattern {
+                            fields: vec![DeconstructedPattern::wildcard(); self.field_column_types(&constructor, &column_type).len()],
+                            constructor,
+                            column_type
+                        },
+                        None => DeconstructedPattern::wildcard()
+                    };
+                    witness.insert(0, missing);
+                    return Some(witness);
+                }
+            },
+            constructor => vec![constructor.clone()]
+        };
+
+        for constructor in constructors {
+            let mut specialized_types = self.field_column_types(&constructor, &column_type);
+            let arity = specialized_types.len();
+            specialized_types.extend_from_slice(&types[1..]);
+            let specialized_matrix: Vec<Vec<DeconstructedPattern>> = matrix.iter().filter_map(|r| specialize(r, &constructor, arity)).collect();
+            let specialized_row = specialize(row, &constructor, arity).unwrap();
+
+            if let Some(mut witness) = self.is_useful(&specialized_matrix, &specialized_row, &specialized_types) {
+                let fields = witness.drain(..arity).collect();
+                witness.insert(0, DeconstructedPattern {
+                    constructor,
+                    fields,
+                    column_type: column_type.clone()
+                });
+                return Some(witness);
+            }
+        }
+
+        None
+    }
+
+    fn witness_to_string(&self, pattern: &DeconstructedPattern) -> String {
+        match &pattern.constructor {
+            Constructor::Wildcard => "_".to_string(),
+            Constructor::Variant(type_ref_index, variant_index) => {
+                let enum_name = self.type_ref_pool.type_refs[*type_ref_index].full_path.last().unwrap();
+                let variant = self.enum_variant(*type_ref_index, *variant_index);
+                let fields: Vec<String> = pattern.fields.iter().map(|f| self.witness_to_string(f)).collect();
+                match &variant.kind {
+                    EnumVariantRefKind::Unit => format!("{}::{}", enum_name, variant.name),
+                    EnumVariantRefKind::Tuple => format!("{}::{}({})", enum_name, variant.name, fields.join(", ")),
+                    EnumVariantRefKind::Struct(names) => format!(
+                        "{}::{} {{ {} }}",
+                        enum_name,
+                        variant.name,
+                        names.iter().zip(fields).map(|(n, f)| format!("{}: {}", n, f)).collect::<Vec<String>>().join(", ")
+                    )
+                }
+            }
+            Constructor::Boolean(boolean) => boolean.to_string(),
+            Constructor::Range(start, end) => {
+                let bound_to_string = |value: i128| match &pattern.column_type {
+                    ColumnType::Character => match std::char::from_u32(value as u32) {
+                        Some(character) => format!("{:?}", character),
+                        None => value.to_string()
+                    },
+                    _ => value.to_string()
+                };
+                if start == end {
+                    bound_to_string(*start)
+                } else {
+                    format!("{}..={}", bound_to_string(*start), bound_to_string(*end))
+                }
+            }
+            Constructor::Opaque(opaque) => opaque.clone(),
+            Constructor::Or => unreachable!()
+        }
+    }
+
+    // The scrutinee type gives the values of the first column
+    pub fn check_match_arms(&mut self, arms: &[TIRMatchArm<'a>], scrutinee_type: Option<&TIRTypeInfo>, span: TokenSpan) {
+        let types = [scrutinee_type.map_or(ColumnType::Unknown, |t| self.type_info_column_type(t))];
+        let mut matrix = vec![];
+        for arm in arms {
+            let row = vec![self.deconstruct_pattern(&arm.pattern)];
+            if self.is_useful(&matrix, &row, &types).is_none() {
+                self.errors.push(TypeCheckerError::UnreachableMatchArm(arm.span));
+            }
+            // A guard can fail, so a guarded arm doesn't make the following ones unreachable
+            if arm.guard.is_none() {
+                matrix.push(row);
+            }
+        }
+
+        if let Some(witness) = self.is_useful(&matrix, &[DeconstructedPattern::wildcard()], &types) {
+            self.errors.push(TypeCheckerError::NonExhaustiveMatch(span, self.witness_to_string(&witness[0])));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tir::type_checker::TypeCheckerError;
+    use crate::testing::type_errors;
+
+    fn missing_value(source: &str) -> String {
+        match &type_errors(source)[..] {
+            [TypeCheckerError::NonExhaustiveMatch(_, witness)] => witness.clone(),
+            errors => panic!("{:?}", errors)
+        }
+    }
+
+    #[test]
+    fn integers_are_bounded_by_the_type_of_the_scrutinee() {
+        assert!(type_errors("mod a; class A { fn f(x: u8) -> u64 { match x { 0..=127 => 1, 128..=255 => 2 } } }").is_empty());
+        assert_eq!(missing_value("mod a; class A { fn f(x: u16) -> u64 { match x { 0..=255 => 1 } } }"), "256..=65535");
+        assert_eq!(missing_value("mod a; class A { fn f(x: i8) -> u64 { match x { 0..=127 => 1 } } }"), "-128..=-1");
+    }
+
+    #[test]
+    fn enum_variants_and_their_fields_are_covered() {
+        let enums = "mod a; enum Opt<T> { Some(T), None } enum Dir { Up, Down }";
+        let source = format!("{} class A {{ fn f(o: Opt<bool>) -> u64 {{ match o {{ Opt::Some(true) => 1, Opt::Some(false) => 2, Opt::None => 3 }} }} }}", enums);
+        assert!(type_errors(&source).is_empty());
+        let source = format!("{} class A {{ fn f(o: Opt<bool>) -> u64 {{ match o {{ Opt::Some(true) => 1, Opt::None => 3 }} }} }}", enums);
+        assert_eq!(missing_value(&source), "Opt::Some(false)");
+        let source = format!("{} class A {{ fn f(d: Dir) -> u64 {{ match d {{ Dir::Up => 1 }} }} }}", enums);
+        assert_eq!(missing_value(&source), "Dir::Down");
+    }
+
+    #[test]
+    fn arms_after_a_catch_all_are_unreachable() {
+        let errors = type_errors("mod a; class A { fn f(b: bool) -> u64 { match b { true => 1, false => 2, _ => 3 } } }");
+        assert!(matches!(errors[..], [TypeCheckerError::UnreachableMatchArm(_)]), "{:?}", errors);
+        // Guards can fail
+        assert!(type_errors("mod a; class A { fn f(b: bool) -> u64 { match b { _ if b => 1, _ => 2 } } }").is_empty());
+    }
+}
@@ -0,0 +1,102 @@
+use crate::tir::type_checker::{TypeChecker, TypeCheckerError, BodyTypes};
+use crate::tir::ast_lowerer::for_lowerer::{expr, variable, binary, method_call, local, statement, block, break_if};
+use crate::tir::{TIRExpr, TIRExprKind, TIROperator, TIRTypeInfo};
+
+// Loops over arrays go through the indexes of the array. Anything else has to implement the standard library's
+// Iterable<T> (fn iter() -> Iterator<T>), with Iterator<T> providing fn has_next() -> bool and fn next() -> T.
+// The iterable is typed before the loop is desugared, it is only stored in the first local of the desugared loop
+// so that it isn't checked twice.
+
+impl<'a, 'b> TypeChecker<'a, 'b> {
+    fn is_iterable(&self, type_info: &TIRTypeInfo) -> bool {
+        match self.type_ref_pool.full_path_to_type_ref_index.get(&vec!["oolang", "Iterable"]) {
+            Some(iterable_type_ref_index) => self.type_ref_pool.as_super(type_info, *iterable_type_ref_index).is_some(),
+            None => false
+        }
+    }
+
+    // Replaces the "for" loop with the loop it is desugared into
+    pub fn check_for(&mut self, for_expr: &mut TIRExpr<'a>, body_types: &mut BodyTypes<'a>) -> Option<TIRTypeInfo> {
+        let span = for_expr.span;
+        let (label, mut iterable, element, [iterable_slot, state_slot], body) = match std::mem::replace(&mut for_expr.kind, TIRExprKind::Null) {
+            TIRExprKind::For(label, iterable, element, temporaries, body) => (label, iterable, element, temporaries, body),
+            _ => unreachable!()
+        };
+        let iterable_type = self.check_expr(&mut iterable, None, body_types);
+        body_types.slots[iterable_slot] = iterable_type.clone();
+
+        let mut statements = vec![];
+        let mut loop_statements = vec![];
+        match &iterable_type {
+            // let $index = 0;
+            // loop { if $index >= length($array) { break; } let name = $array[$index]; $index += 1; body }
+            Some(iterable_type) if iterable_type.remove_array_dim(1).is_some() => {
+                statements.push(local(state_slot, expr(TIRExprKind::Num(0, None), span), span));
+                loop_statements.push(break_if(binary(
+                    variable(state_slot, span),
+                    TIROperator::GtEq,
+                    expr(TIRExprKind::ArrayLength(Box::new(variable(iterable_slot, span))), span),
+                    span
+                ), span));
+                loop_statements.push(local(element, expr(TIRExprKind::Indexing(Box::new(variable(iterable_slot, span)), Box::new(variable(state_slot, span))), span), span));
+                loop_statements.push(statement(binary(variable(state_slot, span), TIROperator::PlusAssign, expr(TIRExprKind::Num(1, None), span), span), span));
+            }
+            // let $iterator = $iterable.iter();
+            // loop { if !$iterator.has_next() { break; } let name = $iterator.next(); body }
+            Some(iterable_type) if self.is_iterable(iterable_type) => {
+                statements.push(local(state_slot, method_call(variable(iterable_slot, span), "iter", span), span));
+                loop_statements.push(break_if(expr(TIRExprKind::PreOp(TIROperator::Not, Box::new(method_call(variable(state_slot, span), "has_next", span))), span), span));
+                loop_statements.push(local(element, method_call(variable(state_slot, span), "next", span), span));
+            }
+            // The body is still checked, with the loop variable left untyped
+            iterable_type => {
+                if let Some(iterable_type) = iterable_type {
+                    self.errors.push(TypeCheckerError::NotIterable(iterable.span, iterable_type.clone()));
+                }
+                *for_expr = expr(TIRExprKind::While(label, Box::new(expr(TIRExprKind::Boolean(false), span)), body), span);
+                self.check_expr(for_expr, None, body_types);
+                return None;
+            }
+        }
+        loop_statements.push(statement(expr(TIRExprKind::Block(body), span), span));
+        statements.push(statement(expr(TIRExprKind::Loop(label, block(loop_statements, span)), span), span));
+
+        let mut desugared = expr(TIRExprKind::Block(block(statements, span)), span);
+        let desugared_type = self.check_expr(&mut desugared, None, body_types);
+        if let TIRExprKind::Block(desugared_block) = &mut desugared.kind {
+            desugared_block.statements.insert(0, local(iterable_slot, *iterable, span));
+        }
+        *for_expr = desugared;
+        desugared_type
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tir::type_checker::TypeCheckerError;
+    use crate::testing::type_errors;
+
+    const ITERABLES: &str = "
+        mod oolang;
+        inter Iterator<T> { pub fn has_next() -> bool; pub fn next() -> T; }
+        inter Iterable<T> { pub fn iter() -> Iterator<T>; }
+        class Countdown impl Iterable<u64> { pub fn iter() -> Iterator<u64> { null } }
+    ";
+
+    #[test]
+    fn arrays_and_iterables_can_be_looped_over() {
+        assert!(type_errors("mod a; class A { fn sum(values: u64[]) -> u64 { let total = 0; for value in values { total += value; } total } }").is_empty());
+        let source = format!("{} class A {{ fn sum(countdown: Countdown) -> u64 {{ let total = 0; for value in countdown {{ total += value; }} total }} }}", ITERABLES);
+        let errors = type_errors(&source);
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn other_values_are_not_iterable() {
+        let errors = type_errors("mod a; class A { fn f(count: u64) { for value in count { value; } } }");
+        assert!(matches!(errors[..], [TypeCheckerError::NotIterable(..)]), "{:?}", errors);
+        // Without the standard library nothing but arrays can be looped over
+        let errors = type_errors("mod a; class A { fn f(a: A) { for value in a { } } }");
+        assert!(matches!(errors[..], [TypeCheckerError::NotIterable(..)]), "{:?}", errors);
+    }
+}
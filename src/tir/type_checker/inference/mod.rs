@@ -0,0 +1,288 @@
+use crate::tir::type_checker::{TypeChecker, TypeCheckerError, BodyTypes, PendingLocal, primitive_type_info, primitive_of, is_never};
+use crate::tir::{TIRExpr, TIRExprKind, TIROperator, TIRTypeInfo, TIRTypeInfoKind, PrimitiveType, TIRStatementBlock, TIRStatementKind};
+use crate::reporting::TokenSpan;
+
+// Unsuffixed literals default to i64 and f64 when nothing says which type they should have.
+// Locals without a type take the type of their initializer, or the type they are first used as when the
+// initializer is such a literal, like the literal would. Generics left out of "new", enum variants and
+// static methods of generic types are taken from the expected type, or inferred from the arguments.
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LiteralKind {
+    Integer,
+    Float,
+    Null
+}
+
+// Literals whose type depends on where they are used
+pub fn literal_kind(expr: &TIRExpr) -> Option<LiteralKind> {
+    match &expr.kind {
+        TIRExprKind::Num(_, None) => Some(LiteralKind::Integer),
+        TIRExprKind::Float(_, None) => Some(LiteralKind::Float),
+        TIRExprKind::Null => Some(LiteralKind::Null),
+        TIRExprKind::PreOp(TIROperator::Minus, expr) => literal_kind(expr).filter(|kind| *kind != LiteralKind::Null),
+        _ => None
+    }
+}
+
+fn mentions_unknown_generics(type_info: &TIRTypeInfo, type_ref_index: usize, generics: &[Option<TIRTypeInfo>]) -> bool {
+    match &type_info.kind {
+        TIRTypeInfoKind::Generic { type_ref_index: generic_type_ref_index, generic_index, .. } =>
+            *generic_type_ref_index == type_ref_index && generics[*generic_index].is_none(),
+        TIRTypeInfoKind::TypeRef { generics: inner_generics, .. } => inner_generics.iter().any(|g| mentions_unknown_generics(g, type_ref_index, generics)),
+        TIRTypeInfoKind::Primitive { .. } => false
+    }
+}
+
+// Matches the parameter with the type of the argument to find the generics of the type the parameter mentions
+fn infer_from(parameter: &TIRTypeInfo, arg_type: &TIRTypeInfo, type_ref_index: usize, generics: &mut Vec<Option<TIRTypeInfo>>) {
+    match (&parameter.kind, &arg_type.kind) {
+        (TIRTypeInfoKind::Generic { type_ref_index: generic_type_ref_index, generic_index, array_dim }, _) if *generic_type_ref_index == type_ref_index => {
+            if generics[*generic_index].is_none() && !is_never(arg_type) {
+                generics[*generic_index] = arg_type.remove_array_dim(*array_dim);
+            }
+        }
+        (
+            TIRTypeInfoKind::TypeRef { type_ref_index: parameter_type_ref_index, generics: parameter_generics, array_dim: parameter_array_dim },
+            TIRTypeInfoKind::TypeRef { type_ref_index: arg_type_ref_index, generics: arg_generics, array_dim: arg_array_dim }
+        ) if parameter_type_ref_index == arg_type_ref_index && parameter_array_dim == arg_array_dim => {
+            for (parameter_generic, arg_generic) in parameter_generics.iter().zip(arg_generics) {
+                infer_from(parameter_generic, arg_generic, type_ref_index, generics);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl<'a, 'b> TypeChecker<'a, 'b> {
+    // Type an unsuffixed literal of that kind takes where it is used
+    pub fn literal_type(&self, kind: LiteralKind, hint: Option<&TIRTypeInfo>, span: TokenSpan) -> Option<TIRTypeInfo> {
+        let hinted_primitive = hint.and_then(primitive_of);
+        match kind {
+            LiteralKind::Integer => Some(primitive_type_info(hinted_primitive.filter(|p| p.is_integer()).unwrap_or(PrimitiveType::I64), span)),
+            LiteralKind::Float => Some(primitive_type_info(hinted_primitive.filter(|p| p.is_float()).unwrap_or(PrimitiveType::F64), span)),
+            LiteralKind::Null => hint.cloned()
+        }
+    }
+
+    // Kind of the literal the expression is, or that the local it accesses is initialized with and still takes its type from
+    pub fn flexible_kind(&self, expr: &TIRExpr<'a>, body_types: &BodyTypes<'a>) -> Option<LiteralKind> {
+        match &expr.kind {
+            TIRExprKind::LocalAccess(slot) => match body_types.pending_locals.get(slot) {
+                Some((PendingLocal::Literal(kind), _)) => Some(*kind),
+                _ => None
+            },
+            _ => literal_kind(expr)
+        }
+    }
+
+    // Expressions that take their type from the other operand rather than give it
+    pub fn is_flexible(&self, expr: &TIRExpr<'a>, body_types: &BodyTypes<'a>) -> bool {
+        self.flexible_kind(expr, body_types).is_some()
+    }
+
+    // Checks the operand that gives its type first, so that literals take the type of the other one
+    pub fn check_operands(&mut self, left: &mut TIRExpr<'a>, right: &mut TIRExpr<'a>, hint: Option<&TIRTypeInfo>, body_types: &mut BodyTypes<'a>) -> (Option<TIRTypeInfo>, Option<TIRTypeInfo>) {
+        if self.is_flexible(left, body_types) && !self.is_flexible(right, body_types) {
+            let right_type = self.check_expr(right, hint, body_types);
+            (self.check_expr(left, right_type.as_ref().or(hint), body_types), right_type)
+        } else {
+            let left_type = self.check_expr(left, hint, body_types);
+            let right_type = self.check_expr(right, left_type.as_ref().or(hint), body_types);
+            (left_type, right_type)
+        }
+    }
+
+    // Evaluates to whether the initializer never completes
+    pub fn check_local(&mut self, slot: usize, type_info: &Option<TIRTypeInfo>, expr: &mut Option<Box<TIRExpr<'a>>>, span: TokenSpan, body_types: &mut BodyTypes<'a>) -> bool {
+        match (type_info, expr) {
+            (Some(type_info), expr) => {
+                body_types.slots[slot] = Some(type_info.clone());
+                match expr {
+                    Some(expr) => {
+                        self.check_expr_against(expr, type_info, body_types);
+                        expr.type_info.as_ref().map_or(false, is_never)
+                    }
+                    None => false
+                }
+            }
+            // The literal is checked once the local is used, see settle_locals
+            (None, Some(expr)) if literal_kind(expr).is_some() => {
+                body_types.pending_locals.insert(slot, (PendingLocal::Literal(literal_kind(expr).unwrap()), span));
+                false
+            }
+            (None, Some(expr)) => {
+                let error_count = self.errors.len();
+                let expr_type = self.check_expr(expr, None, body_types);
+                if expr_type.is_none() && self.errors.len() == error_count {
+                    self.errors.push(TypeCheckerError::TypeAnnotationsNeeded(span));
+                }
+                body_types.slots[slot] = expr_type;
+                expr.type_info.as_ref().map_or(false, is_never)
+            }
+            (None, None) => {
+                body_types.pending_locals.insert(slot, (PendingLocal::Assignment, span));
+                false
+            }
+        }
+    }
+
+    pub fn local_type(&mut self, slot: usize, hint: Option<&TIRTypeInfo>, body_types: &mut BodyTypes<'a>) -> Option<TIRTypeInfo> {
+        match body_types.pending_locals.remove(&slot) {
+            Some((PendingLocal::Literal(kind), span)) => body_types.slots[slot] = self.literal_type(kind, hint, span),
+            // Used before it is assigned
+            Some((PendingLocal::Assignment, span)) => self.errors.push(TypeCheckerError::TypeAnnotationsNeeded(span)),
+            None => {}
+        }
+        body_types.slots[slot].clone()
+    }
+
+    // "name = value" where "name" was declared without a type or a value, evaluates to whether it was
+    pub fn assign_pending_local(&mut self, target: &mut TIRExpr<'a>, value: &mut TIRExpr<'a>, body_types: &mut BodyTypes<'a>) -> bool {
+        let slot = match target.kind {
+            TIRExprKind::LocalAccess(slot) if matches!(body_types.pending_locals.get(&slot), Some((PendingLocal::Assignment, _))) => slot,
+            _ => return false
+        };
+        let (_, span) = body_types.pending_locals.remove(&slot).unwrap();
+        let error_count = self.errors.len();
+        let value_type = self.check_expr(value, None, body_types);
+        if value_type.is_none() && self.errors.len() == error_count {
+            self.errors.push(TypeCheckerError::TypeAnnotationsNeeded(span));
+        }
+        body_types.slots[slot] = value_type.clone();
+        target.type_info = value_type;
+        true
+    }
+
+    // Gives their final type to the locals declared without one in the block, once the block has been checked
+    pub fn settle_locals(&mut self, block: &mut TIRStatementBlock<'a>, body_types: &mut BodyTypes<'a>) {
+        for statement in &mut block.statements {
+            let (slot, type_info, expr) = match &mut statement.kind {
+                TIRStatementKind::Local(slot, type_info, expr) if type_info.is_none() => (*slot, type_info, expr),
+                _ => continue
+            };
+            match body_types.pending_locals.remove(&slot) {
+                // Never used, the literal gets its default type
+                Some((PendingLocal::Literal(kind), span)) => body_types.slots[slot] = self.literal_type(kind, None, span),
+                Some((PendingLocal::Assignment, span)) => self.errors.push(TypeCheckerError::TypeAnnotationsNeeded(span)),
+                None => {}
+            }
+            if let Some(expr) = expr {
+                if expr.type_info.is_none() && literal_kind(expr).is_some() {
+                    match body_types.slots[slot].clone() {
+                        Some(slot_type) => self.check_expr_against(expr, &slot_type, body_types),
+                        None => self.errors.push(TypeCheckerError::TypeAnnotationsNeeded(statement.span))
+                    }
+                }
+            }
+            *type_info = body_types.slots[slot].clone();
+        }
+    }
+
+    // Generics of the type, known ones come from the expected type, the others are inferred from the arguments.
+    // The arguments are checked against the parameters either way, "args_typed" is given when overload resolution
+    // already typed the ones that aren't literals.
+    pub fn check_generic_args(&mut self, type_ref_index: usize, parameters: &[TIRTypeInfo], args: &mut [TIRExpr<'a>], known_generics: Option<Vec<TIRTypeInfo>>, args_typed: bool, span: TokenSpan, body_types: &mut BodyTypes<'a>) -> Option<Vec<TIRTypeInfo>> {
+        let mut generics: Vec<Option<TIRTypeInfo>> = match known_generics {
+            Some(known_generics) => known_generics.into_iter().map(Some).collect(),
+            None => vec![None; self.type_ref_pool.type_refs[type_ref_index].generics.len()]
+        };
+        // Literals go last, so that they take the type the other arguments give to the generics
+        for literals in [false, true] {
+            for (parameter, arg) in parameters.iter().zip(args.iter_mut()) {
+                if self.is_flexible(arg, body_types) != literals {
+                    continue;
+                }
+                let hint = if mentions_unknown_generics(parameter, type_ref_index, &generics) {
+                    None
+                } else {
+                    // The generics that aren't known yet aren't mentioned by the parameter, what stands for them is never used
+                    let known: Vec<TIRTypeInfo> = generics.iter().map(|g| g.clone().unwrap_or_else(|| parameter.clone())).collect();
+                    Some(self.type_ref_pool.substitute_generics(parameter, type_ref_index, &known))
+                };
+                let arg_type = if args_typed && !literals {
+                    arg.type_info.clone()
+                } else {
+                    self.check_expr(arg, hint.as_ref(), body_types)
+                };
+                if let Some(arg_type) = arg_type {
+                    infer_from(parameter, &arg_type, type_ref_index, &mut generics);
+                }
+            }
+        }
+
+        let generics = match generics.into_iter().collect::<Option<Vec<TIRTypeInfo>>>() {
+            Some(generics) => generics,
+            None => {
+                self.errors.push(TypeCheckerError::TypeAnnotationsNeeded(span));
+                return None;
+            }
+        };
+        for (generic, type_ref_generic) in generics.iter().zip(&self.type_ref_pool.type_refs[type_ref_index].generics) {
+            for requirement in &type_ref_generic.super_requirements {
+                let requirement = self.type_ref_pool.substitute_generics(requirement, type_ref_index, &generics);
+                if !self.type_ref_pool.check_assignable_to(generic, &requirement) {
+                    self.errors.push(TypeCheckerError::MismatchedTypes(span, requirement, generic.clone()));
+                }
+            }
+        }
+        for (parameter, arg) in parameters.iter().zip(args.iter()) {
+            let parameter = self.type_ref_pool.substitute_generics(parameter, type_ref_index, &generics);
+            if let Some(arg_type) = &arg.type_info {
+                if !self.is_passable(arg_type, &parameter) {
+                    self.errors.push(TypeCheckerError::MismatchedTypes(arg.span, parameter, arg_type.clone()));
+                }
+            }
+        }
+        Some(generics)
+    }
+
+    // Generics given by the expected type when it is the same generic type
+    pub fn hinted_generics(&self, type_ref_index: usize, hint: Option<&TIRTypeInfo>) -> Option<Vec<TIRTypeInfo>> {
+        match hint.map(|h| &h.kind) {
+            Some(TIRTypeInfoKind::TypeRef { type_ref_index: hint_type_ref_index, generics, array_dim: 0 })
+                if *hint_type_ref_index == type_ref_index && !generics.is_empty() => Some(generics.clone()),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tir::type_checker::TypeCheckerError;
+    use crate::testing::type_errors;
+
+    const SOURCE: &str = "mod a; class A { fn f() { BODY } }";
+
+    #[test]
+    fn untyped_locals_take_the_type_they_are_used_as() {
+        for body in ["let x = 1; let y: u8 = x;", "let x = 1.5; let y: f32 = x;", "let x; x = true; let y: bool = x;", "let x = 1; let y = x + 2u16;"] {
+            assert!(type_errors(&SOURCE.replace("BODY", body)).is_empty(), "{}", body);
+        }
+        let source = SOURCE.replace("BODY", "let x = 1; let y: u8 = x; let z: u16 = x;");
+        assert!(matches!(type_errors(&source)[..], [TypeCheckerError::MismatchedTypes(..)]));
+        let source = SOURCE.replace("BODY", "let x = 300; let y: u8 = x;");
+        assert!(matches!(type_errors(&source)[..], [TypeCheckerError::LiteralOutOfRange(..)]));
+    }
+
+    #[test]
+    fn locals_without_a_known_type_need_annotations() {
+        for body in ["let x;", "let x = null;", "let x; let y = x;"] {
+            let source = SOURCE.replace("BODY", body);
+            let errors = type_errors(&source);
+            assert!(matches!(errors[..], [TypeCheckerError::TypeAnnotationsNeeded(_)]), "{}: {:?}", body, errors);
+        }
+    }
+
+    #[test]
+    fn generics_are_inferred_from_arguments_and_expected_types() {
+        let source = "mod a; class Wrapper<T> { pub inside: T; pub Wrapper(inside: T) { this.inside = inside; } } class A { fn f() { BODY } }";
+        for body in ["let w = new Wrapper(true); let b: bool = w.inside;", "let w: Wrapper<u8> = new Wrapper(1);", "let w = new Wrapper(new A()); let a: A = w.inside;"] {
+            let source = source.replace("BODY", body);
+            assert!(type_errors(&source).is_empty(), "{}: {:?}", body, type_errors(&source));
+        }
+        let source = source.replace("BODY", "let w = new Wrapper(null);");
+        assert!(matches!(type_errors(&source)[..], [TypeCheckerError::TypeAnnotationsNeeded(_)]));
+    }
+}
+
@@ -0,0 +1,983 @@
+use crate::tir::{TIRRoot, TIRTypeKind, TIRMemberKind, TIRModifier, TIRNameAndType, TIRTypeInfo, TIRTypeInfoKind, PrimitiveType, TIRExpr, TIRExprKind, TIROperator, TIRStatementBlock, TIRStatementKind, TIRPattern, TIRPatternKind};
+use crate::type_ref_pool::{TypeRefPool, TypeRefKind, type_ref_index_of};
+use crate::field_ref_pool::FieldRefPool;
+use crate::method_ref_pool::MethodRefPool;
+use crate::reporting::TokenSpan;
+use crate::tir::type_checker::inference::LiteralKind;
+use std::collections::HashMap;
+
+pub mod inference;
+pub mod overload_resolver;
+pub mod for_checker;
+pub mod exhaustiveness;
+
+// Runs between the lowerer and codegen. Every expression gets its type, members that the lowerer couldn't
+// resolve because the type of the expression they're accessed on wasn't known are resolved here.
+// Expressions whose type can't be determined are left untyped, checks involving them are skipped so that
+// one error doesn't cause others.
+
+fn primitive_type_info(primitive: PrimitiveType, span: TokenSpan) -> TIRTypeInfo {
+    TIRTypeInfo {
+        kind: TIRTypeInfoKind::Primitive {
+            primitive,
+            array_dim: 0
+        },
+        span
+    }
+}
+
+fn primitive_of(type_info: &TIRTypeInfo) -> Option<PrimitiveType> {
+    match &type_info.kind {
+        TIRTypeInfoKind::Primitive { primitive, array_dim: 0 } => Some(*primitive),
+        _ => None
+    }
+}
+
+fn is_never(type_info: &TIRTypeInfo) -> bool {
+    primitive_of(type_info) == Some(PrimitiveType::Never)
+}
+
+fn mentions_generics_of(type_info: &TIRTypeInfo, type_ref_index: usize) -> bool {
+    match &type_info.kind {
+        TIRTypeInfoKind::Generic { type_ref_index: generic_type_ref_index, .. } => *generic_type_ref_index == type_ref_index,
+        TIRTypeInfoKind::TypeRef { generics, .. } => generics.iter().any(|g| mentions_generics_of(g, type_ref_index)),
+        TIRTypeInfoKind::Primitive { .. } => false
+    }
+}
+
+fn is_assignment_target(expr: &TIRExpr) -> bool {
+    match &expr.kind {
+        TIRExprKind::LocalAccess(_) | TIRExprKind::FieldAccess(_, _) | TIRExprKind::StaticFieldAccess(_) | TIRExprKind::Indexing(_, _) => true,
+        _ => false
+    }
+}
+
+#[derive(Debug)]
+pub enum TypeCheckerError<'a> {
+    // Expected type and the type that was found
+    MismatchedTypes(TokenSpan, TIRTypeInfo, TIRTypeInfo),
+    // Types of the two branches that should have the same type
+    MismatchedBranches(TokenSpan, TIRTypeInfo, TIRTypeInfo),
+    InvalidOperands(TokenSpan, TIROperator),
+    InvalidAssignmentTarget(TokenSpan),
+    NullForPrimitive(TokenSpan, TIRTypeInfo),
+    LiteralOutOfRange(TokenSpan, PrimitiveType),
+    NotIndexable(TokenSpan, TIRTypeInfo),
+    // Neither an array nor an oolang::Iterable
+    NotIterable(TokenSpan, TIRTypeInfo),
+    // Ranges are only used by "for" loops
+    RangeOutsideFor(TokenSpan),
+    TypeUsedAsValue(TokenSpan),
+    NotCallable(TokenSpan),
+    NoSuchField(TokenSpan, &'a str),
+    NoSuchMethod(TokenSpan, &'a str),
+    // None of the overloads can take the arguments
+    NoApplicableMethod(TokenSpan, &'a str),
+    NoApplicableConstructor(TokenSpan),
+    // Method ref indexes or constructor indexes of the overloads that fit the arguments equally well
+    AmbiguousMethodCall(TokenSpan, &'a str, Vec<usize>),
+    AmbiguousConstructorCall(TokenSpan, Vec<usize>),
+    MethodWithoutCall(TokenSpan, &'a str),
+    StaticMemberAccessedOnInstance(TokenSpan, &'a str),
+    InaccessibleField(TokenSpan, &'a str),
+    InaccessibleMethod(TokenSpan, &'a str),
+    TypeAnnotationsNeeded(TokenSpan),
+    // The string is an example of a value that isn't matched
+    NonExhaustiveMatch(TokenSpan, String),
+    UnreachableMatchArm(TokenSpan),
+    // String literals are instances of oolang::String, which wasn't declared
+    NoStringType(TokenSpan)
+}
+
+impl<'a> TypeCheckerError<'a> {
+    pub fn span(&self) -> TokenSpan {
+        match self {
+            TypeCheckerError::MismatchedTypes(span, _, _) |
+            TypeCheckerError::MismatchedBranches(span, _, _) |
+            TypeCheckerError::InvalidOperands(span, _) |
+            TypeCheckerError::InvalidAssignmentTarget(span) |
+            TypeCheckerError::NullForPrimitive(span, _) |
+            TypeCheckerError::LiteralOutOfRange(span, _) |
+            TypeCheckerError::NotIndexable(span, _) |
+            TypeCheckerError::NotIterable(span, _) |
+            TypeCheckerError::RangeOutsideFor(span) |
+            TypeCheckerError::TypeUsedAsValue(span) |
+            TypeCheckerError::NotCallable(span) |
+            TypeCheckerError::NoSuchField(span, _) |
+            TypeCheckerError::NoSuchMethod(span, _) |
+            TypeCheckerError::NoApplicableMethod(span, _) |
+            TypeCheckerError::NoApplicableConstructor(span) |
+            TypeCheckerError::AmbiguousMethodCall(span, _, _) |
+            TypeCheckerError::AmbiguousConstructorCall(span, _) |
+            TypeCheckerError::MethodWithoutCall(span, _) |
+            TypeCheckerError::StaticMemberAccessedOnInstance(span, _) |
+            TypeCheckerError::InaccessibleField(span, _) |
+            TypeCheckerError::InaccessibleMethod(span, _) |
+            TypeCheckerError::TypeAnnotationsNeeded(span) |
+            TypeCheckerError::NonExhaustiveMatch(span, _) |
+            TypeCheckerError::UnreachableMatchArm(span) |
+            TypeCheckerError::NoStringType(span) => *span
+        }
+    }
+}
+
+struct LoopTypes<'a> {
+    label: Option<&'a str>,
+    // Type of the values the loop is broken out of with, if it is known yet
+    break_type: Option<TIRTypeInfo>,
+    // A loop that is never broken out of never produces a value
+    broken: bool
+}
+
+// Locals declared without a type whose type is only known once they are used
+enum PendingLocal {
+    // Initialized with a literal that takes its type from the first use of the local
+    Literal(LiteralKind),
+    // Declared without a value, it gets the type of the first value assigned to it
+    Assignment
+}
+
+// Types of the locals and the loops in scope while checking a method body or a field initializer
+pub struct BodyTypes<'a> {
+    type_ref_index: usize,
+    // Indexed by slot, None until the local is declared or when its type isn't known
+    slots: Vec<Option<TIRTypeInfo>>,
+    // With the span of their declaration
+    pending_locals: HashMap<usize, (PendingLocal, TokenSpan)>,
+    // Innermost loop last
+    loops: Vec<LoopTypes<'a>>,
+    return_type: Option<TIRTypeInfo>
+}
+
+pub struct TypeChecker<'a, 'b> {
+    type_ref_pool: &'b TypeRefPool<'a>,
+    field_ref_pool: &'b FieldRefPool<'a>,
+    method_ref_pool: &'b MethodRefPool<'a>,
+
+    errors: Vec<TypeCheckerError<'a>>
+}
+
+impl<'a, 'b> TypeChecker<'a, 'b> {
+    pub fn new(type_ref_pool: &'b TypeRefPool<'a>, field_ref_pool: &'b FieldRefPool<'a>, method_ref_pool: &'b MethodRefPool<'a>) -> Self {
+        Self {
+            type_ref_pool,
+            field_ref_pool,
+            method_ref_pool,
+            errors: vec![]
+        }
+    }
+
+    pub fn check(mut self, tir_root: &mut TIRRoot<'a>) -> Vec<TypeCheckerError<'a>> {
+        for tir_type in &mut tir_root.types {
+            let type_ref_index = tir_type.type_ref_index;
+            let members = match &mut tir_type.kind {
+                TIRTypeKind::Class { members, .. } | TIRTypeKind::Interface { members, .. } => members,
+                TIRTypeKind::Enum { .. } => continue
+            };
+            for member in members {
+                let is_static = member.modifiers.iter().any(|m| matches!(m, TIRModifier::Static));
+                match &mut member.kind {
+                    TIRMemberKind::Field { name_and_type, expression, local_count } => if let Some(expression) = expression {
+                        let mut body_types = self.body_types(type_ref_index, is_static, &[], *local_count, None, member.span);
+                        self.check_expr_against(expression, &name_and_type.type_info, &mut body_types);
+                    }
+                    TIRMemberKind::Method { name_and_type, parameters, block, local_count } => if let Some(block) = block {
+                        let return_type = name_and_type.type_info.clone();
+                        let mut body_types = self.body_types(type_ref_index, is_static, parameters, *local_count, Some(return_type.clone()), member.span);
+                        self.check_body(block, &return_type, &mut body_types);
+                    }
+                    TIRMemberKind::Constructor { parameters, super_call, block, local_count } => {
+                        let void = primitive_type_info(PrimitiveType::Void, block.span);
+                        let mut body_types = self.body_types(type_ref_index, false, parameters, *local_count, Some(void.clone()), member.span);
+                        if let Some((constructor_indexes, args)) = super_call {
+                            let super_class = match &self.type_ref_pool.type_refs[type_ref_index].kind {
+                                TypeRefKind::Class(class_type_ref) => class_type_ref.super_class.clone().unwrap(),
+                                _ => unreachable!()
+                            };
+                            let super_type_ref_index = type_ref_index_of(&super_class).unwrap();
+                            let constructors = match &self.type_ref_pool.type_refs[super_type_ref_index].kind {
+                                TypeRefKind::Class(class_type_ref) => &class_type_ref.constructors,
+                                _ => unreachable!()
+                            };
+                            let parameters: Vec<Vec<Option<TIRTypeInfo>>> = constructor_indexes.iter()
+                                .map(|constructor_index| constructors[*constructor_index].iter()
+                                    .map(|p| self.member_type(p, super_type_ref_index, Some(&super_class)))
+                                    .collect())
+                                .collect();
+                            if let [parameters] = &parameters[..] {
+                                self.check_args(args, parameters, &mut body_types);
+                            } else {
+                                let span = member.span;
+                                let candidates = constructor_indexes.clone();
+                                if let Some((position, arg_types)) = self.pick_overload(&parameters, args, &mut body_types, |equally_good| if equally_good.is_empty() {
+                                    TypeCheckerError::NoApplicableConstructor(span)
+                                } else {
+                                    TypeCheckerError::AmbiguousConstructorCall(span, equally_good.iter().map(|i| candidates[*i]).collect())
+                                }) {
+                                    self.check_flexible_args(args, &arg_types, &parameters[position], &mut body_types);
+                                    *constructor_indexes = vec![constructor_indexes[position]];
+                                }
+                            }
+                        }
+                        self.check_body(block, &void, &mut body_types);
+                    }
+                }
+            }
+        }
+
+        self.errors
+    }
+
+    fn string_type(&mut self, span: TokenSpan) -> Option<TIRTypeInfo> {
+        match self.type_ref_pool.full_path_to_type_ref_index.get(&vec!["oolang", "String"]) {
+            Some(type_ref_index) => Some(TIRTypeInfo {
+                kind: TIRTypeInfoKind::TypeRef {
+                    type_ref_index: *type_ref_index,
+                    generics: vec![],
+                    array_dim: 0
+                },
+                span
+            }),
+            None => {
+                self.errors.push(TypeCheckerError::NoStringType(span));
+                None
+            }
+        }
+    }
+
+    fn this_type(&self, type_ref_index: usize, span: TokenSpan) -> TIRTypeInfo {
+        TIRTypeInfo {
+            kind: TIRTypeInfoKind::TypeRef {
+                type_ref_index,
+                generics: (0..self.type_ref_pool.type_refs[type_ref_index].generics.len()).map(|generic_index| TIRTypeInfo {
+                    kind: TIRTypeInfoKind::Generic {
+                        type_ref_index,
+                        generic_index,
+                        array_dim: 0
+                    },
+                    span
+                }).collect(),
+                array_dim: 0
+            },
+            span
+        }
+    }
+
+    // Slots start with "this" (unless static) and the parameters, like in the lowerer
+    fn body_types(&self, type_ref_index: usize, is_static: bool, parameters: &[TIRNameAndType<'a>], local_count: usize, return_type: Option<TIRTypeInfo>, span: TokenSpan) -> BodyTypes<'a> {
+        let mut slots = vec![None; local_count];
+        let first_parameter_slot = if is_static {
+            0
+        } else {
+            slots[0] = Some(self.this_type(type_ref_index, span));
+            1
+        };
+        for (i, parameter) in parameters.iter().enumerate() {
+            slots[first_parameter_slot + i] = Some(parameter.type_info.clone());
+        }
+
+        BodyTypes {
+            type_ref_index,
+            slots,
+            pending_locals: HashMap::new(),
+            loops: vec![],
+            return_type
+        }
+    }
+
+    // The body evaluates to the return value, unless it never completes
+    fn check_body(&mut self, block: &mut TIRStatementBlock<'a>, return_type: &TIRTypeInfo, body_types: &mut BodyTypes<'a>) {
+        if let Some(block_type) = self.check_block(block, Some(return_type), body_types) {
+            if !self.is_assignable(&block_type, return_type) {
+                let span = match block.statements.last() {
+                    Some(statement) if statement.ending => statement.span,
+                    _ => block.span
+                };
+                self.errors.push(TypeCheckerError::MismatchedTypes(span, return_type.clone(), block_type));
+            }
+        }
+    }
+
+    fn is_assignable(&self, found: &TIRTypeInfo, expected: &TIRTypeInfo) -> bool {
+        is_never(found) || self.type_ref_pool.check_assignable_to(found, expected)
+    }
+
+    // Type of a member declared in a type, as seen through the type it is accessed on (or one of its subtypes)
+    fn member_type(&self, type_info: &TIRTypeInfo, declaring_type_ref_index: usize, receiver: Option<&TIRTypeInfo>) -> Option<TIRTypeInfo> {
+        let declaring_type = receiver.and_then(|r| self.type_ref_pool.as_super(r, declaring_type_ref_index));
+        match declaring_type.as_ref().map(|t| &t.kind) {
+            Some(TIRTypeInfoKind::TypeRef { generics, .. }) if !generics.is_empty() =>
+                Some(self.type_ref_pool.substitute_generics(type_info, declaring_type_ref_index, generics)),
+            _ if mentions_generics_of(type_info, declaring_type_ref_index) => None,
+            _ => Some(type_info.clone())
+        }
+    }
+
+    // Types whose members can be accessed on a value, generics give access to the members of their requirements
+    fn receiver_types(&self, type_info: &TIRTypeInfo) -> Vec<TIRTypeInfo> {
+        match &type_info.kind {
+            TIRTypeInfoKind::TypeRef { array_dim: 0, .. } => vec![type_info.clone()],
+            TIRTypeInfoKind::Generic { type_ref_index, generic_index, array_dim: 0 } =>
+                self.type_ref_pool.type_refs[*type_ref_index].generics[*generic_index].super_requirements.clone(),
+            _ => vec![]
+        }
+    }
+
+    fn find_field(&self, type_info: &TIRTypeInfo, name: &str) -> Option<usize> {
+        self.receiver_types(type_info).iter().filter_map(type_ref_index_of)
+            .find_map(|type_ref_index| self.field_ref_pool.find_field(&self.type_ref_pool.type_hierarchy(type_ref_index), name))
+    }
+
+    fn find_methods(&self, type_info: &TIRTypeInfo, name: &str) -> Vec<usize> {
+        let mut methods = vec![];
+        for type_ref_index in self.receiver_types(type_info).iter().filter_map(type_ref_index_of) {
+            for method_ref_index in self.method_ref_pool.find_methods(&self.type_ref_pool.type_hierarchy(type_ref_index), name) {
+                if !methods.contains(&method_ref_index) {
+                    methods.push(method_ref_index);
+                }
+            }
+        }
+        methods
+    }
+
+    fn check_expr_against(&mut self, expr: &mut TIRExpr<'a>, expected: &TIRTypeInfo, body_types: &mut BodyTypes<'a>) {
+        if let Some(found) = self.check_expr(expr, Some(expected), body_types) {
+            if !self.is_assignable(&found, expected) {
+                self.errors.push(TypeCheckerError::MismatchedTypes(expr.span, expected.clone(), found));
+            }
+        }
+    }
+
+    // Like check_expr_against, arguments can also be widened to their parameter
+    fn check_arg_against(&mut self, arg: &mut TIRExpr<'a>, parameter: &TIRTypeInfo, body_types: &mut BodyTypes<'a>) {
+        if let Some(found) = self.check_expr(arg, Some(parameter), body_types) {
+            if !self.is_passable(&found, parameter) {
+                self.errors.push(TypeCheckerError::MismatchedTypes(arg.span, parameter.clone(), found));
+            }
+        }
+    }
+
+    // Arguments whose parameter type isn't known are still checked on their own
+    fn check_args(&mut self, args: &mut [TIRExpr<'a>], parameters: &[Option<TIRTypeInfo>], body_types: &mut BodyTypes<'a>) {
+        for (arg, parameter) in args.iter_mut().zip(parameters) {
+            match parameter {
+                Some(parameter) => self.check_arg_against(arg, parameter, body_types),
+                None => {
+                    self.check_expr(arg, None, body_types);
+                }
+            }
+        }
+    }
+
+    // The type both branches have, the most general of the two or the expected type if they are both one of it.
+    // A branch that never completes takes the type of the other one.
+    fn unify(&mut self, first: Option<TIRTypeInfo>, second: Option<TIRTypeInfo>, hint: Option<&TIRTypeInfo>, span: TokenSpan) -> Option<TIRTypeInfo> {
+        match (first, second) {
+            (Some(first), second) if is_never(&first) => second,
+            (first, Some(second)) if is_never(&second) => first,
+            (Some(first), Some(second)) => if self.is_assignable(&second, &first) {
+                Some(first)
+            } else if self.is_assignable(&first, &second) {
+                Some(second)
+            } else if let Some(hint) = hint.filter(|hint| self.is_assignable(&first, hint) && self.is_assignable(&second, hint)) {
+                Some(hint.clone())
+            } else {
+                self.errors.push(TypeCheckerError::MismatchedBranches(span, first, second));
+                None
+            },
+            _ => None
+        }
+    }
+
+    fn check_block(&mut self, block: &mut TIRStatementBlock<'a>, hint: Option<&TIRTypeInfo>, body_types: &mut BodyTypes<'a>) -> Option<TIRTypeInfo> {
+        let mut diverges = false;
+        let mut ending_type = None;
+        for statement in &mut block.statements {
+            match &mut statement.kind {
+                TIRStatementKind::Local(slot, type_info, expr) => diverges |= self.check_local(*slot, type_info, expr, statement.span, body_types),
+                TIRStatementKind::Expression(expr) => if statement.ending {
+                    ending_type = Some(self.check_expr(expr, hint, body_types));
+                } else {
+                    diverges |= self.check_expr(expr, None, body_types).as_ref().map_or(false, is_never);
+                }
+            }
+        }
+        self.settle_locals(block, body_types);
+
+        match ending_type {
+            Some(ending_type) => ending_type,
+            None if diverges => Some(primitive_type_info(PrimitiveType::Never, block.span)),
+            None => Some(primitive_type_info(PrimitiveType::Void, block.span))
+        }
+    }
+
+    fn check_condition(&mut self, condition: &mut TIRExpr<'a>, body_types: &mut BodyTypes<'a>) {
+        let boolean = primitive_type_info(PrimitiveType::Boolean, condition.span);
+        self.check_expr_against(condition, &boolean, body_types);
+    }
+
+    // Gives their type to the locals bound by the pattern
+    fn bind_pattern(&mut self, pattern: &TIRPattern, scrutinee_type: Option<&TIRTypeInfo>, body_types: &mut BodyTypes<'a>) {
+        match &pattern.kind {
+            TIRPatternKind::Binding(slot) => body_types.slots[*slot] = scrutinee_type.cloned(),
+            TIRPatternKind::TypeTest(Some(slot), type_info) => body_types.slots[*slot] = Some(type_info.clone()),
+            TIRPatternKind::EnumVariant(type_ref_index, variant_index, patterns) => {
+                let fields = match &self.type_ref_pool.type_refs[*type_ref_index].kind {
+                    TypeRefKind::Enum(enum_type_ref) => &enum_type_ref.variants[*variant_index].fields,
+                    _ => unreachable!()
+                };
+                let fields: Vec<Option<TIRTypeInfo>> = fields.iter().map(|f| self.member_type(f, *type_ref_index, scrutinee_type)).collect();
+                for (pattern, field) in patterns.iter().zip(fields) {
+                    self.bind_pattern(pattern, field.as_ref(), body_types);
+                }
+            }
+            TIRPatternKind::Or(patterns) => for pattern in patterns {
+                self.bind_pattern(pattern, scrutinee_type, body_types);
+            }
+            _ => {}
+        }
+    }
+
+    fn integer_literal_type(&mut self, value: u64, suffix: Option<PrimitiveType>, negated: bool, hint: Option<&TIRTypeInfo>, span: TokenSpan) -> Option<TIRTypeInfo> {
+        // Suffixed literals are checked by the lowerer
+        if let Some(suffix) = suffix {
+            return Some(primitive_type_info(suffix, span));
+        }
+        let primitive = hint.and_then(primitive_of).filter(|p| p.is_integer()).unwrap_or(PrimitiveType::I64);
+        if !primitive.integer_literal_in_range(value, negated) {
+            self.errors.push(TypeCheckerError::LiteralOutOfRange(span, primitive));
+        }
+        Some(primitive_type_info(primitive, span))
+    }
+
+    fn float_literal_type(&mut self, value: f64, suffix: Option<PrimitiveType>, hint: Option<&TIRTypeInfo>, span: TokenSpan) -> Option<TIRTypeInfo> {
+        if let Some(suffix) = suffix {
+            return Some(primitive_type_info(suffix, span));
+        }
+        let primitive = hint.and_then(primitive_of).filter(|p| p.is_float()).unwrap_or(PrimitiveType::F64);
+        if primitive == PrimitiveType::F32 && !(value as f32).is_finite() {
+            self.errors.push(TypeCheckerError::LiteralOutOfRange(span, primitive));
+        }
+        Some(primitive_type_info(primitive, span))
+    }
+
+    fn field_type(&self, on_type: Option<&TIRTypeInfo>, field_ref_index: usize) -> Option<TIRTypeInfo> {
+        let field_ref = &self.field_ref_pool.field_refs[field_ref_index];
+        self.member_type(&field_ref.type_info, field_ref.associated_type_ref_index, on_type)
+    }
+
+    // Parameters of the method as seen through the type it is called on, the ones mentioning unknown generics aren't known
+    fn method_parameters(&self, on_type: Option<&TIRTypeInfo>, method_ref_index: usize) -> Vec<Option<TIRTypeInfo>> {
+        let method_ref = &self.method_ref_pool.method_refs[method_ref_index];
+        method_ref.parameters.iter()
+            .map(|p| self.member_type(p, method_ref.associated_type_ref_index, on_type))
+            .collect()
+    }
+
+    // Checks the arguments against the parameters, evaluates to the return type
+    fn method_call_type(&mut self, on_type: Option<&TIRTypeInfo>, method_ref_index: usize, args: &mut [TIRExpr<'a>], body_types: &mut BodyTypes<'a>) -> Option<TIRTypeInfo> {
+        let method_ref = &self.method_ref_pool.method_refs[method_ref_index];
+        let parameters = self.method_parameters(on_type, method_ref_index);
+        let return_type = self.member_type(&method_ref.return_type, method_ref.associated_type_ref_index, on_type);
+        self.check_args(args, &parameters, body_types);
+        return_type
+    }
+
+    // Static methods mentioning the generics of their type get them from their arguments
+    fn is_generic_static_method(&self, method_ref_index: usize) -> bool {
+        let method_ref = &self.method_ref_pool.method_refs[method_ref_index];
+        method_ref.is_static && method_ref.parameters.iter().chain(std::iter::once(&method_ref.return_type))
+            .any(|t| mentions_generics_of(t, method_ref.associated_type_ref_index))
+    }
+
+    fn generic_static_method_call_type(&mut self, method_ref_index: usize, args: &mut [TIRExpr<'a>], args_typed: bool, span: TokenSpan, body_types: &mut BodyTypes<'a>) -> Option<TIRTypeInfo> {
+        let method_ref_pool = self.method_ref_pool;
+        let method_ref = &method_ref_pool.method_refs[method_ref_index];
+        let declaring_type_ref_index = method_ref.associated_type_ref_index;
+        let generics = self.check_generic_args(declaring_type_ref_index, &method_ref.parameters, args, None, args_typed, span, body_types)?;
+        Some(self.type_ref_pool.substitute_generics(&method_ref.return_type, declaring_type_ref_index, &generics))
+    }
+
+    // Generics of "new" that are given, or that the expected type gives when they are left out ("new Wrapper(10)")
+    fn known_new_generics(&self, type_info: &TIRTypeInfo, hint: Option<&TIRTypeInfo>) -> Option<Vec<TIRTypeInfo>> {
+        match &type_info.kind {
+            TIRTypeInfoKind::TypeRef { type_ref_index, generics, .. } if generics.is_empty() => self.hinted_generics(*type_ref_index, hint),
+            TIRTypeInfoKind::TypeRef { generics, .. } => Some(generics.clone()),
+            _ => unreachable!()
+        }
+    }
+
+    // Checks the arguments of "new" against the constructor, evaluates to the type with its generics
+    fn new_type(&mut self, type_info: &mut TIRTypeInfo, constructor_index: usize, args: &mut [TIRExpr<'a>], args_typed: bool, hint: Option<&TIRTypeInfo>, span: TokenSpan, body_types: &mut BodyTypes<'a>) -> Option<TIRTypeInfo> {
+        let type_ref_pool = self.type_ref_pool;
+        let type_ref_index = type_ref_index_of(type_info).unwrap();
+        let parameters = match &type_ref_pool.type_refs[type_ref_index].kind {
+            TypeRefKind::Class(class_type_ref) => &class_type_ref.constructors[constructor_index],
+            _ => unreachable!()
+        };
+        let known_generics = self.known_new_generics(type_info, hint);
+        let generics = self.check_generic_args(type_ref_index, parameters, args, known_generics, args_typed, span, body_types)?;
+        if let TIRTypeInfoKind::TypeRef { generics: type_info_generics, .. } = &mut type_info.kind {
+            *type_info_generics = generics;
+        }
+        Some(type_info.clone())
+    }
+
+    fn check_assignment_target(&mut self, target: &TIRExpr<'a>) {
+        if !is_assignment_target(target) {
+            self.errors.push(TypeCheckerError::InvalidAssignmentTarget(target.span));
+        }
+    }
+
+    // Operands of "+", "==", "+=", ... that are both known
+    fn binary_operator_type(&mut self, operator: &TIROperator, left: &TIRTypeInfo, right: &TIRTypeInfo, span: TokenSpan) -> Option<TIRTypeInfo> {
+        let result = match (primitive_of(left), primitive_of(right)) {
+            (Some(left), Some(right)) => operator.binary_result(left, right),
+            // References are only compared by identity
+            (None, None) if matches!(operator, TIROperator::Eq | TIROperator::NotEq) &&
+                (self.is_assignable(left, right) || self.is_assignable(right, left)) => Some(PrimitiveType::Boolean),
+            _ => None
+        };
+        match result {
+            Some(result) => Some(primitive_type_info(result, span)),
+            None => {
+                self.errors.push(TypeCheckerError::InvalidOperands(span, operator.clone()));
+                None
+            }
+        }
+    }
+
+    // Operand of "++" and "--"
+    fn check_step_operand(&mut self, operator: &TIROperator, operand: &mut TIRExpr<'a>, span: TokenSpan, body_types: &mut BodyTypes<'a>) -> Option<TIRTypeInfo> {
+        let operand_type = self.check_expr(operand, None, body_types);
+        self.check_assignment_target(operand);
+        let operand_type = operand_type?;
+        if primitive_of(&operand_type).map_or(false, |p| p.is_integer()) {
+            Some(operand_type)
+        } else {
+            self.errors.push(TypeCheckerError::InvalidOperands(span, operator.clone()));
+            None
+        }
+    }
+
+    // Evaluates to the type of the expression and stores it in the expression
+    fn check_expr(&mut self, expr: &mut TIRExpr<'a>, hint: Option<&TIRTypeInfo>, body_types: &mut BodyTypes<'a>) -> Option<TIRTypeInfo> {
+        let type_info = self.expr_type(expr, hint, body_types);
+        expr.type_info = type_info.clone();
+        type_info
+    }
+
+    fn expr_type(&mut self, expr: &mut TIRExpr<'a>, hint: Option<&TIRTypeInfo>, body_types: &mut BodyTypes<'a>) -> Option<TIRTypeInfo> {
+        let span = expr.span;
+        match &mut expr.kind {
+            TIRExprKind::StringLiteral(_) => self.string_type(span),
+            TIRExprKind::CharLiteral(_) => Some(primitive_type_info(PrimitiveType::Character, span)),
+            TIRExprKind::Num(value, suffix) => self.integer_literal_type(*value, *suffix, false, hint, span),
+            TIRExprKind::Float(value, suffix) => self.float_literal_type(*value, *suffix, hint, span),
+            TIRExprKind::Boolean(_) => Some(primitive_type_info(PrimitiveType::Boolean, span)),
+            TIRExprKind::Null => match hint {
+                Some(hint) if hint.is_reference() => Some(hint.clone()),
+                Some(hint) => {
+                    self.errors.push(TypeCheckerError::NullForPrimitive(span, hint.clone()));
+                    None
+                }
+                None => None
+            },
+            TIRExprKind::This => Some(self.this_type(body_types.type_ref_index, span)),
+            TIRExprKind::Super => match &self.type_ref_pool.type_refs[body_types.type_ref_index].kind {
+                TypeRefKind::Class(class_type_ref) => class_type_ref.super_class.clone(),
+                _ => unreachable!()
+            },
+
+            TIRExprKind::BinOp(left, operator, right) => match operator {
+                TIROperator::Assign => {
+                    if !self.assign_pending_local(left, right, body_types) {
+                        let (left_type, right_type) = self.check_operands(left, right, None, body_types);
+                        self.check_assignment_target(left);
+                        if let (Some(left_type), Some(right_type)) = (left_type, right_type) {
+                            if !self.is_assignable(&right_type, &left_type) {
+                                self.errors.push(TypeCheckerError::MismatchedTypes(right.span, left_type, right_type));
+                            }
+                        }
+                    }
+                    Some(primitive_type_info(PrimitiveType::Void, span))
+                }
+                TIROperator::And | TIROperator::Or => {
+                    self.check_condition(left, body_types);
+                    self.check_condition(right, body_types);
+                    Some(primitive_type_info(PrimitiveType::Boolean, span))
+                }
+                _ => {
+                    let compound_operator = operator.compound_operator();
+                    // The result of a comparison says nothing about the type of its operands
+                    let hint = if operator.is_comparison() || compound_operator.is_some() { None } else { hint };
+                    let error_count = self.errors.len();
+                    let (left_type, right_type) = self.check_operands(left, right, hint, body_types);
+                    if compound_operator.is_some() {
+                        self.check_assignment_target(left);
+                    }
+                    let result = match (&left_type, &right_type) {
+                        (Some(left_type), Some(right_type)) =>
+                            self.binary_operator_type(compound_operator.as_ref().unwrap_or(operator), left_type, right_type, span),
+                        // Neither operand tells what the other one is, like in "null == null"
+                        (None, None) if self.errors.len() == error_count => {
+                            self.errors.push(TypeCheckerError::TypeAnnotationsNeeded(span));
+                            None
+                        }
+                        _ if operator.is_comparison() => Some(primitive_type_info(PrimitiveType::Boolean, span)),
+                        _ => None
+                    };
+                    match compound_operator {
+                        Some(_) => Some(primitive_type_info(PrimitiveType::Void, span)),
+                        None => result
+                    }
+                }
+            }
+            TIRExprKind::PreOp(operator, operand) => match operator {
+                TIROperator::Inc | TIROperator::Dec => self.check_step_operand(operator, operand, span, body_types),
+                _ => {
+                    // "-128i8" is in range even though "128i8" is not
+                    let operand_type = match (&operator, &operand.kind) {
+                        (TIROperator::Minus, TIRExprKind::Num(value, suffix)) => {
+                            let operand_type = self.integer_literal_type(*value, *suffix, true, hint, operand.span);
+                            operand.type_info = operand_type.clone();
+                            operand_type
+                        }
+                        (TIROperator::Not, _) => self.check_expr(operand, None, body_types),
+                        _ => self.check_expr(operand, hint, body_types)
+                    }?;
+                    match primitive_of(&operand_type).and_then(|p| operator.unary_result(p)) {
+                        Some(result) => Some(primitive_type_info(result, span)),
+                        None => {
+                            self.errors.push(TypeCheckerError::InvalidOperands(span, operator.clone()));
+                            None
+                        }
+                    }
+                }
+            }
+            TIRExprKind::PostOp(operand, operator) => self.check_step_operand(operator, operand, span, body_types),
+
+            TIRExprKind::TypeAccess(_) => {
+                self.errors.push(TypeCheckerError::TypeUsedAsValue(span));
+                None
+            }
+            TIRExprKind::LocalAccess(slot) => self.local_type(*slot, hint, body_types),
+            TIRExprKind::FieldAccess(on, field_ref_index) => {
+                let on_type = self.check_expr(on, None, body_types);
+                self.field_type(on_type.as_ref(), *field_ref_index)
+            }
+            TIRExprKind::StaticFieldAccess(field_ref_index) => self.field_type(None, *field_ref_index),
+            TIRExprKind::MethodCall(on, method_ref_index, args) => {
+                let on_type = self.check_expr(on, None, body_types);
+                self.method_call_type(on_type.as_ref(), *method_ref_index, args, body_types)
+            }
+            TIRExprKind::StaticMethodCall(method_ref_index, args) => {
+                let method_ref_index = *method_ref_index;
+                if self.is_generic_static_method(method_ref_index) {
+                    self.generic_static_method_call_type(method_ref_index, args, false, span, body_types)
+                } else {
+                    self.method_call_type(None, method_ref_index, args, body_types)
+                }
+            }
+            TIRExprKind::OverloadedCall(on, method_ref_indexes, args) => {
+                let on_type = match on {
+                    Some(on) => self.check_expr(on, None, body_types),
+                    None => None
+                };
+                let (method_ref_index, type_info) = self.resolve_overloaded_call(on_type.as_ref(), method_ref_indexes, args, span, body_types)?;
+                if let TIRExprKind::OverloadedCall(on, _, args) = std::mem::replace(&mut expr.kind, TIRExprKind::Null) {
+                    expr.kind = match on {
+                        Some(on) if !self.method_ref_pool.method_refs[method_ref_index].is_static => TIRExprKind::MethodCall(on, method_ref_index, args),
+                        _ => TIRExprKind::StaticMethodCall(method_ref_index, args)
+                    };
+                }
+                type_info
+            }
+
+            TIRExprKind::MemberAccess(on, name) => {
+                let name = *name;
+                let on_type = self.check_expr(on, None, body_types)?;
+                let field_ref_index = match self.find_field(&on_type, name) {
+                    Some(field_ref_index) => field_ref_index,
+                    None => {
+                        self.errors.push(if self.find_methods(&on_type, name).is_empty() {
+                            TypeCheckerError::NoSuchField(span, name)
+                        } else {
+                            TypeCheckerError::MethodWithoutCall(span, name)
+                        });
+                        return None;
+                    }
+                };
+                let field_ref = &self.field_ref_pool.field_refs[field_ref_index];
+                if field_ref.is_static {
+                    self.errors.push(TypeCheckerError::StaticMemberAccessedOnInstance(span, name));
+                    return None;
+                }
+                if !self.type_ref_pool.is_accessible(field_ref.visibility, field_ref.associated_type_ref_index, body_types.type_ref_index) {
+                    self.errors.push(TypeCheckerError::InaccessibleField(span, name));
+                    return None;
+                }
+                if let TIRExprKind::MemberAccess(on, _) = std::mem::replace(&mut expr.kind, TIRExprKind::Null) {
+                    expr.kind = TIRExprKind::FieldAccess(on, field_ref_index);
+                }
+                self.field_type(Some(&on_type), field_ref_index)
+            }
+            TIRExprKind::Call(callee, args) => {
+                let arg_count = args.len();
+                let (on, name) = match &mut callee.kind {
+                    TIRExprKind::MemberAccess(on, name) => (on, *name),
+                    _ => {
+                        if self.check_expr(callee, None, body_types).is_some() {
+                            self.errors.push(TypeCheckerError::NotCallable(callee.span));
+                        }
+                        self.check_args(args, &vec![None; arg_count], body_types);
+                        return None;
+                    }
+                };
+                let on_type = self.check_expr(on, None, body_types);
+                let type_ref_index = body_types.type_ref_index;
+                // Same as in the lowerer, overloads with the right number of parameters that can be called from here
+                let method_ref_pool = self.method_ref_pool;
+                let method_ref_indexes = on_type.as_ref().and_then(|on_type| {
+                    let method_refs = &method_ref_pool.method_refs;
+                    let candidates: Vec<usize> = self.find_methods(on_type, name).into_iter()
+                        .filter(|m| method_refs[*m].parameters.len() == arg_count)
+                        .collect();
+                    if candidates.is_empty() {
+                        self.errors.push(TypeCheckerError::NoSuchMethod(span, name));
+                        return None;
+                    }
+                    let candidates: Vec<usize> = candidates.into_iter()
+                        .filter(|m| self.type_ref_pool.is_accessible(method_refs[*m].visibility, method_refs[*m].associated_type_ref_index, type_ref_index))
+                        .collect();
+                    if candidates.is_empty() {
+                        self.errors.push(TypeCheckerError::InaccessibleMethod(span, name));
+                        return None;
+                    }
+                    let candidates: Vec<usize> = candidates.into_iter().filter(|m| !method_refs[*m].is_static).collect();
+                    if candidates.is_empty() {
+                        self.errors.push(TypeCheckerError::StaticMemberAccessedOnInstance(span, name));
+                        return None;
+                    }
+                    Some(candidates)
+                });
+                let (method_ref_index, type_info) = match method_ref_indexes.as_deref() {
+                    Some([method_ref_index]) => (*method_ref_index, self.method_call_type(on_type.as_ref(), *method_ref_index, args, body_types)),
+                    Some(method_ref_indexes) => self.resolve_overloaded_call(on_type.as_ref(), method_ref_indexes, args, span, body_types)?,
+                    None => {
+                        self.check_args(args, &vec![None; arg_count], body_types);
+                        return None;
+                    }
+                };
+                if let TIRExprKind::Call(callee, args) = std::mem::replace(&mut expr.kind, TIRExprKind::Null) {
+                    if let TIRExprKind::MemberAccess(on, _) = callee.kind {
+                        expr.kind = TIRExprKind::MethodCall(on, method_ref_index, args);
+                    }
+                }
+                type_info
+            }
+            TIRExprKind::Indexing(array, index) => {
+                let array_type = self.check_expr(array, None, body_types);
+                let u64_type = primitive_type_info(PrimitiveType::U64, index.span);
+                self.check_expr_against(index, &u64_type, body_types);
+                let mut element_type = array_type?;
+                match &mut element_type.kind {
+                    TIRTypeInfoKind::TypeRef { array_dim, .. } |
+                    TIRTypeInfoKind::Generic { array_dim, .. } |
+                    TIRTypeInfoKind::Primitive { array_dim, .. } => if *array_dim == 0 {
+                        self.errors.push(TypeCheckerError::NotIndexable(span, element_type.clone()));
+                        return None;
+                    } else {
+                        *array_dim -= 1;
+                    }
+                }
+                element_type.span = span;
+                Some(element_type)
+            }
+            TIRExprKind::EnumConstruct(type_ref_index, variant_index, args) => {
+                let type_ref_pool = self.type_ref_pool;
+                let type_ref_index = *type_ref_index;
+                let fields = match &type_ref_pool.type_refs[type_ref_index].kind {
+                    TypeRefKind::Enum(enum_type_ref) => &enum_type_ref.variants[*variant_index].fields,
+                    _ => unreachable!()
+                };
+                let known_generics = self.hinted_generics(type_ref_index, hint);
+                let generics = self.check_generic_args(type_ref_index, fields, args, known_generics, false, span, body_types)?;
+                Some(TIRTypeInfo {
+                    kind: TIRTypeInfoKind::TypeRef {
+                        type_ref_index,
+                        generics,
+                        array_dim: 0
+                    },
+                    span
+                })
+            }
+            TIRExprKind::Range(start, end, _) => {
+                if let (Some(start_type), Some(end_type)) = self.check_operands(start, end, None, body_types) {
+                    if start_type != end_type {
+                        self.errors.push(TypeCheckerError::MismatchedTypes(end.span, start_type, end_type));
+                    }
+                }
+                self.errors.push(TypeCheckerError::RangeOutsideFor(span));
+                None
+            }
+            TIRExprKind::ArrayLength(array) => {
+                self.check_expr(array, None, body_types);
+                Some(primitive_type_info(PrimitiveType::U64, span))
+            }
+            TIRExprKind::New(type_info, constructor_index, args) => self.new_type(type_info, *constructor_index, args, false, hint, span, body_types),
+            TIRExprKind::OverloadedNew(type_info, constructor_indexes, args) => {
+                let constructor_index = self.resolve_overloaded_new(type_info, constructor_indexes, args, hint, span, body_types)?;
+                let type_info = self.new_type(type_info, constructor_index, args, true, hint, span, body_types);
+                if let TIRExprKind::OverloadedNew(new_type_info, _, args) = std::mem::replace(&mut expr.kind, TIRExprKind::Null) {
+                    expr.kind = TIRExprKind::New(new_type_info, constructor_index, args);
+                }
+                type_info
+            }
+
+            TIRExprKind::Block(block) => self.check_block(block, hint, body_types),
+            TIRExprKind::IfElse(condition, then_block, else_block) => {
+                self.check_condition(condition, body_types);
+                let then_type = self.check_block(then_block, hint, body_types);
+                let else_type = self.check_block(else_block, then_type.as_ref().filter(|t| !is_never(t)).or(hint), body_types);
+                self.unify(then_type, else_type, hint, else_block.span)
+            }
+            TIRExprKind::If(condition, block) => {
+                self.check_condition(condition, body_types);
+                let void = primitive_type_info(PrimitiveType::Void, span);
+                if let Some(block_type) = self.check_block(block, Some(&void), body_types) {
+                    if !self.is_assignable(&block_type, &void) {
+                        self.errors.push(TypeCheckerError::MismatchedTypes(block.span, void.clone(), block_type));
+                    }
+                }
+                Some(void)
+            }
+            TIRExprKind::Loop(label, block) => {
+                body_types.loops.push(LoopTypes {
+                    label: *label,
+                    break_type: hint.cloned(),
+                    broken: false
+                });
+                self.check_block(block, None, body_types);
+                let loop_types = body_types.loops.pop().unwrap();
+                if loop_types.broken {
+                    loop_types.break_type
+                } else {
+                    Some(primitive_type_info(PrimitiveType::Never, span))
+                }
+            }
+            TIRExprKind::While(label, condition, block) => {
+                // "break" in the condition exits this loop too
+                body_types.loops.push(LoopTypes {
+                    label: *label,
+                    break_type: Some(primitive_type_info(PrimitiveType::Void, span)),
+                    broken: true
+                });
+                self.check_condition(condition, body_types);
+                self.check_block(block, None, body_types);
+                body_types.loops.pop();
+                Some(primitive_type_info(PrimitiveType::Void, span))
+            }
+            TIRExprKind::For(..) => self.check_for(expr, body_types),
+            TIRExprKind::Match(scrutinee, arms) => {
+                let scrutinee_type = self.check_expr(scrutinee, None, body_types);
+                let mut match_type = Some(primitive_type_info(PrimitiveType::Never, span));
+                for arm in arms.iter_mut() {
+                    self.bind_pattern(&arm.pattern, scrutinee_type.as_ref(), body_types);
+                    if let Some(guard) = &mut arm.guard {
+                        self.check_condition(guard, body_types);
+                    }
+                    let arm_type = self.check_expr(&mut arm.body, match_type.as_ref().filter(|t| !is_never(t)).or(hint), body_types);
+                    match_type = self.unify(match_type, arm_type, hint, arm.body.span);
+                }
+                self.check_match_arms(arms, scrutinee_type.as_ref(), span);
+                match_type
+            }
+            TIRExprKind::Break(label, value) => {
+                // The lowerer made sure that the loop exists
+                let loop_index = match label {
+                    Some(label) => body_types.loops.iter().rposition(|l| l.label == Some(*label)),
+                    None => body_types.loops.len().checked_sub(1)
+                }.unwrap();
+                let value_type = match value {
+                    Some(value) => {
+                        let break_type = body_types.loops[loop_index].break_type.clone();
+                        match &break_type {
+                            Some(break_type) => {
+                                self.check_expr_against(value, break_type, body_types);
+                                Some(break_type.clone())
+                            }
+                            None => self.check_expr(value, None, body_types)
+                        }
+                    }
+                    None => Some(primitive_type_info(PrimitiveType::Void, span))
+                };
+                let loop_types = &body_types.loops[loop_index];
+                match (&loop_types.break_type, value_type) {
+                    (Some(break_type), Some(value_type)) if value.is_none() && !self.is_assignable(&value_type, break_type) =>
+                        self.errors.push(TypeCheckerError::MismatchedTypes(span, break_type.clone(), value_type)),
+                    (None, value_type) => body_types.loops[loop_index].break_type = value_type,
+                    _ => {}
+                }
+                body_types.loops[loop_index].broken = true;
+                Some(primitive_type_info(PrimitiveType::Never, span))
+            }
+            TIRExprKind::Continue(_) => Some(primitive_type_info(PrimitiveType::Never, span)),
+            TIRExprKind::Return(value) => {
+                if let (Some(value), Some(return_type)) = (value, body_types.return_type.clone()) {
+                    self.check_expr_against(value, &return_type, body_types);
+                }
+                Some(primitive_type_info(PrimitiveType::Never, span))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tir::type_checker::TypeCheckerError;
+    use crate::testing::type_errors;
+
+    #[test]
+    fn branches_that_never_complete_take_the_type_of_the_other() {
+        assert!(type_errors("mod a; class A { fn f(flag: bool) -> u64 { if flag { return 1; } else { 2 } } }").is_empty());
+        let errors = type_errors("mod a; class A { fn f(flag: bool) -> u64 { if flag { 1 } else { true } } }");
+        assert!(matches!(errors[..], [TypeCheckerError::MismatchedBranches(..)]), "{:?}", errors);
+    }
+
+    #[test]
+    fn string_literals_need_the_standard_string() {
+        let errors = type_errors("mod a; class A { fn greeting() { \"hello\"; } }");
+        assert!(matches!(errors[..], [TypeCheckerError::NoStringType(_)]));
+        assert!(type_errors("mod oolang; class String { } class A { fn greeting() -> String { \"hello\" } }").is_empty());
+    }
+
+    #[test]
+    fn ranges_and_types_are_not_values() {
+        let errors = type_errors("mod a; class A { fn f() { let r = 0..10; } }");
+        assert!(matches!(errors[..], [TypeCheckerError::RangeOutsideFor(_)]), "{:?}", errors);
+        let errors = type_errors("mod a; class A { fn f() { let t = A; } }");
+        assert!(matches!(errors[..], [TypeCheckerError::TypeUsedAsValue(_)]), "{:?}", errors);
+    }
+
+    #[test]
+    fn members_of_typed_expressions_respect_visibility() {
+        // The class of "b" is only known once its initializer is typed
+        let source = "mod a; class B { priv secret: u64; priv fn hidden() { } } class A { fn f() { let b = new B(); BODY } }";
+        let source = source.replace("BODY", "b.secret; b.hidden();");
+        let errors = type_errors(&source);
+        assert!(matches!(errors[..], [TypeCheckerError::InaccessibleField(_, "secret"), TypeCheckerError::InaccessibleMethod(_, "hidden")]), "{:?}", errors);
+    }
+
+    #[test]
+    fn operands_that_are_both_untyped_need_annotations() {
+        for body in ["if null == null { }", "null != null;"] {
+            let source = "mod a; class A { fn f() { BODY } }".replace("BODY", body);
+            let errors = type_errors(&source);
+            assert!(matches!(errors[..], [TypeCheckerError::TypeAnnotationsNeeded(_)]), "{:?}", errors);
+        }
+        // Either operand can type the other one
+        let source = "mod a; class A { fn f(a: A) -> bool { let b = 1; let c = 2u8; b == c && null == a && a != null } }";
+        assert!(type_errors(source).is_empty());
+    }
+}
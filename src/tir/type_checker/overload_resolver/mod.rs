@@ -0,0 +1,237 @@
+use crate::tir::type_checker::{TypeChecker, TypeCheckerError, BodyTypes, primitive_of, is_never, mentions_generics_of};
+use crate::tir::type_checker::inference::LiteralKind;
+use crate::tir::{TIRExpr, TIRTypeInfo};
+use crate::type_ref_pool::{TypeRefKind, type_ref_index_of};
+use crate::reporting::TokenSpan;
+
+// The lowerer leaves every overload with as many parameters as there are arguments, they are picked between here.
+// Arguments are typed on their own first, except for literals which take the type of the parameter, like they would
+// take the type of the other operand (an unsuffixed "1" is passed as it is to i64, and widened to the others). The overloads
+// that take every argument as it is come first, then the ones that need numeric widening, then the ones that need
+// subtyping. Among the first of these that isn't empty, the most specific overload is called: the one whose
+// parameters could all be passed to the parameters of each of the others.
+
+// How an argument is passed to its parameter, from the best to the worst
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Conversion {
+    Exact,
+    Widening,
+    Subtyping
+}
+
+// Argument typed before the overload is picked
+pub enum ArgType {
+    // Literals, and locals initialized with one, get their type from the parameter of the picked overload
+    Flexible(LiteralKind),
+    Typed(Option<TIRTypeInfo>)
+}
+
+impl<'a, 'b> TypeChecker<'a, 'b> {
+    pub fn conversion(&self, found: &TIRTypeInfo, expected: &TIRTypeInfo) -> Option<Conversion> {
+        if found == expected || is_never(found) {
+            return Some(Conversion::Exact);
+        }
+        match (primitive_of(found), primitive_of(expected)) {
+            (Some(found), Some(expected)) if found.widens_to(expected) => Some(Conversion::Widening),
+            _ if self.is_assignable(found, expected) => Some(Conversion::Subtyping),
+            _ => None
+        }
+    }
+
+    // Values can be given to parameters of a type they widen to
+    pub fn is_passable(&self, found: &TIRTypeInfo, expected: &TIRTypeInfo) -> bool {
+        self.conversion(found, expected).is_some()
+    }
+
+    // Parameters that aren't known (they mention generics that aren't) and arguments that couldn't be typed fit anything
+    fn arg_conversion(&self, arg_type: &ArgType, parameter: Option<&TIRTypeInfo>) -> Option<Conversion> {
+        let parameter = match parameter {
+            Some(parameter) => parameter,
+            None => return Some(Conversion::Subtyping)
+        };
+        match arg_type {
+            ArgType::Flexible(LiteralKind::Null) => Some(Conversion::Subtyping).filter(|_| parameter.is_reference()),
+            ArgType::Flexible(kind) => {
+                let literal_type = self.literal_type(*kind, Some(parameter), parameter.span)?;
+                if literal_type != *parameter {
+                    None
+                } else if self.literal_type(*kind, None, parameter.span).as_ref() == Some(parameter) {
+                    Some(Conversion::Exact)
+                } else {
+                    Some(Conversion::Widening)
+                }
+            }
+            ArgType::Typed(Some(arg_type)) => self.conversion(arg_type, parameter),
+            ArgType::Typed(None) => Some(Conversion::Exact)
+        }
+    }
+
+    // The worst conversion the overload needs for the arguments, None if it can't take them
+    fn overload_conversion(&self, parameters: &[Option<TIRTypeInfo>], arg_types: &[ArgType]) -> Option<Conversion> {
+        parameters.iter().zip(arg_types)
+            .map(|(parameter, arg_type)| self.arg_conversion(arg_type, parameter.as_ref()))
+            .try_fold(Conversion::Exact, |worst, conversion| conversion.map(|conversion| worst.max(conversion)))
+    }
+
+    fn is_more_specific(&self, parameters: &[Option<TIRTypeInfo>], other_parameters: &[Option<TIRTypeInfo>]) -> bool {
+        parameters.iter().zip(other_parameters).all(|(parameter, other_parameter)| match (parameter, other_parameter) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(parameter), Some(other_parameter)) => self.is_passable(parameter, other_parameter)
+        })
+    }
+
+    fn type_overloaded_args(&mut self, args: &mut [TIRExpr<'a>], body_types: &mut BodyTypes<'a>) -> Vec<ArgType> {
+        args.iter_mut().map(|arg| match self.flexible_kind(arg, body_types) {
+            Some(kind) => ArgType::Flexible(kind),
+            None => ArgType::Typed(self.check_expr(arg, None, body_types))
+        }).collect()
+    }
+
+    // Checks the arguments that weren't typed before picking the overload against its parameters
+    pub fn check_flexible_args(&mut self, args: &mut [TIRExpr<'a>], arg_types: &[ArgType], parameters: &[Option<TIRTypeInfo>], body_types: &mut BodyTypes<'a>) {
+        for ((arg, arg_type), parameter) in args.iter_mut().zip(arg_types).zip(parameters) {
+            if let ArgType::Flexible(_) = arg_type {
+                match parameter {
+                    Some(parameter) => self.check_arg_against(arg, parameter, body_types),
+                    None => {
+                        self.check_expr(arg, None, body_types);
+                    }
+                }
+            }
+        }
+    }
+
+    // Types the arguments and evaluates to the position of the overload they are for, given the parameters of each
+    // overload as seen from the call. The error is made from the positions of the overloads that fit equally well,
+    // there are none if no overload fits.
+    pub fn pick_overload(&mut self, parameters: &[Vec<Option<TIRTypeInfo>>], args: &mut [TIRExpr<'a>], body_types: &mut BodyTypes<'a>, error: impl FnOnce(Vec<usize>) -> TypeCheckerError<'a>) -> Option<(usize, Vec<ArgType>)> {
+        let arg_types = self.type_overloaded_args(args, body_types);
+        let conversions: Vec<Option<Conversion>> = parameters.iter().map(|p| self.overload_conversion(p, &arg_types)).collect();
+        let applicable: Vec<usize> = match conversions.iter().flatten().min() {
+            Some(best) => (0..parameters.len()).filter(|i| conversions[*i] == Some(*best)).collect(),
+            None => vec![]
+        };
+        let most_specific: Vec<usize> = applicable.iter().copied()
+            .filter(|i| applicable.iter().all(|j| i == j || self.is_more_specific(&parameters[*i], &parameters[*j])))
+            .collect();
+        match most_specific[..] {
+            [position] => Some((position, arg_types)),
+            _ => {
+                // Arguments that couldn't be typed already have their error and fit every overload
+                if applicable.len() < 2 || arg_types.iter().all(|arg_type| !matches!(arg_type, ArgType::Typed(None))) {
+                    self.errors.push(error(applicable));
+                }
+                self.check_flexible_args(args, &arg_types, &vec![None; args.len()], body_types);
+                None
+            }
+        }
+    }
+
+    // Evaluates to the method ref index of the overload that is called and the type of the call
+    pub fn resolve_overloaded_call(&mut self, on_type: Option<&TIRTypeInfo>, method_ref_indexes: &[usize], args: &mut [TIRExpr<'a>], span: TokenSpan, body_types: &mut BodyTypes<'a>) -> Option<(usize, Option<TIRTypeInfo>)> {
+        let method_ref_pool = self.method_ref_pool;
+        let method_refs = &method_ref_pool.method_refs;
+        // Static overloads don't see the generics of what they would be called on
+        let parameters: Vec<Vec<Option<TIRTypeInfo>>> = method_ref_indexes.iter()
+            .map(|m| self.method_parameters(on_type.filter(|_| !method_refs[*m].is_static), *m))
+            .collect();
+        let name = method_refs[method_ref_indexes[0]].name;
+        let (position, arg_types) = self.pick_overload(&parameters, args, body_types, |equally_good| if equally_good.is_empty() {
+            TypeCheckerError::NoApplicableMethod(span, name)
+        } else {
+            TypeCheckerError::AmbiguousMethodCall(span, name, equally_good.iter().map(|i| method_ref_indexes[*i]).collect())
+        })?;
+
+        let method_ref_index = method_ref_indexes[position];
+        let method_ref = &method_refs[method_ref_index];
+        let type_info = if self.is_generic_static_method(method_ref_index) {
+            self.generic_static_method_call_type(method_ref_index, args, true, span, body_types)
+        } else {
+            self.check_flexible_args(args, &arg_types, &parameters[position], body_types);
+            let on_type = on_type.filter(|_| !method_ref.is_static);
+            self.member_type(&method_ref.return_type, method_ref.associated_type_ref_index, on_type)
+        };
+        Some((method_ref_index, type_info))
+    }
+
+    // Evaluates to the index of the constructor that is called, its arguments are checked with the generics of "new"
+    pub fn resolve_overloaded_new(&mut self, type_info: &TIRTypeInfo, constructor_indexes: &[usize], args: &mut [TIRExpr<'a>], hint: Option<&TIRTypeInfo>, span: TokenSpan, body_types: &mut BodyTypes<'a>) -> Option<usize> {
+        let type_ref_pool = self.type_ref_pool;
+        let type_ref_index = type_ref_index_of(type_info).unwrap();
+        let constructors = match &type_ref_pool.type_refs[type_ref_index].kind {
+            TypeRefKind::Class(class_type_ref) => &class_type_ref.constructors,
+            _ => unreachable!()
+        };
+        let known_generics = self.known_new_generics(type_info, hint);
+        let parameters: Vec<Vec<Option<TIRTypeInfo>>> = constructor_indexes.iter().map(|constructor_index| {
+            constructors[*constructor_index].iter().map(|p| match &known_generics {
+                Some(generics) => Some(type_ref_pool.substitute_generics(p, type_ref_index, generics)),
+                None if mentions_generics_of(p, type_ref_index) => None,
+                None => Some(p.clone())
+            }).collect()
+        }).collect();
+        let (position, _) = self.pick_overload(&parameters, args, body_types, |equally_good| if equally_good.is_empty() {
+            TypeCheckerError::NoApplicableConstructor(span)
+        } else {
+            TypeCheckerError::AmbiguousConstructorCall(span, equally_good.iter().map(|i| constructor_indexes[*i]).collect())
+        })?;
+        Some(constructor_indexes[position])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tir::type_checker::TypeCheckerError;
+    use crate::testing::type_errors;
+
+    // The overloads return different types, so that the checked return type tells which one was picked
+    const OVERLOADS: &str = "
+        mod a;
+        class Animal { }
+        class Dog: Animal { }
+        class Puppy: Dog { }
+        class Pair { pub Pair(a: Animal, d: Dog) { } pub Pair(d: Dog, a: Animal) { } }
+        class A {
+            fn pick(x: u8) -> bool { true }
+            fn pick(x: u64) -> u64 { x }
+            static fn make(x: u8) -> bool { true }
+            static fn make(x: u64) -> u64 { x }
+            fn take(a: Animal) -> u64 { 1 }
+            fn take(d: Dog) -> bool { true }
+            fn both(a: Animal, d: Dog) { }
+            fn both(d: Dog, a: Animal) { }
+            fn f(dog: Dog, puppy: Puppy) { BODY }
+        }
+    ";
+
+    #[test]
+    fn the_closest_overload_is_picked() {
+        for body in [
+            "let r: bool = pick(1u8);",
+            "let r: u64 = pick(1u64);",
+            "let r: u64 = pick(1u16);",
+            "let r: u64 = A::make(1u32);",
+            "let r: bool = take(dog);",
+            "let r: bool = take(puppy);",
+            "let r: u64 = take(new Animal());"
+        ] {
+            let source = OVERLOADS.replace("BODY", body);
+            let errors = type_errors(&source);
+            assert!(errors.is_empty(), "{}: {:?}", body, errors);
+        }
+    }
+
+    #[test]
+    fn ambiguous_calls_list_their_candidates() {
+        let source = OVERLOADS.replace("BODY", "both(dog, dog);");
+        let errors = type_errors(&source);
+        assert!(matches!(errors[..], [TypeCheckerError::AmbiguousMethodCall(_, "both", ref candidates)] if candidates.len() == 2), "{:?}", errors);
+        let source = OVERLOADS.replace("BODY", "new Pair(dog, puppy);");
+        let errors = type_errors(&source);
+        assert!(matches!(errors[..], [TypeCheckerError::AmbiguousConstructorCall(_, ref candidates)] if candidates.len() == 2), "{:?}", errors);
+        let source = OVERLOADS.replace("BODY", "pick(true);");
+        let errors = type_errors(&source);
+        assert!(matches!(errors[..], [TypeCheckerError::NoApplicableMethod(_, "pick")]), "{:?}", errors);
+    }
+}
@@ -1,5 +1,14 @@
 use std::collections::HashMap;
-use crate::tir::TIRTypeInfo;
+use crate::tir::{TIRTypeInfo, TIRTypeInfoKind};
+use crate::ast::ASTVisibility;
+
+// Type ref the type info points to, if it points to one (arrays don't count)
+pub fn type_ref_index_of(type_info: &TIRTypeInfo) -> Option<usize> {
+    match &type_info.kind {
+        TIRTypeInfoKind::TypeRef { type_ref_index, array_dim: 0, .. } => Some(*type_ref_index),
+        _ => None
+    }
+}
 
 #[derive(Debug)]
 pub struct TypeRefGeneric<'a> {
@@ -10,19 +19,54 @@ pub struct TypeRefGeneric<'a> {
 #[derive(Debug)]
 pub struct ClassTypeRef {
     pub super_class: Option<TIRTypeInfo>,
+    pub impls: Vec<TIRTypeInfo>,
+    // Parameters of every constructor, the index of a constructor is its position here
+    pub constructors: Vec<Vec<TIRTypeInfo>>,
+    // Visibility of every constructor, in the same order
+    pub constructor_visibilities: Vec<ASTVisibility>,
 
     pub is_abstract: bool
 }
 
 #[derive(Debug)]
-pub enum TypeRefKind {
-    Class(ClassTypeRef)
+pub struct InterfaceTypeRef {
+    pub super_interfaces: Vec<TIRTypeInfo>
+}
+
+#[derive(Debug)]
+pub enum EnumVariantRefKind<'a> {
+    Unit,
+    Tuple,
+    // Names of the fields
+    Struct(Vec<&'a str>)
+}
+
+#[derive(Debug)]
+pub struct EnumVariantRef<'a> {
+    pub name: &'a str,
+    pub kind: EnumVariantRefKind<'a>,
+    pub fields: Vec<TIRTypeInfo>
+}
+
+#[derive(Debug)]
+pub struct EnumTypeRef<'a> {
+    // The index of a variant is its discriminant
+    pub variants: Vec<EnumVariantRef<'a>>,
+    pub name_to_variant_index: HashMap<&'a str, usize>
+}
+
+#[derive(Debug)]
+pub enum TypeRefKind<'a> {
+    Class(ClassTypeRef),
+    Interface(InterfaceTypeRef),
+    Enum(EnumTypeRef<'a>)
 }
 
 #[derive(Debug)]
 pub struct TypeRef<'a> {
     pub full_path: Vec<&'a str>,
-    pub kind: TypeRefKind,
+    pub visibility: ASTVisibility,
+    pub kind: TypeRefKind<'a>,
     pub generics: Vec<TypeRefGeneric<'a>>,
     pub name_to_generic_index: HashMap<&'a str, usize>
 }
@@ -43,13 +87,203 @@ impl<'a> TypeRefPool<'a> {
         }
     }
 
+    // Module the type is declared in
+    pub fn module_of(&self, type_ref_index: usize) -> &[&'a str] {
+        let full_path = &self.type_refs[type_ref_index].full_path;
+        &full_path[..full_path.len() - 1]
+    }
+
+    // Types aren't nested, so private types are visible to their whole module like module ones
+    pub fn is_type_accessible(&self, type_ref_index: usize, from_module: &[&str]) -> bool {
+        match self.type_refs[type_ref_index].visibility {
+            ASTVisibility::Public => true,
+            ASTVisibility::Module | ASTVisibility::Private => self.module_of(type_ref_index) == from_module
+        }
+    }
+
+    // Whether a member with that visibility declared in a type can be used from code in the other type
+    pub fn is_accessible(&self, visibility: ASTVisibility, declaring_type_ref_index: usize, from_type_ref_index: usize) -> bool {
+        match visibility {
+            ASTVisibility::Public => true,
+            ASTVisibility::Module => self.module_of(declaring_type_ref_index) == self.module_of(from_type_ref_index),
+            ASTVisibility::Private => declaring_type_ref_index == from_type_ref_index
+        }
+    }
+
+    // Replaces the generics of the type ref with the given type infos (e.g. T in Wrapper<T> with u64 for Wrapper<u64>)
+    pub fn substitute_generics(&self, type_info: &TIRTypeInfo, type_ref_index: usize, generics: &[TIRTypeInfo]) -> TIRTypeInfo {
+        let kind = match &type_info.kind {
+            TIRTypeInfoKind::Generic { type_ref_index: generic_type_ref_index, generic_index, array_dim } if *generic_type_ref_index == type_ref_index => {
+                let mut kind = generics[*generic_index].kind.clone();
+                match &mut kind {
+                    TIRTypeInfoKind::TypeRef { array_dim: substituted_array_dim, .. } |
+                    TIRTypeInfoKind::Generic { array_dim: substituted_array_dim, .. } |
+                    TIRTypeInfoKind::Primitive { array_dim: substituted_array_dim, .. } => *substituted_array_dim += *array_dim
+                }
+                kind
+            }
+            TIRTypeInfoKind::TypeRef { type_ref_index: inner_type_ref_index, generics: inner_generics, array_dim } => TIRTypeInfoKind::TypeRef {
+                type_ref_index: *inner_type_ref_index,
+                generics: inner_generics.iter().map(|g| self.substitute_generics(g, type_ref_index, generics)).collect(),
+                array_dim: *array_dim
+            },
+            kind => kind.clone()
+        };
+
+        TIRTypeInfo {
+            kind,
+            span: type_info.span
+        }
+    }
+
+    // Type refs of the super class, interface impls and super interfaces of the type
+    pub fn direct_supers(&self, type_ref_index: usize) -> Vec<usize> {
+        let supers: Vec<&TIRTypeInfo> = match &self.type_refs[type_ref_index].kind {
+            TypeRefKind::Class(class_type_ref) => class_type_ref.super_class.iter().chain(class_type_ref.impls.iter()).collect(),
+            TypeRefKind::Interface(interface_type_ref) => interface_type_ref.super_interfaces.iter().collect(),
+            TypeRefKind::Enum(_) => vec![]
+        };
+        supers.into_iter().filter_map(type_ref_index_of).collect()
+    }
+
+    // The type and all of its supers, closest first
+    pub fn type_hierarchy(&self, type_ref_index: usize) -> Vec<usize> {
+        let mut hierarchy = vec![type_ref_index];
+        let mut i = 0;
+        while i < hierarchy.len() {
+            for super_type_ref_index in self.direct_supers(hierarchy[i]) {
+                if !hierarchy.contains(&super_type_ref_index) {
+                    hierarchy.push(super_type_ref_index);
+                }
+            }
+            i += 1;
+        }
+        hierarchy
+    }
+
+    // Direct supers of the type, with the generics it gives them (Wrapper<u64> for SimpleWrapper<u64> with SimpleWrapper<T> impl Wrapper<T>)
+    pub fn super_types(&self, type_info: &TIRTypeInfo) -> Vec<TIRTypeInfo> {
+        match &type_info.kind {
+            TIRTypeInfoKind::TypeRef { type_ref_index, generics, array_dim: 0 } => {
+                let supers: Vec<&TIRTypeInfo> = match &self.type_refs[*type_ref_index].kind {
+                    TypeRefKind::Class(class_type_ref) => class_type_ref.super_class.iter().chain(class_type_ref.impls.iter()).collect(),
+                    TypeRefKind::Interface(interface_type_ref) => interface_type_ref.super_interfaces.iter().collect(),
+                    TypeRefKind::Enum(_) => vec![]
+                };
+                supers.into_iter().map(|super_type| if generics.is_empty() {
+                    super_type.clone()
+                } else {
+                    self.substitute_generics(super_type, *type_ref_index, generics)
+                }).collect()
+            }
+            // Generics can be used as any of their requirements
+            TIRTypeInfoKind::Generic { type_ref_index, generic_index, array_dim: 0 } =>
+                self.type_refs[*type_ref_index].generics[*generic_index].super_requirements.clone(),
+            _ => vec![]
+        }
+    }
+
+    // The type seen as one of its supers (or as itself), with the generics it gives to that super
+    pub fn as_super(&self, type_info: &TIRTypeInfo, super_type_ref_index: usize) -> Option<TIRTypeInfo> {
+        let mut queue = vec![type_info.clone()];
+        // Type refs and generics already walked through, so that this ends even if the supers have cycles
+        let mut visited: Vec<(usize, Option<usize>)> = vec![];
+        let mut i = 0;
+        while i < queue.len() {
+            let key = match &queue[i].kind {
+                TIRTypeInfoKind::TypeRef { type_ref_index, array_dim: 0, .. } if *type_ref_index == super_type_ref_index => return Some(queue[i].clone()),
+                TIRTypeInfoKind::TypeRef { type_ref_index, .. } => (*type_ref_index, None),
+                TIRTypeInfoKind::Generic { type_ref_index, generic_index, .. } => (*type_ref_index, Some(*generic_index)),
+                TIRTypeInfoKind::Primitive { .. } => return None
+            };
+            if !visited.contains(&key) {
+                visited.push(key);
+                let super_types = self.super_types(&queue[i]);
+                queue.extend(super_types);
+            }
+            i += 1;
+        }
+        None
+    }
+
+    // Whether a value of the first type can be used where a value of the second is expected.
+    // Generics of generic types have to be the same, arrays of references can be used as arrays of their supers.
     pub fn check_assignable_to(&self, to_assign: &TIRTypeInfo, type_info: &TIRTypeInfo) -> bool {
         if to_assign == type_info {
-            true
+            return true;
+        }
+        match (to_assign.array_dim(), &type_info.kind) {
+            (0, TIRTypeInfoKind::TypeRef { type_ref_index, array_dim: 0, .. }) => match self.as_super(to_assign, *type_ref_index) {
+                Some(super_type) => super_type == *type_info,
+                None => false
+            },
+            (array_dim, _) if array_dim > 0 && array_dim == type_info.array_dim() && to_assign.is_reference_array() => match (to_assign.remove_array_dim(array_dim), type_info.remove_array_dim(array_dim)) {
+                (Some(to_assign), Some(type_info)) => self.check_assignable_to(&to_assign, &type_info),
+                _ => false
+            },
+            _ => false
         }
-        else {
-            // TODO : Check super classes and assignment to generics
-            todo!()
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::tir::ast_lowerer::ASTtoTIRLowererError;
+    use crate::tir::type_checker::TypeCheckerError;
+    use crate::testing::{lowering_error, type_errors};
+
+    const HIERARCHY: &str = "
+        mod a;
+        inter Named { }
+        class Animal impl Named { }
+        class Dog: Animal { }
+        class Holder<T> { }
+        class DogHolder: Holder<Dog> { }
+        class Kennel<T: Animal> { }
+    ";
+
+    fn assignment_errors(to_assign: &str, type_info: &str) -> Vec<String> {
+        let source = format!("{} class A {{ fn f(value: {}) {{ let v: {} = value; }} }}", HIERARCHY, to_assign, type_info);
+        type_errors(&source).iter().map(|error| format!("{:?}", error)).collect()
+    }
+
+    #[test]
+    fn values_can_be_used_as_their_supers() {
+        for (to_assign, type_info) in [
+            ("Dog", "Animal"),
+            ("Dog", "Named"),
+            ("DogHolder", "Holder<Dog>"),
+            ("Dog[]", "Animal[]"),
+            ("Dog[][]", "Named[][]"),
+            ("Kennel<Dog>", "Kennel<Dog>")
+        ] {
+            assert!(assignment_errors(to_assign, type_info).is_empty(), "{} as {}", to_assign, type_info);
+        }
+        for (to_assign, type_info) in [
+            ("Animal", "Dog"),
+            ("DogHolder", "Holder<Animal>"),
+            ("Holder<Dog>", "Holder<Animal>"),
+            ("u8[]", "u64[]"),
+            ("Dog[]", "Animal")
+        ] {
+            let errors = assignment_errors(to_assign, type_info);
+            assert!(errors.len() == 1 && errors[0].starts_with("MismatchedTypes"), "{} as {}: {:?}", to_assign, type_info, errors);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn null_is_only_a_reference() {
+        assert!(type_errors("mod a; class A { fn f() { let a: A = null; let b: A[] = null; } }").is_empty());
+        let errors = type_errors("mod a; class A { fn f() { let a: u64 = null; } }");
+        assert!(matches!(errors[..], [TypeCheckerError::NullForPrimitive(..)]), "{:?}", errors);
+    }
+
+    #[test]
+    fn generics_meet_their_bounds() {
+        for member in ["fn f(kennel: Kennel<Named>) { }", "kennel: Kennel<Named>;", "fn f() { let kennel: Kennel<Named> = null; }"] {
+            let source = format!("{} class A {{ {} }}", HIERARCHY, member);
+            assert!(matches!(lowering_error(&source), ASTtoTIRLowererError::TypeMismatch(_)), "{}", member);
+        }
+        let source = format!("{} class A<T: Dog> {{ fn f(kennel: Kennel<T>) {{ }} }}", HIERARCHY);
+        assert!(type_errors(&source).is_empty());
+    }
+}